@@ -0,0 +1,139 @@
+use indexmap::{IndexMap, IndexSet};
+
+use crate::evaluator::{assignment, Evaluator, EvaluatorError};
+use crate::evaluator_result::Classification;
+
+impl Evaluator {
+    /// the values of the whole expression indexed by row, see `evaluator::assignment` for the order.
+    pub fn truth_vector(&self) -> Result<Vec<bool>, EvaluatorError> {
+        let variables = self.variables();
+        let rows: u64 = 1 << variables.len();
+        (0..rows).map(|i| self.value(&assignment(&variables, i))).collect()
+    }
+
+    pub fn classify(&self) -> Result<Classification, EvaluatorError> {
+        let vector = self.truth_vector()?;
+        Ok(if vector.iter().all(|v| *v) {
+            Classification::Tautology
+        } else if vector.iter().all(|v| !*v) {
+            Classification::Contradiction
+        } else {
+            Classification::Contingency
+        })
+    }
+
+    /// an assignment that makes the expression true, if there is one.
+    pub fn satisfying_assignment(&self) -> Result<Option<IndexMap<String, bool>>, EvaluatorError> {
+        self.find_assignment(true)
+    }
+
+    /// an assignment that makes the expression false, if there is one.
+    pub fn counterexample(&self) -> Result<Option<IndexMap<String, bool>>, EvaluatorError> {
+        self.find_assignment(false)
+    }
+
+    pub fn is_satisfiable(&self) -> Result<bool, EvaluatorError> {
+        Ok(self.satisfying_assignment()?.is_some())
+    }
+
+    pub fn is_tautology(&self) -> Result<bool, EvaluatorError> {
+        Ok(self.counterexample()?.is_none())
+    }
+
+    /// compares two expressions over the union of their variables. Returns `None` when they are
+    /// equivalent, otherwise an assignment on which they differ.
+    pub fn difference(&self, other: &Evaluator) -> Result<Option<IndexMap<String, bool>>, EvaluatorError> {
+        let variables: Vec<String> = self
+            .variables()
+            .into_iter()
+            .chain(other.variables())
+            .collect::<IndexSet<_>>()
+            .into_iter()
+            .collect();
+        let rows: u64 = 1 << variables.len();
+        for i in (0..rows).rev() {
+            let row = assignment(&variables, i);
+            if self.value(&row)? != other.value(&row)? {
+                return Ok(Some(row));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn is_equivalent(&self, other: &Evaluator) -> Result<bool, EvaluatorError> {
+        Ok(self.difference(other)?.is_none())
+    }
+
+    fn find_assignment(&self, value: bool) -> Result<Option<IndexMap<String, bool>>, EvaluatorError> {
+        let variables = self.variables();
+        let rows: u64 = 1 << variables.len();
+        for i in (0..rows).rev() {
+            let row = assignment(&variables, i);
+            if self.value(&row)? == value {
+                return Ok(Some(row));
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tokenizer::Tokens;
+
+    use super::*;
+
+    fn evaluator(s: &str) -> Evaluator {
+        Evaluator::new(Tokens::from_text(s)).unwrap()
+    }
+
+    #[test]
+    fn classify() {
+        assert_eq!(evaluator("p or not p").classify().unwrap(), Classification::Tautology);
+        assert_eq!(evaluator("p and not p").classify().unwrap(), Classification::Contradiction);
+        assert_eq!(evaluator("p -> q").classify().unwrap(), Classification::Contingency);
+        assert_eq!(evaluator("true").classify().unwrap(), Classification::Tautology);
+    }
+
+    #[test]
+    fn sat() {
+        let row = evaluator("p and not q").satisfying_assignment().unwrap().unwrap();
+        assert_eq!(row.get("p"), Some(&true));
+        assert_eq!(row.get("q"), Some(&false));
+        assert!(!evaluator("p and not p").is_satisfiable().unwrap());
+    }
+
+    #[test]
+    fn equivalence() {
+        assert!(evaluator("p -> q").is_equivalent(&evaluator("not p or q")).unwrap());
+        assert!(evaluator("not (p and q)").is_equivalent(&evaluator("not p or not q")).unwrap());
+        let row = evaluator("p -> q").difference(&evaluator("q -> p")).unwrap().unwrap();
+        assert_ne!(row.get("p"), row.get("q"));
+        assert!(!evaluator("p").is_equivalent(&evaluator("p and r")).unwrap());
+    }
+
+    #[test]
+    fn derived_connectives() {
+        assert!(evaluator("p nand q").is_equivalent(&evaluator("not (p and q)")).unwrap());
+        assert!(evaluator("p nor q").is_equivalent(&evaluator("not (p or q)")).unwrap());
+        assert!(evaluator("p <- q").is_equivalent(&evaluator("q -> p")).unwrap());
+        assert!(evaluator("p ↛ q").is_equivalent(&evaluator("not (p -> q)")).unwrap());
+        assert_eq!(evaluator("p ↑ p").classify().unwrap(), Classification::Contingency);
+        assert_eq!(evaluator("(p ↓ q) and p").classify().unwrap(), Classification::Contradiction);
+        assert_eq!(evaluator("p nand q").simplify().unwrap().to_string(), "¬p ∨ ¬q");
+    }
+
+    #[test]
+    fn double_negation() {
+        assert!(evaluator("not not p").is_equivalent(&evaluator("p")).unwrap());
+        assert!(evaluator("q and ¬¬p").is_equivalent(&evaluator("p and q")).unwrap());
+    }
+
+    #[test]
+    fn syntax_errors() {
+        assert!(Evaluator::new(Tokens::from_text("p and")).is_err());
+        assert!(Evaluator::new(Tokens::from_text("p q")).is_err());
+        assert!(Evaluator::new(Tokens::from_text("(and p)")).is_err());
+        assert!(Evaluator::new(Tokens::from_text("p ⇒∼ Q ∨ R")).is_ok());
+    }
+}
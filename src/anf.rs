@@ -0,0 +1,153 @@
+use std::fmt;
+
+use crate::completeness::BooleanFunction;
+use crate::evaluator::{Evaluator, EvaluatorError};
+use crate::synthesis::Specification;
+use crate::tokenizer::{Token, Tokens};
+
+/// The algebraic normal form (Zhegalkin polynomial) of a function: a xor of products of
+/// variables. A monomial is a mask of its variables, the first variable being the most
+/// significant bit as in the rows of a truth table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Anf {
+    variables: Vec<String>,
+    coefficients: Vec<bool>,
+}
+
+/// the Möbius transform of a truth table: `coefficients[m]` is the xor of the table on the rows
+/// that are subsets of `m`. The transform is its own inverse.
+pub fn mobius(table: &[bool]) -> Vec<bool> {
+    let mut coefficients = table.to_vec();
+    let mut step = 1;
+    while step < coefficients.len() {
+        for i in 0..coefficients.len() {
+            if i & step != 0 {
+                coefficients[i] ^= coefficients[i ^ step];
+            }
+        }
+        step <<= 1;
+    }
+    coefficients
+}
+
+impl Anf {
+    pub fn from_specification(specification: &Specification) -> Self {
+        Anf {
+            variables: specification.variables().to_vec(),
+            coefficients: mobius(specification.table()),
+        }
+    }
+
+    /// the algebraic normal form of a function given by its table alone, over the variables
+    /// `x1`, `x2`, ... in the order of its arguments.
+    pub fn from_function(function: &BooleanFunction) -> Self {
+        Anf {
+            variables: (1..=function.arity()).map(|i| format!("x{}", i)).collect(),
+            coefficients: mobius(function.table()),
+        }
+    }
+
+    pub fn variables(&self) -> &[String] {
+        &self.variables
+    }
+
+    /// the monomials with a coefficient of 1, by degree and then in order of the variables.
+    pub fn monomials(&self) -> Vec<u64> {
+        let mut monomials: Vec<u64> = (0..self.coefficients.len() as u64).filter(|m| self.coefficients[*m as usize]).collect();
+        monomials.sort_by_key(|m| (m.count_ones(), std::cmp::Reverse(*m)));
+        monomials
+    }
+
+    /// the number of variables of the largest monomial, 0 for constants.
+    pub fn degree(&self) -> usize {
+        self.monomials().iter().map(|m| m.count_ones() as usize).max().unwrap_or(0)
+    }
+
+    /// the polynomial written with `⊕` and `∧`. The constant term comes first as `1`.
+    pub fn to_tokens(&self) -> Tokens {
+        let monomials = self.monomials();
+        if monomials.is_empty() {
+            return Tokens::from(vec![Token::False]);
+        }
+        let n = self.variables.len();
+        let mut tokens = Vec::new();
+        for (i, m) in monomials.iter().enumerate() {
+            if i > 0 {
+                tokens.push(Token::XOr('⊕'));
+            }
+            let factors: Vec<&String> = (0..n).filter(|k| m >> (n - k - 1) & 1 == 1).map(|k| &self.variables[k]).collect();
+            if factors.is_empty() {
+                tokens.push(Token::True);
+                continue;
+            }
+            let enclose = factors.len() > 1 && monomials.len() > 1;
+            if enclose {
+                tokens.push(Token::OpenParen);
+            }
+            for (j, v) in factors.iter().enumerate() {
+                if j > 0 {
+                    tokens.push(Token::And('∧'));
+                }
+                tokens.push(Token::Ident(v.to_string()));
+            }
+            if enclose {
+                tokens.push(Token::CloseParen);
+            }
+        }
+        Tokens::from(tokens)
+    }
+}
+
+impl fmt::Display for Anf {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_tokens())
+    }
+}
+
+impl Evaluator {
+    /// the algebraic normal form of the expression over its variables.
+    pub fn anf(&self) -> Result<Anf, EvaluatorError> {
+        Ok(Anf::from_specification(&Specification::from_evaluator(self)?))
+    }
+
+    /// the degree of the algebraic normal form.
+    pub fn algebraic_degree(&self) -> Result<usize, EvaluatorError> {
+        Ok(self.anf()?.degree())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn anf(s: &str) -> Anf {
+        let evaluator = Evaluator::new(Tokens::from_text(s)).unwrap();
+        let anf = evaluator.anf().unwrap();
+        let check = Evaluator::new(anf.to_tokens()).unwrap();
+        assert!(evaluator.is_equivalent(&check).unwrap(), "{} is not {}", anf, s);
+        anf
+    }
+
+    #[test]
+    fn polynomials() {
+        assert_eq!(anf("p or q").to_string(), "p ⊕ q ⊕ (p ∧ q)");
+        assert_eq!(anf("not p").to_string(), "1 ⊕ p");
+        assert_eq!(anf("p -> q").to_string(), "1 ⊕ p ⊕ (p ∧ q)");
+        assert_eq!(anf("maj(a, b, c)").to_string(), "(a ∧ b) ⊕ (a ∧ c) ⊕ (b ∧ c)");
+        assert_eq!(anf("p and not p").to_string(), "0");
+        assert_eq!(anf("p and q and r").to_string(), "p ∧ q ∧ r");
+    }
+
+    #[test]
+    fn degree() {
+        assert_eq!(anf("xor(a, b, c, d)").degree(), 1);
+        assert_eq!(anf("ite(a, b, c)").degree(), 2);
+        assert_eq!(anf("exactly(1, a, b, c)").degree(), 3);
+        assert_eq!(anf("true").degree(), 0);
+        //the transform is an involution.
+        let table = [false, true, true, true, false, false, true, false];
+        assert_eq!(mobius(&mobius(&table)), table);
+        let function = BooleanFunction::from_table(table.to_vec()).unwrap();
+        assert_eq!(Anf::from_function(&function).to_string(), "x2 ⊕ x3 ⊕ (x1 ∧ x3) ⊕ (x2 ∧ x3)");
+    }
+}
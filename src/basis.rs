@@ -0,0 +1,376 @@
+use std::fmt;
+use std::mem::discriminant;
+use std::str::FromStr;
+
+use crate::evaluator::{Evaluator, EvaluatorError};
+use crate::expression::Expression;
+use crate::tokenizer::{Function, Token, Tokens};
+
+/// A functionally complete set of connectives to rewrite expressions with.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Basis {
+    /// `{¬, ∧}`
+    NotAnd,
+    /// `{¬, ∨}`
+    NotOr,
+    /// `{→, ¬}`
+    ImplicationNot,
+    /// `{↑}`
+    #[default]
+    Nand,
+    /// `{↓}`
+    Nor,
+}
+
+impl Basis {
+    pub const ALL: [Basis; 5] = [Basis::NotAnd, Basis::NotOr, Basis::ImplicationNot, Basis::Nand, Basis::Nor];
+
+    pub fn connectives(&self) -> Vec<Token> {
+        match self {
+            Basis::NotAnd => vec![Token::Not('¬'), Token::And('∧')],
+            Basis::NotOr => vec![Token::Not('¬'), Token::Or('∨')],
+            Basis::ImplicationNot => vec![Token::Implication('→'), Token::Not('¬')],
+            Basis::Nand => vec![Token::Nand('↑')],
+            Basis::Nor => vec![Token::Nor('↓')],
+        }
+    }
+
+    /// whether the operator is one of the connectives, whatever symbol it was written with.
+    pub fn allows(&self, operator: &Token) -> bool {
+        self.connectives().iter().any(|c| discriminant(c) == discriminant(operator))
+    }
+}
+
+impl fmt::Display for Basis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let connectives: Vec<String> = self.connectives().iter().map(|c| c.to_string()).collect();
+        write!(f, "{{{}}}", connectives.join(", "))
+    }
+}
+
+impl FromStr for Basis {
+    type Err = String;
+    /// `and`, `or`, `implication`, `nand` or `nor`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "and" | "not-and" => Ok(Basis::NotAnd),
+            "or" | "not-or" => Ok(Basis::NotOr),
+            "implication" | "->" => Ok(Basis::ImplicationNot),
+            "nand" => Ok(Basis::Nand),
+            "nor" => Ok(Basis::Nor),
+            _ => Err(format!("unknown basis '{}'", s)),
+        }
+    }
+}
+
+impl Expression {
+    /// the same function written with the connectives of `basis` only. Constants are folded
+    /// away, so they only remain when the whole expression is constant. The modal and temporal
+    /// operators are not connectives and give an error.
+    pub fn rewrite(&self, basis: Basis) -> Result<Expression, EvaluatorError> {
+        Ok(to_basis(&core(self)?, basis, false))
+    }
+
+    /// whether the expression is a constant or only uses the connectives of `basis`.
+    pub fn uses_only(&self, basis: Basis) -> bool {
+        match self {
+            Expression::Operation(operator, operands) => {
+                basis.allows(operator)
+                    && operands.iter().all(|o| !matches!(o, Expression::Constant(_)) && o.uses_only(basis))
+            }
+            Expression::Count(_) => false,
+            _ => true,
+        }
+    }
+}
+
+impl Evaluator {
+    /// an equivalent expression that only uses the connectives of `basis`. The truth vector of
+    /// the result is checked against the one of the expression before it is returned.
+    pub fn rewrite(&self, basis: Basis) -> Result<Tokens, EvaluatorError> {
+        let expression = Expression::from(self);
+        let rewritten = expression.rewrite(basis)?;
+        let variables = self.variables();
+        if !rewritten.uses_only(basis) || rewritten.truth_vector(&variables) != expression.truth_vector(&variables) {
+            return Err(EvaluatorError::new(format!("Could not rewrite the expression with {}.", basis)));
+        }
+        Ok(rewritten.to_tokens())
+    }
+}
+
+fn operation(operator: Token, operands: Vec<Expression>) -> Expression {
+    Expression::Operation(operator, operands)
+}
+
+fn not(e: Expression) -> Expression {
+    match e {
+        Expression::Constant(v) => Expression::Constant(!v),
+        Expression::Operation(Token::Not(_), mut operands) => operands.remove(0),
+        e => operation(Token::Not('¬'), vec![e]),
+    }
+}
+
+fn and(a: Expression, b: Expression) -> Expression {
+    match (a, b) {
+        (Expression::Constant(false), _) | (_, Expression::Constant(false)) => Expression::Constant(false),
+        (Expression::Constant(true), e) | (e, Expression::Constant(true)) => e,
+        (a, b) => operation(Token::And('∧'), vec![a, b]),
+    }
+}
+
+fn or(a: Expression, b: Expression) -> Expression {
+    match (a, b) {
+        (Expression::Constant(true), _) | (_, Expression::Constant(true)) => Expression::Constant(true),
+        (Expression::Constant(false), e) | (e, Expression::Constant(false)) => e,
+        (a, b) => operation(Token::Or('∨'), vec![a, b]),
+    }
+}
+
+fn xor(a: Expression, b: Expression) -> Expression {
+    or(and(a.clone(), not(b.clone())), and(not(a), b))
+}
+
+/// the xor of the operands, split in halves so that each of them is copied a logarithmic
+/// number of times.
+fn xor_all(operands: &[Expression]) -> Expression {
+    match operands {
+        [] => Expression::Constant(false),
+        [operand] => operand.clone(),
+        _ => {
+            let (left, right) = operands.split_at(operands.len() / 2);
+            xor(xor_all(left), xor_all(right))
+        }
+    }
+}
+
+/// at least `k` of the operands are true: `j` of the first half and `k - j` of the second.
+fn at_least(k: usize, operands: &[Expression]) -> Expression {
+    if k == 0 {
+        return Expression::Constant(true);
+    }
+    if k > operands.len() {
+        return Expression::Constant(false);
+    }
+    if let [operand] = operands {
+        return operand.clone();
+    }
+    let (left, right) = operands.split_at(operands.len() / 2);
+    (0..=k)
+        .map(|j| and(at_least(j, left), at_least(k - j, right)))
+        .fold(Expression::Constant(false), or)
+}
+
+fn not_a_connective(operator: &Token) -> EvaluatorError {
+    EvaluatorError::new(format!("'{}' is not a connective of propositional logic.", operator))
+}
+
+/// collects the operands of a chain of `⊕`, `↔`, `≡`, `≠` and `xor`, each in the core form.
+/// Returns whether the chain is the negation of the xor of `operands`.
+fn parity(e: &Expression, operands: &mut Vec<Expression>) -> Result<bool, EvaluatorError> {
+    let Expression::Operation(operator, arguments) = e else {
+        operands.push(e.clone());
+        return Ok(false);
+    };
+    let mut negated = match operator {
+        Token::XOr(_) | Token::NotEquals(_) | Token::Function(Function::Xor) => false,
+        Token::Biconditional(_) | Token::Equals(_) | Token::Not(_) => true,
+        _ => {
+            operands.push(core(e)?);
+            return Ok(false);
+        }
+    };
+    for argument in arguments {
+        negated ^= parity(argument, operands)?;
+    }
+    Ok(negated)
+}
+
+/// the expression with `¬`, `∧` and `∨` only, and without constants unless it is one.
+fn core(e: &Expression) -> Result<Expression, EvaluatorError> {
+    let Expression::Operation(operator, operands) = e else {
+        return Ok(e.clone());
+    };
+    if matches!(operator, Token::XOr(_) | Token::NotEquals(_) | Token::Biconditional(_) | Token::Equals(_))
+        || *operator == Token::Function(Function::Xor)
+    {
+        let mut operands = Vec::new();
+        let negated = parity(e, &mut operands)?;
+        let chain = xor_all(&operands);
+        return Ok(if negated { not(chain) } else { chain });
+    }
+    let mut operands: Vec<Expression> = operands.iter().map(core).collect::<Result<_, _>>()?;
+    if let Token::Function(function) = operator {
+        let count = match operands.first() {
+            Some(Expression::Count(k)) if function.takes_count() => *k,
+            _ => 0,
+        };
+        if function.takes_count() {
+            operands.remove(0);
+        }
+        return Ok(match function {
+            Function::Ite => {
+                let [c, a, b]: [Expression; 3] = operands.try_into().expect("ite takes 3 arguments");
+                or(and(c.clone(), a), and(not(c), b))
+            }
+            Function::Xor => unreachable!("xor is a parity chain"),
+            Function::Maj => at_least(operands.len() / 2 + 1, &operands),
+            Function::AtLeast => at_least(count, &operands),
+            Function::AtMost => not(at_least(count + 1, &operands)),
+            Function::Exactly => and(at_least(count, &operands), not(at_least(count + 1, &operands))),
+        });
+    }
+    if let (Token::Not(_), [a]) = (operator, operands.as_slice()) {
+        return Ok(not(a.clone()));
+    }
+    let Ok([a, b]) = <[Expression; 2]>::try_from(operands) else {
+        return Err(not_a_connective(operator));
+    };
+    Ok(match operator {
+        Token::And(_) => and(a, b),
+        Token::Or(_) => or(a, b),
+        Token::Nand(_) => not(and(a, b)),
+        Token::Nor(_) => not(or(a, b)),
+        Token::Implication(_) => or(not(a), b),
+        Token::Converse(_) => or(a, not(b)),
+        Token::NonImplication(_) => and(a, not(b)),
+        _ => return Err(not_a_connective(operator)),
+    })
+}
+
+/// the negation of `e` in the connectives of `basis`. A negation that is already there is
+/// taken away rather than negated again.
+fn negate(e: Expression, basis: Basis) -> Expression {
+    let negation = match basis {
+        Basis::Nand => Token::Nand('↑'),
+        Basis::Nor => Token::Nor('↓'),
+        _ => return not(e),
+    };
+    match e {
+        Expression::Operation(operator, mut operands) if operator == negation && operands[0] == operands[1] => {
+            operands.remove(0)
+        }
+        e => operation(negation, vec![e.clone(), e]),
+    }
+}
+
+/// rewrites an expression made of `¬`, `∧` and `∨`, or its negation when `negated`. The
+/// negations are pushed down to the variables, so a subterm is only negated once.
+fn to_basis(e: &Expression, basis: Basis, negated: bool) -> Expression {
+    match e {
+        Expression::Operation(Token::Not(_), operands) => to_basis(&operands[0], basis, !negated),
+        Expression::Operation(operator, _) => {
+            let mut parts = Vec::new();
+            chain(e, operator, &mut parts);
+            //¬(a ∧ b) is ¬a ∨ ¬b.
+            junction(&parts, matches!(operator, Token::And(_)) != negated, negated, basis)
+        }
+        e if negated => negate(e.clone(), basis),
+        e => e.clone(),
+    }
+}
+
+/// the operands of a chain of the same operator, like `a`, `b` and `c` in `(a ∧ b) ∧ c`.
+fn chain<'a>(e: &'a Expression, operator: &Token, parts: &mut Vec<&'a Expression>) {
+    match e {
+        Expression::Operation(o, operands) if o == operator => operands.iter().for_each(|o| chain(o, operator, parts)),
+        e => parts.push(e),
+    }
+}
+
+/// the conjunction, or the disjunction, of the parts, each negated when `negated`. The parts
+/// are split in halves, so a chain only nests as deep as its logarithm.
+fn junction(parts: &[&Expression], conjunction: bool, negated: bool, basis: Basis) -> Expression {
+    if let [part] = parts {
+        return to_basis(part, basis, negated);
+    }
+    let (left, right) = parts.split_at(parts.len() / 2);
+    let halves = |conjunction: bool, negated: bool| {
+        vec![junction(left, conjunction, negated, basis), junction(right, conjunction, negated, basis)]
+    };
+    //the other junction is the negation of this one over the negated parts.
+    match (basis, conjunction) {
+        (Basis::NotAnd, true) => operation(Token::And('∧'), halves(true, negated)),
+        (Basis::NotOr, false) => operation(Token::Or('∨'), halves(false, negated)),
+        (Basis::ImplicationNot, false) => {
+            let [a, b]: [Expression; 2] = halves(false, negated).try_into().expect("two halves");
+            operation(Token::Implication('→'), vec![negate(a, basis), b])
+        }
+        (Basis::NotAnd | Basis::NotOr | Basis::ImplicationNot, _) => {
+            negate(junction(parts, !conjunction, !negated, basis), basis)
+        }
+        (Basis::Nand, true) => negate(operation(Token::Nand('↑'), halves(true, negated)), basis),
+        (Basis::Nand, false) => operation(Token::Nand('↑'), halves(true, !negated)),
+        (Basis::Nor, true) => operation(Token::Nor('↓'), halves(false, !negated)),
+        (Basis::Nor, false) => negate(operation(Token::Nor('↓'), halves(false, negated)), basis),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rewrite(s: &str, basis: Basis) -> String {
+        let evaluator = Evaluator::new(Tokens::from_text(s)).unwrap();
+        evaluator.rewrite(basis).unwrap().to_string()
+    }
+
+    #[test]
+    fn nand_and_nor() {
+        assert_eq!(rewrite("not p", Basis::Nand), "p ↑ p");
+        assert_eq!(rewrite("p and q", Basis::Nand), "(p ↑ q) ↑ (p ↑ q)");
+        assert_eq!(rewrite("p or q", Basis::Nand), "(p ↑ p) ↑ (q ↑ q)");
+        assert_eq!(rewrite("p or q", Basis::Nor), "(p ↓ q) ↓ (p ↓ q)");
+        assert_eq!(rewrite("p and q", Basis::Nor), "(p ↓ p) ↓ (q ↓ q)");
+    }
+
+    #[test]
+    fn other_bases() {
+        assert_eq!(rewrite("p -> q", Basis::NotAnd), "¬(p ∧ ¬q)");
+        assert_eq!(rewrite("p and q", Basis::NotOr), "¬(¬p ∨ ¬q)");
+        assert_eq!(rewrite("p or q", Basis::ImplicationNot), "¬p → q");
+        assert_eq!(rewrite("p and true", Basis::Nand), "p");
+        assert_eq!(rewrite("p and not p or true", Basis::Nor), "1");
+    }
+
+    #[test]
+    fn temporal_operator() {
+        let (p, q) = (Expression::Variable("p".into()), Expression::Variable("q".into()));
+        let until = Expression::Operation(Token::Temporal(crate::tokenizer::Temporal::Until), vec![p, q]);
+        let error = until.rewrite(Basis::Nand).unwrap_err();
+        assert_eq!(error.message(), "'U' is not a connective of propositional logic.");
+    }
+
+    #[test]
+    fn many_variables() {
+        //chains are rewritten as balanced trees, their size grows with the square of their length.
+        let xs: Vec<String> = (0..12).map(|i| format!("x{}", i)).collect();
+        for s in [xs.join(" and "), xs.join(" or "), xs.join(" xor ")] {
+            for basis in Basis::ALL {
+                let rewritten = rewrite(&s, basis);
+                assert!(rewritten.len() < 20_000, "{} in {}: {} bytes", s, basis, rewritten.len());
+            }
+        }
+        assert!(rewrite(&xs.join(" and "), Basis::Nand).len() < 3000);
+    }
+
+    #[test]
+    fn every_connective() {
+        let expressions = [
+            "(p <-> q) xor (r nor p)",
+            "(p <- q) ↛ not (q != r)",
+            "ite(p, q, r) = maj(p, q, r)",
+            "exactly(2, p, q, r) or atmost(1, p, q) and atleast(2, q, r, p)",
+            "xor(p, q, r) nand 1",
+        ];
+        for s in expressions {
+            let evaluator = Evaluator::new(Tokens::from_text(s)).unwrap();
+            for basis in Basis::ALL {
+                let tokens = evaluator.rewrite(basis).unwrap();
+                let expression = Expression::from(&Evaluator::new(tokens).unwrap());
+                assert!(expression.uses_only(basis), "{} in {}: {}", s, basis, expression);
+            }
+        }
+        assert_eq!(Basis::ImplicationNot.to_string(), "{→, ¬}");
+        assert_eq!("nand".parse::<Basis>().unwrap(), Basis::Nand);
+    }
+}
@@ -0,0 +1,426 @@
+use prettytable::Table;
+
+use crate::definitions::Definitions;
+use crate::evaluator::{Evaluator, EvaluatorError};
+use crate::evaluator_result::{Classification, EvaluatorResult};
+use crate::render::RenderOptions;
+use crate::tokenizer::Tokens;
+
+/// An expression of the batch that could be evaluated.
+pub struct BatchEntry {
+    /// 1-based line number in the input.
+    pub line: usize,
+    pub expression: String,
+    pub evaluator: Evaluator,
+    pub result: EvaluatorResult,
+}
+
+impl BatchEntry {
+    pub fn classification(&self) -> Classification {
+        self.result.classification()
+    }
+}
+
+/// A line of the batch that could not be evaluated.
+pub struct BatchError {
+    /// 1-based line number in the input.
+    pub line: usize,
+    pub expression: String,
+    pub error: EvaluatorError,
+}
+
+/// What a report shows for each expression.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ReportKind {
+    /// the truth table of each expression.
+    #[default]
+    Tables,
+    /// one row per expression with its classification.
+    Summary,
+}
+
+/// The results of evaluating a text with one expression per line. Empty lines and lines starting
+/// with `#` are skipped, lines with errors are collected in `errors` and do not stop the batch.
+/// Lines like `name := EXPR` are definitions, used by the lines after them.
+pub struct BatchReport {
+    pub entries: Vec<BatchEntry>,
+    pub errors: Vec<BatchError>,
+}
+
+impl BatchReport {
+    pub fn from_text(text: &str) -> Self {
+        Self::with_definitions(text, Definitions::new(), false)
+    }
+
+    /// evaluates `text` starting from `definitions`. With `named_columns` the tables get a
+    /// column for each definition an expression uses, see `Definitions::evaluate_all`.
+    pub fn with_definitions(text: &str, definitions: Definitions, named_columns: bool) -> Self {
+        let mut definitions = definitions;
+        let mut entries = Vec::new();
+        let mut errors = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let expression = line.trim();
+            if expression.is_empty() || expression.starts_with('#') {
+                continue;
+            }
+            let tokens = Tokens::from_text(expression);
+            if Definitions::is_definition(&tokens) {
+                if let Err(error) = definitions.define(tokens) {
+                    errors.push(BatchError { line: i + 1, expression: expression.to_string(), error });
+                }
+                continue;
+            }
+            let evaluated = definitions.compile(&tokens).and_then(|evaluator| {
+                definitions.evaluate_all(&tokens, named_columns).map(|result| (evaluator, result))
+            });
+            match evaluated {
+                Ok((evaluator, result)) => entries.push(BatchEntry {
+                    line: i + 1,
+                    expression: expression.to_string(),
+                    evaluator,
+                    result,
+                }),
+                Err(error) => errors.push(BatchError { line: i + 1, expression: expression.to_string(), error }),
+            }
+        }
+        BatchReport { entries, errors }
+    }
+
+    pub fn from_file(file_name: &str) -> std::io::Result<Self> {
+        Ok(Self::from_text(&std::fs::read_to_string(file_name)?))
+    }
+
+    fn title(entry: &BatchEntry) -> String {
+        format!("line {}: {}", entry.line, entry.expression)
+    }
+
+    fn error_lines(&self) -> Vec<String> {
+        self.errors
+            .iter()
+            .map(|e| format!("line {}: {}", e.line, e.error.annotate(&e.expression)))
+            .collect()
+    }
+
+    fn true_rows(entry: &BatchEntry) -> String {
+        let column = entry.result.final_column();
+        format!("{}/{}", column.iter().filter(|v| **v).count(), column.len())
+    }
+
+    fn summary_table(&self) -> Table {
+        let mut table = Table::new();
+        table.add_row(["line", "expression", "classification", "true rows"].into());
+        for entry in self.entries.iter() {
+            table.add_row(
+                [
+                    entry.line.to_string(),
+                    entry.expression.clone(),
+                    entry.classification().to_string(),
+                    Self::true_rows(entry),
+                ]
+                .into(),
+            );
+        }
+        table
+    }
+
+    /// the report as printed to the terminal, without colors.
+    pub fn to_plain_text(&self, options: &RenderOptions, kind: ReportKind) -> String {
+        let mut out = String::new();
+        match kind {
+            ReportKind::Tables => {
+                for entry in self.entries.iter() {
+                    out.push_str(&format!("{}\n{}\n", Self::title(entry), entry.result.to_plain_text(options)));
+                }
+            }
+            ReportKind::Summary => out.push_str(&self.summary_table().to_string()),
+        }
+        if !self.errors.is_empty() {
+            out.push_str(&format!("\n{} line(s) with errors:\n{}\n", self.errors.len(), self.error_lines().join("\n")));
+        }
+        out
+    }
+
+    /// tables (or the summary) one after the other, separated by empty lines and followed by
+    /// `error,line,expression,message` rows.
+    #[cfg(feature = "csv")]
+    pub fn to_csv(&self, options: &RenderOptions, kind: ReportKind) -> String {
+        let mut parts = Vec::new();
+        match kind {
+            ReportKind::Tables => {
+                for entry in self.entries.iter() {
+                    let mut title = Table::new();
+                    title.add_row([Self::title(entry)].into());
+                    parts.push(format!("{}{}", table_to_csv(&title), entry.result.to_csv(options)));
+                }
+            }
+            ReportKind::Summary => parts.push(table_to_csv(&self.summary_table())),
+        }
+        if !self.errors.is_empty() {
+            let mut errors = Table::new();
+            for e in self.errors.iter() {
+                errors.add_row(["error".to_string(), e.line.to_string(), e.expression.clone(), e.error.to_string()].into());
+            }
+            parts.push(table_to_csv(&errors));
+        }
+        parts.join("\n")
+    }
+
+    #[cfg(feature = "markdown")]
+    pub fn to_markdown(&self, options: &RenderOptions, kind: ReportKind) -> String {
+        let code = |text: &str| format!("`{}`", text.replace('`', "'"));
+        let mut out = String::new();
+        match kind {
+            ReportKind::Tables => {
+                for entry in self.entries.iter() {
+                    out.push_str(&format!(
+                        "### line {}: {}\n\n{}\n",
+                        entry.line,
+                        code(&entry.expression),
+                        entry.result.to_markdown(options)
+                    ));
+                }
+            }
+            ReportKind::Summary => {
+                out.push_str("| line | expression | classification | true rows |\n|------|------------|----------------|-----------|\n");
+                for entry in self.entries.iter() {
+                    out.push_str(&format!(
+                        "| {} | {} | {} | {} |\n",
+                        entry.line,
+                        code(&entry.expression).replace('|', "\\|"),
+                        entry.classification(),
+                        Self::true_rows(entry)
+                    ));
+                }
+            }
+        }
+        if !self.errors.is_empty() {
+            out.push_str("\n### Errors\n\n");
+            for e in self.errors.iter() {
+                out.push_str(&format!("- line {}: {} {}\n", e.line, code(&e.expression), e.error));
+            }
+        }
+        out
+    }
+
+    #[cfg(feature = "latex")]
+    pub fn to_latex(&self, options: &RenderOptions, kind: ReportKind) -> String {
+        let mut out = String::new();
+        match kind {
+            ReportKind::Tables => {
+                for entry in self.entries.iter() {
+                    out.push_str(&format!(
+                        "\\paragraph{{Line {}:}} {}\n\n{}\n",
+                        entry.line,
+                        verb(&entry.expression),
+                        entry.result.to_latex(options)
+                    ));
+                }
+            }
+            ReportKind::Summary => {
+                out.push_str("\\begin{tabular}{|r|l|l|r|}\n\\hline\nline & expression & classification & true rows \\\\\n\\hline\n");
+                for entry in self.entries.iter() {
+                    out.push_str(&format!(
+                        "{} & {} & {} & {} \\\\\n",
+                        entry.line,
+                        verb(&entry.expression),
+                        entry.classification(),
+                        Self::true_rows(entry)
+                    ));
+                }
+                out.push_str("\\hline\n\\end{tabular}\n");
+            }
+        }
+        if !self.errors.is_empty() {
+            out.push_str("\n\\paragraph{Errors}\n\\begin{itemize}\n");
+            for e in self.errors.iter() {
+                out.push_str(&format!("\\item line {}: {} {}\n", e.line, verb(&e.expression), verb(e.error.message())));
+            }
+            out.push_str("\\end{itemize}\n");
+        }
+        out
+    }
+
+    #[cfg(feature = "html")]
+    pub fn to_html(&self, options: &RenderOptions, kind: ReportKind) -> String {
+        use crate::html::{escape, html_document};
+        let mut body = String::from("<h1>Batch report</h1>\n");
+        match kind {
+            ReportKind::Tables => {
+                for entry in self.entries.iter() {
+                    body.push_str(&format!("<h2>{}</h2>\n{}", escape(&Self::title(entry)), entry.result.html_table(options)));
+                }
+            }
+            ReportKind::Summary => {
+                body.push_str("<table>\n<thead>\n<tr><th>line</th><th>expression</th><th>classification</th><th>true rows</th></tr>\n</thead>\n<tbody>\n");
+                for entry in self.entries.iter() {
+                    let class = match entry.classification() {
+                        Classification::Tautology => "t",
+                        Classification::Contradiction => "f",
+                        Classification::Contingency => "",
+                    };
+                    body.push_str(&format!(
+                        "<tr><td>{}</td><td>{}</td><td class=\"{}\">{}</td><td>{}</td></tr>\n",
+                        entry.line,
+                        escape(&entry.expression),
+                        class,
+                        entry.classification(),
+                        Self::true_rows(entry)
+                    ));
+                }
+                body.push_str("</tbody>\n</table>\n");
+            }
+        }
+        if !self.errors.is_empty() {
+            body.push_str("<h2>Errors</h2>\n<ul>\n");
+            for e in self.errors.iter() {
+                body.push_str(&format!(
+                    "<li>line {}: <code>{}</code> {}</li>\n",
+                    e.line,
+                    escape(&e.expression),
+                    escape(&e.error.to_string())
+                ));
+            }
+            body.push_str("</ul>\n");
+        }
+        html_document("Batch report", options, &body)
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self, kind: ReportKind) -> String {
+        use crate::json::TruthTable;
+        let entries: Vec<serde_json::Value> = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let mut value = serde_json::json!({
+                    "line": entry.line,
+                    "expression": entry.expression,
+                    "classification": entry.classification(),
+                });
+                if kind == ReportKind::Tables {
+                    value["table"] = serde_json::to_value(TruthTable::from_result(&entry.evaluator, &entry.result)).unwrap();
+                }
+                value
+            })
+            .collect();
+        let errors: Vec<serde_json::Value> = self
+            .errors
+            .iter()
+            .map(|e| {
+                serde_json::json!({
+                    "line": e.line,
+                    "expression": e.expression,
+                    "message": e.error.message(),
+                    "position": e.error.position(),
+                })
+            })
+            .collect();
+        serde_json::to_string_pretty(&serde_json::json!({ "entries": entries, "errors": errors })).unwrap()
+    }
+
+    #[cfg(feature = "pdf")]
+    pub fn save_to_pdf(&self, file_name: &str, options: &RenderOptions, kind: ReportKind) {
+        self.try_save_to_pdf(file_name, options, kind).expect("Failed to write PDF file");
+    }
+
+    /// like `save_to_pdf`, but a missing font or a file that can not be written is returned
+    /// instead of panicking.
+    #[cfg(feature = "pdf")]
+    pub fn try_save_to_pdf(&self, file_name: &str, options: &RenderOptions, kind: ReportKind) -> Result<(), genpdf::error::Error> {
+        use genpdf::elements::{Break, Paragraph};
+        let mut doc = crate::pdf::pdf_document("Batch report")?;
+        let mut width: f32 = 210.0;
+        match kind {
+            ReportKind::Tables => {
+                for entry in self.entries.iter() {
+                    if let Some((table, w)) = entry.result.pdf_table(options) {
+                        width = width.max(w);
+                        doc.push(Paragraph::new(Self::title(entry)));
+                        doc.push(table);
+                        doc.push(Break::new(1));
+                    }
+                }
+            }
+            ReportKind::Summary => {
+                for entry in self.entries.iter() {
+                    doc.push(Paragraph::new(format!(
+                        "{}: {} ({} true rows)",
+                        Self::title(entry),
+                        entry.classification(),
+                        Self::true_rows(entry)
+                    )));
+                }
+            }
+        }
+        if !self.errors.is_empty() {
+            doc.push(Break::new(1));
+            doc.push(Paragraph::new("Errors"));
+            for e in self.errors.iter() {
+                doc.push(Paragraph::new(format!("line {}: {} {}", e.line, e.expression, e.error)));
+            }
+        }
+        doc.set_paper_size(genpdf::Size::new(width, 297));
+        doc.render_to_file(file_name)
+    }
+}
+
+/// the text as it is in `\verb`, with a delimiter that is not in the text. Text with every
+/// delimiter is set in `\texttt` instead.
+#[cfg(feature = "latex")]
+fn verb(text: &str) -> String {
+    match ['|', '+', '@', '/', ';', '"', '`'].iter().find(|d| !text.contains(**d)) {
+        Some(d) => format!("\\verb{}{}{}", d, text, d),
+        None => format!("\\texttt{{{}}}", crate::latex::escape(text)),
+    }
+}
+
+#[cfg(feature = "csv")]
+fn table_to_csv(table: &Table) -> String {
+    let mut out = Vec::new();
+    table.to_csv(&mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn continues_past_errors() {
+        let report = BatchReport::from_text("p or not p\n\n# a comment\np and\np and not p\n(p -> q\n");
+        let lines: Vec<usize> = report.entries.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![1, 5]);
+        assert_eq!(report.entries[0].classification(), Classification::Tautology);
+        assert_eq!(report.entries[1].classification(), Classification::Contradiction);
+        let errors: Vec<usize> = report.errors.iter().map(|e| e.line).collect();
+        assert_eq!(errors, vec![4, 6]);
+        let text = report.to_plain_text(&RenderOptions::default(), ReportKind::Summary);
+        assert!(text.contains("2 line(s) with errors"));
+        assert!(text.contains("line 6: mis-match parentheses"));
+    }
+
+    #[test]
+    fn definitions() {
+        let report = BatchReport::from_text("safe := door_closed and not alarm\nsafe -> ready\nloop := loop or p\nloop\n");
+        assert_eq!(report.entries.len(), 2);
+        assert_eq!(report.entries[0].evaluator.variables(), vec!["door_closed", "alarm", "ready"]);
+        let errors: Vec<usize> = report.errors.iter().map(|e| e.line).collect();
+        assert_eq!(errors, vec![3]);
+    }
+
+    #[test]
+    #[cfg(feature = "latex")]
+    fn latex_expressions() {
+        let report = BatchReport::from_text("p | q\np | !q + r\n");
+        let latex = report.to_latex(&RenderOptions::default(), ReportKind::Summary);
+        assert!(latex.contains("\\verb+p | q+"));
+        assert!(latex.contains("\\verb@p | !q + r@"));
+    }
+
+    #[test]
+    fn expressions_file() {
+        let report = BatchReport::from_file("expressions.txt").unwrap();
+        assert!(report.errors.is_empty());
+        assert_eq!(report.entries.len(), 20);
+    }
+}
@@ -0,0 +1,141 @@
+use indexmap::IndexMap;
+
+use crate::evaluator::{Evaluator, EvaluatorError};
+use crate::evaluator_result::EvaluatorResult;
+use crate::synthesis::{input_count, Specification};
+use crate::tokenizer::Tokens;
+
+/// the index of the row of `values` in a table over `variables`. As in `evaluator::assignment`,
+/// the first variable is the most significant bit and true is 1. `evaluate_all` starts with every
+/// variable true, so its row `r` (counting from 0) has the index `2^n - 1 - r`.
+pub fn row_index(variables: &[String], values: &IndexMap<String, bool>) -> Result<u64, EvaluatorError> {
+    variables.iter().try_fold(0u64, |index, v| match values.get(v) {
+        Some(value) => Ok(index << 1 | u64::from(*value)),
+        None => Err(EvaluatorError::new(format!("Please provide a value for '{}'.", v))),
+    })
+}
+
+/// `Σm(1, 3, 7)`, the rows where a function is true.
+pub fn minterm_notation(minterms: &[u64]) -> String {
+    format!("Σm({})", join(minterms))
+}
+
+/// `ΠM(0, 2)`, the rows where a function is false.
+pub fn maxterm_notation(maxterms: &[u64]) -> String {
+    format!("ΠM({})", join(maxterms))
+}
+
+fn join(indices: &[u64]) -> String {
+    indices.iter().map(|i| i.to_string()).collect::<Vec<_>>().join(", ")
+}
+
+impl Specification {
+    pub fn minterm_notation(&self) -> String {
+        minterm_notation(&self.minterms())
+    }
+
+    pub fn maxterm_notation(&self) -> String {
+        maxterm_notation(&self.maxterms())
+    }
+}
+
+impl Evaluator {
+    /// the index of the row of `values` in the truth table of the expression.
+    pub fn row_index(&self, values: &IndexMap<String, bool>) -> Result<u64, EvaluatorError> {
+        row_index(&self.variables(), values)
+    }
+
+    /// the indices of the rows where the expression is true, in increasing order.
+    pub fn minterms(&self) -> Result<Vec<u64>, EvaluatorError> {
+        Ok(Specification::from_evaluator(self)?.minterms())
+    }
+
+    /// the indices of the rows where the expression is false, in increasing order.
+    pub fn maxterms(&self) -> Result<Vec<u64>, EvaluatorError> {
+        Ok(Specification::from_evaluator(self)?.maxterms())
+    }
+
+    /// the canonical sum of products: a product of every variable for each minterm.
+    pub fn sum_of_minterms(&self) -> Result<Tokens, EvaluatorError> {
+        Ok(Specification::from_evaluator(self)?.canonical_dnf())
+    }
+
+    /// the canonical product of sums: a sum of every variable for each maxterm.
+    pub fn product_of_maxterms(&self) -> Result<Tokens, EvaluatorError> {
+        Ok(Specification::from_evaluator(self)?.canonical_cnf())
+    }
+}
+
+impl EvaluatorResult {
+    /// the index of each row, read from the variable columns (see `Specification::from_result`).
+    pub fn row_indices(&self) -> Vec<u64> {
+        let header: Vec<String> = self.result.first().map(|row| row.keys().cloned().collect()).unwrap_or_default();
+        let n = input_count(&header);
+        self.result
+            .iter()
+            .map(|row| row.values().take(n).fold(0u64, |index, v| index << 1 | u64::from(*v)))
+            .collect()
+    }
+
+    /// the indices of the rows where the last column is true, in increasing order.
+    pub fn minterms(&self) -> Vec<u64> {
+        self.terms(true)
+    }
+
+    /// the indices of the rows where the last column is false, in increasing order.
+    pub fn maxterms(&self) -> Vec<u64> {
+        self.terms(false)
+    }
+
+    fn terms(&self, value: bool) -> Vec<u64> {
+        let mut indices: Vec<u64> = self
+            .row_indices()
+            .into_iter()
+            .zip(self.final_column())
+            .filter(|(_, v)| *v == value)
+            .map(|(i, _)| i)
+            .collect();
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluator(s: &str) -> Evaluator {
+        Evaluator::new(Tokens::from_text(s)).unwrap()
+    }
+
+    #[test]
+    fn notation() {
+        let e = evaluator("p -> q");
+        assert_eq!(minterm_notation(&e.minterms().unwrap()), "Σm(0, 1, 3)");
+        assert_eq!(maxterm_notation(&e.maxterms().unwrap()), "ΠM(2)");
+        assert_eq!(e.sum_of_minterms().unwrap().to_string(), "(¬p ∧ ¬q) ∨ (¬p ∧ q) ∨ (p ∧ q)");
+        assert_eq!(e.product_of_maxterms().unwrap().to_string(), "¬p ∨ q");
+        let spec = Specification::from_evaluator(&evaluator("maj(a, b, c)")).unwrap();
+        assert_eq!(spec.minterm_notation(), "Σm(3, 5, 6, 7)");
+        assert_eq!(spec.maxterm_notation(), "ΠM(0, 1, 2, 4)");
+        assert_eq!(Specification::from_notation(spec.variables().to_vec(), &spec.maxterm_notation()).unwrap(), spec);
+        assert!(evaluator("p and not p").minterms().unwrap().is_empty());
+    }
+
+    #[test]
+    fn row_order() {
+        let e = evaluator("(p and q) or r");
+        let result = e.evaluate_all().unwrap();
+        //evaluate_all lists the rows from the last index to the first.
+        assert_eq!(result.row_indices(), [7, 6, 5, 4, 3, 2, 1, 0]);
+        for (row, index) in result.result.iter().zip(result.row_indices()) {
+            assert_eq!(e.row_index(row).unwrap(), index);
+        }
+        assert_eq!(result.minterms(), e.minterms().unwrap());
+        assert_eq!(result.maxterms(), e.maxterms().unwrap());
+        let values: IndexMap<String, bool> = [("r".to_string(), true), ("p".to_string(), true), ("q".to_string(), false)].into();
+        assert_eq!(e.row_index(&values).unwrap(), 0b101);
+        assert!(e.row_index(&IndexMap::new()).is_err());
+    }
+}
@@ -0,0 +1,229 @@
+use std::fmt;
+
+use crate::evaluator::{apply, Evaluator, EvaluatorError};
+use crate::tokenizer::Token;
+
+/// A boolean function given by its truth table. `table[i]` is the value for the arguments of row
+/// `i`, where the first argument is the most significant bit of `i` (see `evaluator::assignment`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct BooleanFunction {
+    arity: usize,
+    table: Vec<bool>,
+}
+
+/// The five maximal clones of Post's lattice. A set of connectives is functionally complete when,
+/// for each class, one of the connectives is not in it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PostClass {
+    /// `f(0, ..., 0) = 0`.
+    PreservesFalse,
+    /// `f(1, ..., 1) = 1`.
+    PreservesTrue,
+    /// `f(¬x1, ..., ¬xn) = ¬f(x1, ..., xn)`.
+    SelfDual,
+    /// changing an argument from 0 to 1 never changes the value from 1 to 0.
+    Monotone,
+    /// a xor of some of the arguments, possibly negated.
+    Affine,
+}
+
+impl PostClass {
+    pub const ALL: [PostClass; 5] = [
+        PostClass::PreservesFalse,
+        PostClass::PreservesTrue,
+        PostClass::SelfDual,
+        PostClass::Monotone,
+        PostClass::Affine,
+    ];
+
+    /// the usual name of the class: T0, T1, D, M or L.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            PostClass::PreservesFalse => "T0",
+            PostClass::PreservesTrue => "T1",
+            PostClass::SelfDual => "D",
+            PostClass::Monotone => "M",
+            PostClass::Affine => "L",
+        }
+    }
+}
+
+impl fmt::Display for PostClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            PostClass::PreservesFalse => "0-preserving",
+            PostClass::PreservesTrue => "1-preserving",
+            PostClass::SelfDual => "self-dual",
+            PostClass::Monotone => "monotone",
+            PostClass::Affine => "affine",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl BooleanFunction {
+    /// a function of `arity` arguments, `table` must have `2^arity` rows.
+    pub fn new(arity: usize, table: Vec<bool>) -> Result<Self, EvaluatorError> {
+        if arity >= 64 || table.len() as u64 != 1 << arity {
+            return Err(EvaluatorError::new(format!(
+                "A function of {} arguments needs a table of 2^{} rows, not {}.",
+                arity,
+                arity,
+                table.len()
+            )));
+        }
+        Ok(BooleanFunction { arity, table })
+    }
+
+    /// a function given by its truth vector alone, the arity is read from its length.
+    pub fn from_table(table: Vec<bool>) -> Result<Self, EvaluatorError> {
+        Self::new(table.len().trailing_zeros() as usize, table)
+    }
+
+    /// the connective of an operator or a constant token, `None` for other tokens.
+    pub fn from_token(token: &Token) -> Option<Self> {
+        let arity = match token {
+            Token::True | Token::False => 0,
+            Token::Not(_) => 1,
+            Token::And(_) | Token::Nand(_) | Token::Or(_) | Token::Nor(_) | Token::XOr(_) |
+            Token::Implication(_) | Token::Converse(_) | Token::NonImplication(_) |
+            Token::Biconditional(_) | Token::Equals(_) | Token::NotEquals(_) => 2,
+            _ => return None,
+        };
+        let table = (0..1u64 << arity)
+            .map(|i| {
+                if arity == 0 {
+                    return *token == Token::True;
+                }
+                let operands: Vec<Token> = (0..arity).map(|k| Token::from((i >> (arity - k - 1)) & 1 == 1)).collect();
+                apply(token, &operands)
+            })
+            .collect();
+        Some(BooleanFunction { arity, table })
+    }
+
+    /// the function of an expression, its arguments are the variables in order of appearance.
+    pub fn from_evaluator(evaluator: &Evaluator) -> Result<Self, EvaluatorError> {
+        Self::new(evaluator.variables().len(), evaluator.truth_vector()?)
+    }
+
+    pub fn arity(&self) -> usize {
+        self.arity
+    }
+
+    pub fn table(&self) -> &[bool] {
+        &self.table
+    }
+
+    pub fn preserves_false(&self) -> bool {
+        !self.table[0]
+    }
+
+    pub fn preserves_true(&self) -> bool {
+        self.table[self.table.len() - 1]
+    }
+
+    pub fn is_self_dual(&self) -> bool {
+        let last = self.table.len() - 1;
+        (0..self.table.len()).all(|i| self.table[i] != self.table[last - i])
+    }
+
+    pub fn is_monotone(&self) -> bool {
+        (0..self.table.len()).all(|i| {
+            (0..self.arity).all(|bit| i & (1 << bit) != 0 || self.table[i] <= self.table[i | (1 << bit)])
+        })
+    }
+
+    pub fn is_affine(&self) -> bool {
+        //an affine function is fixed by its value at 0 and the effect of each single argument.
+        let constant = self.table[0];
+        let effects: Vec<bool> = (0..self.arity).map(|bit| self.table[1 << bit] != constant).collect();
+        (0..self.table.len()).all(|i| {
+            let odd = (0..self.arity).filter(|bit| i & (1 << bit) != 0 && effects[*bit]).count() % 2 == 1;
+            self.table[i] == (constant != odd)
+        })
+    }
+
+    pub fn is_in(&self, class: PostClass) -> bool {
+        match class {
+            PostClass::PreservesFalse => self.preserves_false(),
+            PostClass::PreservesTrue => self.preserves_true(),
+            PostClass::SelfDual => self.is_self_dual(),
+            PostClass::Monotone => self.is_monotone(),
+            PostClass::Affine => self.is_affine(),
+        }
+    }
+
+    /// the classes of Post's lattice that contain the function.
+    pub fn classes(&self) -> Vec<PostClass> {
+        PostClass::ALL.into_iter().filter(|c| self.is_in(*c)).collect()
+    }
+}
+
+/// the classes that contain every function of the set. The set is functionally complete when
+/// there are none.
+pub fn closed_classes(functions: &[BooleanFunction]) -> Vec<PostClass> {
+    PostClass::ALL
+        .into_iter()
+        .filter(|c| functions.iter().all(|f| f.is_in(*c)))
+        .collect()
+}
+
+/// whether every boolean function can be written with the connectives of the set.
+pub fn is_functionally_complete(functions: &[BooleanFunction]) -> bool {
+    closed_classes(functions).is_empty()
+}
+
+impl Evaluator {
+    /// the classes of Post's lattice that contain the function of the expression.
+    pub fn post_classes(&self) -> Result<Vec<PostClass>, EvaluatorError> {
+        Ok(BooleanFunction::from_evaluator(self)?.classes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::Tokens;
+
+    fn connectives(text: &str) -> Vec<BooleanFunction> {
+        Tokens::from_text(text).iter().map(|t| BooleanFunction::from_token(t).unwrap()).collect()
+    }
+
+    #[test]
+    fn connective_classes() {
+        use PostClass::*;
+        assert_eq!(connectives("and")[0].classes(), [PreservesFalse, PreservesTrue, Monotone]);
+        assert_eq!(connectives("not")[0].classes(), [SelfDual, Affine]);
+        assert_eq!(connectives("xor")[0].classes(), [PreservesFalse, Affine]);
+        assert_eq!(connectives("->")[0].classes(), [PreservesTrue]);
+        assert!(connectives("nand")[0].classes().is_empty());
+        assert_eq!(connectives("1")[0].classes(), [PreservesTrue, Monotone, Affine]);
+    }
+
+    #[test]
+    fn complete_sets() {
+        assert!(is_functionally_complete(&connectives("not and")));
+        assert!(is_functionally_complete(&connectives("not or")));
+        assert!(is_functionally_complete(&connectives("-> 0")));
+        assert!(is_functionally_complete(&connectives("↑")));
+        assert!(is_functionally_complete(&connectives("↓")));
+        assert_eq!(closed_classes(&connectives("and or")), [PostClass::PreservesFalse, PostClass::PreservesTrue, PostClass::Monotone]);
+        assert_eq!(closed_classes(&connectives("->")), [PostClass::PreservesTrue]);
+        assert_eq!(closed_classes(&connectives("xor <-> not")), [PostClass::Affine]);
+        //majority and negation are both self-dual.
+        let maj = BooleanFunction::new(3, vec![false, false, false, true, false, true, true, true]).unwrap();
+        assert_eq!(closed_classes(&[maj.clone(), connectives("not")[0].clone()]), [PostClass::SelfDual]);
+        assert!(is_functionally_complete(&[maj, connectives("not")[0].clone(), connectives("0")[0].clone()]));
+        assert!(BooleanFunction::new(2, vec![true]).is_err());
+        assert!(BooleanFunction::from_table(vec![true, false, true]).is_err());
+    }
+
+    #[test]
+    fn expressions() {
+        let classes = |s: &str| Evaluator::new(Tokens::from_text(s)).unwrap().post_classes().unwrap();
+        assert_eq!(classes("maj(a, b, c)"), [PostClass::PreservesFalse, PostClass::PreservesTrue, PostClass::SelfDual, PostClass::Monotone]);
+        assert_eq!(classes("xor(a, b, c)"), [PostClass::PreservesFalse, PostClass::PreservesTrue, PostClass::SelfDual, PostClass::Affine]);
+        assert_eq!(classes("p and not p"), [PostClass::PreservesFalse, PostClass::Monotone, PostClass::Affine]);
+    }
+}
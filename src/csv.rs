@@ -1,11 +1,22 @@
 use std::fs::File;
 use crate::evaluator_result::EvaluatorResult;
+use crate::render::RenderOptions;
 
 #[allow(dead_code)]
 impl EvaluatorResult {
+    pub fn to_csv(&self, options: &RenderOptions) -> String {
+        let mut out = Vec::new();
+        self.get_table(options).to_csv(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
     pub fn save_to_csv(&self,file_name: &str) {
-        let table = self.get_table();
+        self.save_to_csv_with_options(file_name, &RenderOptions::default());
+    }
+
+    pub fn save_to_csv_with_options(&self,file_name: &str, options: &RenderOptions) {
+        let table = self.get_table(options);
         let mut out = File::create(file_name).unwrap();
         table.to_csv(&mut out).unwrap();
     }
-}
\ No newline at end of file
+}
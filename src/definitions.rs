@@ -0,0 +1,236 @@
+use indexmap::{IndexMap, IndexSet};
+
+use crate::evaluator::{Evaluator, EvaluatorError};
+use crate::evaluator_result::EvaluatorResult;
+use crate::tokenizer::{Token, Tokens};
+
+/// Named formulas written `name := EXPR`, e.g. `safe := door_closed and not alarm`.
+/// A defined name used in an expression is replaced by its formula in parentheses. Definitions
+/// can use names that are defined later, but not themselves (directly or through other names).
+#[derive(Debug, Clone, Default)]
+pub struct Definitions {
+    definitions: IndexMap<String, Tokens>,
+}
+
+impl Definitions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// reads one definition per line, empty lines and lines starting with `#` are skipped.
+    /// Error positions are byte offsets in `text`.
+    pub fn from_text(text: &str) -> Result<Self, EvaluatorError> {
+        let mut definitions = Self::new();
+        let mut offset = 0;
+        for line in text.split_inclusive('\n') {
+            let trimmed = line.trim();
+            if !trimmed.is_empty() && !trimmed.starts_with('#') {
+                definitions.define(Tokens::from_text(line).shifted(offset))?;
+            }
+            offset += line.len();
+        }
+        Ok(definitions)
+    }
+
+    pub fn from_file(file_name: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self::from_text(&std::fs::read_to_string(file_name)?)?)
+    }
+
+    /// whether the tokens start with `name :=`.
+    pub fn is_definition(tokens: &[Token]) -> bool {
+        matches!(tokens, [Token::Ident(_), Token::Define, ..])
+    }
+
+    /// adds the definition `name := EXPR`, replacing an older one with the same name, and
+    /// returns the name.
+    pub fn define(&mut self, tokens: Tokens) -> Result<String, EvaluatorError> {
+        if let Some(span) = tokens.unknown().first() {
+            return Err(EvaluatorError::at("Unknown symbol.", Some(span.start)));
+        }
+        let Some(Token::Ident(name)) = tokens.first().filter(|_| Self::is_definition(&tokens)) else {
+            return Err(EvaluatorError::at("Expected 'name := expression'.", tokens.span(0).map(|s| s.start)));
+        };
+        let body = tokens.slice(2..tokens.len());
+        if body.is_empty() {
+            let end = tokens.span(1).map(|s| s.end);
+            return Err(EvaluatorError::at("Missing the formula after ':='.", end));
+        }
+        Evaluator::new(body.clone())?;
+        self.expand_with(&body, &mut vec![name.clone()])?;
+        self.definitions.insert(name.clone(), body);
+        Ok(name.clone())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Tokens> {
+        self.definitions.get(name)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Tokens> {
+        self.definitions.shift_remove(name)
+    }
+
+    /// the definitions in the order they were made.
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Tokens)> {
+        self.definitions.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.definitions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.definitions.is_empty()
+    }
+
+    /// the tokens with the defined names replaced by their formulas. The tokens of a formula
+    /// get the span of the name they replace.
+    pub fn expand(&self, tokens: &Tokens) -> Result<Tokens, EvaluatorError> {
+        self.expand_with(tokens, &mut Vec::new())
+    }
+
+    /// the expression with the definitions expanded, ready to be evaluated.
+    pub fn compile(&self, tokens: &Tokens) -> Result<Evaluator, EvaluatorError> {
+        Evaluator::new(self.expand(tokens)?)
+    }
+
+    /// the defined names the tokens use, directly or through other definitions, in the order
+    /// they appear in the expansion.
+    pub fn used(&self, tokens: &[Token]) -> Vec<String> {
+        let mut used = IndexSet::new();
+        let mut pending: Vec<&Token> = tokens.iter().rev().collect();
+        while let Some(token) = pending.pop() {
+            if let Token::Ident(name) = token {
+                if let Some(body) = self.definitions.get(name) {
+                    if used.insert(name.clone()) {
+                        pending.extend(body.iter().rev());
+                    }
+                }
+            }
+        }
+        used.into_iter().collect()
+    }
+
+    /// the truth table of the expression. With `named_columns` the value of each definition it
+    /// uses is added as a column named after it, right after the variables.
+    pub fn evaluate_all(&self, tokens: &Tokens, named_columns: bool) -> Result<EvaluatorResult, EvaluatorError> {
+        let evaluator = self.compile(tokens)?;
+        let mut result = evaluator.evaluate_all()?;
+        if !named_columns {
+            return Ok(result);
+        }
+        let n = evaluator.variables().len();
+        let columns = self
+            .used(tokens)
+            .into_iter()
+            .map(|name| Ok((self.compile(&self.definitions[&name])?, name)))
+            .collect::<Result<Vec<_>, EvaluatorError>>()?;
+        for row in result.result.iter_mut() {
+            let values: IndexMap<String, bool> = row.iter().take(n).map(|(k, v)| (k.clone(), *v)).collect();
+            for (k, (definition, name)) in columns.iter().enumerate() {
+                let value = definition.value(&values)?;
+                row.insert_before(n + k, name.clone(), value);
+            }
+        }
+        Ok(result)
+    }
+
+    /// expands with `stack` holding the names being expanded, to detect cycles.
+    fn expand_with(&self, tokens: &Tokens, stack: &mut Vec<String>) -> Result<Tokens, EvaluatorError> {
+        if let Some(span) = tokens.unknown().first() {
+            return Err(EvaluatorError::at("Unknown symbol.", Some(span.start)));
+        }
+        let mut expanded = Vec::new();
+        let mut spans = Vec::new();
+        for (i, token) in tokens.iter().enumerate() {
+            let span = tokens.span(i);
+            self.push(token, stack, &mut expanded, span.as_ref().map(|s| s.start))?;
+            spans.resize(expanded.len(), span.unwrap_or_default());
+        }
+        if tokens.spans().is_empty() {
+            return Ok(Tokens::from(expanded));
+        }
+        Ok(Tokens::with_spans(expanded, spans))
+    }
+
+    fn push(
+        &self,
+        token: &Token,
+        stack: &mut Vec<String>,
+        expanded: &mut Vec<Token>,
+        position: Option<usize>,
+    ) -> Result<(), EvaluatorError> {
+        let Token::Ident(name) = token else {
+            expanded.push(token.clone());
+            return Ok(());
+        };
+        if let Some(i) = stack.iter().position(|n| n == name) {
+            let cycle: Vec<&str> = stack[i..].iter().chain([name]).map(|n| n.as_str()).collect();
+            return Err(EvaluatorError::at(format!("Cyclic definition: {}.", cycle.join(" → ")), position));
+        }
+        let Some(body) = self.definitions.get(name) else {
+            expanded.push(token.clone());
+            return Ok(());
+        };
+        stack.push(name.clone());
+        expanded.push(Token::OpenParen);
+        for t in body.iter() {
+            self.push(t, stack, expanded, position)?;
+        }
+        expanded.push(Token::CloseParen);
+        stack.pop();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expansion() {
+        let definitions = Definitions::from_text("safe := door_closed and not alarm\n\n# ready\nready := safe or override\n").unwrap();
+        let tokens = Tokens::from_text("ready -> safe");
+        assert_eq!(definitions.used(&tokens), vec!["ready", "safe"]);
+        let evaluator = definitions.compile(&tokens).unwrap();
+        assert_eq!(evaluator.variables(), vec!["door_closed", "alarm", "override"]);
+        let expected = Evaluator::new(Tokens::from_text(
+            "((door_closed and not alarm) or override) -> (door_closed and not alarm)",
+        ))
+        .unwrap();
+        assert!(evaluator.is_equivalent(&expected).unwrap());
+        //the expanded tokens keep the position of the name they come from.
+        let expanded = definitions.expand(&tokens).unwrap();
+        assert_eq!(expanded.span(1), Some(0..5));
+        assert_eq!(expanded.span(expanded.len() - 1), Some(9..13));
+    }
+
+    #[test]
+    fn cycles() {
+        let mut definitions = Definitions::new();
+        definitions.define(Tokens::from_text("a := b and p")).unwrap();
+        let error = definitions.define(Tokens::from_text("b := not a")).err().unwrap();
+        assert_eq!(error.message(), "Cyclic definition: b → a → b.");
+        assert_eq!(error.position(), Some(9));
+        assert!(definitions.define(Tokens::from_text("c := c or p")).is_err());
+        assert!(definitions.define(Tokens::from_text("d :=")).is_err());
+        assert!(definitions.define(Tokens::from_text("e := p := q")).is_err());
+        assert!(definitions.get("b").is_none());
+        let error = Definitions::from_text("x := p\ny := (x or q\n").err().unwrap();
+        assert_eq!(error.position(), Some(12));
+    }
+
+    #[test]
+    fn named_columns() {
+        let definitions = Definitions::from_text("safe := door_closed and not alarm").unwrap();
+        let tokens = Tokens::from_text("safe -> ready");
+        let result = definitions.evaluate_all(&tokens, true).unwrap();
+        let columns: Vec<&String> = result.result[0].keys().collect();
+        assert_eq!(columns[..4], ["door_closed", "alarm", "ready", "safe"]);
+        assert_eq!(columns.last().unwrap().as_str(), "((door_closed ∧ ¬alarm) → ready)");
+        for row in result.result.iter() {
+            assert_eq!(row["safe"], row["door_closed"] && !row["alarm"]);
+        }
+        let plain = definitions.evaluate_all(&tokens, false).unwrap();
+        assert!(!plain.result[0].contains_key("safe"));
+    }
+}
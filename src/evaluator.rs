@@ -1,61 +1,127 @@
 use std::collections::{HashSet, VecDeque};
 use indexmap::{IndexMap, IndexSet};
-use crate::{evaluator_result::EvaluatorResult, tokenizer::{Token, Tokens}};
+use crate::{evaluator_result::EvaluatorResult, tokenizer::{Function, Temporal, Token, Tokens}};
 use std::fmt;
 
 
 #[derive(Debug)]
 pub struct EvaluatorError {
     message: String,
+    position: Option<usize>,
 }
+impl EvaluatorError {
+    pub(crate) fn new(message: impl Into<String>) -> Self {
+        EvaluatorError { message: message.into(), position: None }
+    }
+
+    pub(crate) fn at(message: impl Into<String>, position: Option<usize>) -> Self {
+        EvaluatorError { message: message.into(), position }
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// byte offset of the error in the text given to `Tokens::from_text`, when it is known.
+    pub fn position(&self) -> Option<usize> {
+        self.position
+    }
+
+    /// the message followed by `text` with a caret under the position of the error.
+    pub fn annotate(&self, text: &str) -> String {
+        match self.position.filter(|p| *p <= text.len() && text.is_char_boundary(*p)) {
+            Some(p) => format!("{}\n  {}\n  {}^", self.message, text, " ".repeat(text[..p].chars().count())),
+            None => self.message.clone(),
+        }
+    }
+}
+
+impl std::error::Error for EvaluatorError {}
+
 impl fmt::Display for EvaluatorError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", self.message)
     }
 }
 
+/// A validated expression, ready to be evaluated.
+/// With the `serde` feature it is (de)serialized as its tokens; deserializing validates them again.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "Tokens", try_from = "Tokens"))]
 pub struct Evaluator {
    tokens: Tokens,
-   idents: HashSet<char>
+   idents: HashSet<String>
+}
+
+impl From<Evaluator> for Tokens {
+    /// the tokens of the expression, without the enclosing parentheses added by `Evaluator::new`.
+    fn from(evaluator: Evaluator) -> Self {
+        let tokens: &[Token] = &evaluator.tokens;
+        Tokens::from(tokens[1..tokens.len() - 1].to_vec())
+    }
+}
+
+impl TryFrom<Tokens> for Evaluator {
+    type Error = EvaluatorError;
+    fn try_from(tokens: Tokens) -> Result<Self, Self::Error> {
+        Evaluator::new(tokens)
+    }
 }
 
 impl Evaluator {
     pub fn new(tokens: Tokens)-> Result<Self,EvaluatorError> {
+        let position = |i: usize| tokens.span(i).map(|s| s.start);
+        for (i, token) in tokens.iter().enumerate() {
+            let message = match token {
+                Token::Necessarily(_) | Token::Possibly(_) => "a modal operator, it is evaluated at the worlds of a Kripke model",
+                Token::Temporal(_) => "a temporal operator, it is evaluated at the steps of a trace",
+                _ => continue,
+            };
+            return Err(EvaluatorError::at(format!("'{}' is {}.", token, message), position(i)));
+        }
+        Self::with_modalities(tokens)
+    }
+
+    /// like `new`, but the modal and temporal operators are accepted. Only `modal` and `ltl` give
+    /// them a meaning.
+    pub(crate) fn with_modalities(tokens: Tokens)-> Result<Self,EvaluatorError> {
         //do some validation
         let mut tokens = tokens;
-        fn check_enclosing(tkns: &[Token], left: Token, right: Token)-> Result<(),EvaluatorError> {
-            let mut p_count = 0;
-            for t in tkns {
+        fn check_enclosing(tokens: &Tokens, left: Token, right: Token)-> Result<(),EvaluatorError> {
+            let mut open = Vec::new();
+            for (i, t) in tokens.iter().enumerate() {
                 if t == &left {
-                    p_count +=1;
-                }else if t == &right {
-                    p_count -=1;
-                }
-                
-                if p_count < 0 {
-                    break;
+                    open.push(i);
+                }else if t == &right && open.pop().is_none() {
+                    return Err(EvaluatorError::at("mis-match parentheses", tokens.span(i).map(|s| s.start)));
                 }
             }
-            if p_count != 0 {
-                return  Err(EvaluatorError { message: "mis-match parentheses".into() });
+            if let Some(i) = open.pop() {
+                return Err(EvaluatorError::at("mis-match parentheses", tokens.span(i).map(|s| s.start)));
             }
             Ok(())
         }
+        if let Some(span) = tokens.unknown().first() {
+            return Err(EvaluatorError::at("Unknown symbol.", Some(span.start)));
+        }
         let tkns: &[Token] = &tokens;
-        if tkns.len() == 0 {
-            return  Err(EvaluatorError { message: "Empty expression.".into() });
+        if tkns.is_empty() {
+            return  Err(EvaluatorError::new("Empty expression."));
         }
 
-        check_enclosing(tkns, Token::OpenParen, Token::CloseParen)?;
-        check_enclosing(tkns, Token::OpenBracket, Token::CloseBracket)?;
-        check_enclosing(tkns, Token::OpenCurlyBrace, Token::CloseCurlyBrace)?;
+        mark_functions(&mut tokens);
+        check_enclosing(&tokens, Token::OpenParen, Token::CloseParen)?;
+        check_enclosing(&tokens, Token::OpenBracket, Token::CloseBracket)?;
+        check_enclosing(&tokens, Token::OpenCurlyBrace, Token::CloseCurlyBrace)?;
+        check_syntax(&tokens)?;
         
         tokens.enclose(Token::OpenParen, Token::CloseParen);
 
-        let idents: HashSet<char> = tokens.iter().filter_map(|t| {
+        let idents: HashSet<String> = tokens.iter().filter_map(|t| {
             match t {
-                Token::Ident(chr)=> {
-                    return Some(*chr);
+                Token::Ident(name)=> {
+                    Some(name.clone())
                 }
                 _ => None
             }
@@ -64,161 +130,211 @@ impl Evaluator {
     }
 }
 
-impl Evaluator {
-    pub fn evaluate(&self, values: &IndexMap<char,bool>) -> Result<IndexMap<String, bool>, EvaluatorError> {
-        //validate values
-        if !self.idents.iter().all(|x| values.contains_key(x)) {
-            return Err(EvaluatorError{message: "Please provide value for all idents.".into()});
+/// reads `xor` as the function `Function::Xor` where it starts an operand and is followed by `(`.
+fn mark_functions(tokens: &mut Tokens) {
+    for i in 0..tokens.len() {
+        let starts_operand = i == 0 || !matches!(
+            tokens[i - 1],
+            Token::Ident(_) | Token::True | Token::False | Token::Number(_) |
+            Token::CloseParen | Token::CloseBracket | Token::CloseCurlyBrace
+        );
+        if starts_operand && matches!(tokens[i], Token::XOr(_)) && tokens.get(i + 1) == Some(&Token::OpenParen) {
+            tokens[i] = Token::Function(Function::Xor);
         }
-        let mut operators_stack = VecDeque::<Token>::new();
-        let mut operands_stack = VecDeque::<(Option<String>,Token)>::new();
-        let mut result: IndexMap<String,bool> = IndexMap::new();
-        let tokens: &[Token] = &self.tokens;
-        for token in tokens {
-            match token {
-                Token::Ident(chr) => {
-                     let v = values.get(chr).unwrap();
-                     let v = if *v {
-                        Token::True
-                    }else {
-                        Token::False
-                    };
-                    operands_stack.push_front((Some(chr.to_string()),v));
-                },
-                Token::OpenParen | Token::OpenBracket | Token::OpenCurlyBrace => {
-                    operators_stack.push_front(token.clone());
-                },
-                Token::CloseParen | Token::CloseBracket | Token::CloseCurlyBrace => {
-                  while let Some(op) = operators_stack.pop_front() {
-                      if op == Token::OpenParen || 
-                         op == Token::OpenBracket || 
-                         op == Token::OpenCurlyBrace {break;}
-                      Self::evaluate_operator(op, &mut operands_stack);
-                      let res = operands_stack.front().unwrap().clone();
-                      if let Some(name) = res.0 {
-                        result.insert(name, res.1.into());
-                      }
-                  }  
-                },
-                Token::False => operands_stack.push_front((Some("false".into()),token.clone())),
-                Token::True => operands_stack.push_front((Some("true".into()),token.clone())),
-                _ => {
-                    while operators_stack.len() > 0 && (get_priority(&operators_stack.front().unwrap())<=get_priority(&token)) {
-                        Self::evaluate_operator(operators_stack.pop_front().unwrap(),&mut operands_stack);
-                        let res = operands_stack.front().unwrap().clone();
-                        if let Some(name) = res.0 {
-                            result.insert(name, res.1.into());
-                        }
-                    }
-                    operators_stack.push_front(token.clone());
-                }
+    }
+}
+
+/// checks that operators and operands alternate, so that evaluation never runs out of operands,
+/// and that functions are called with the right arguments.
+fn check_syntax(tokens: &Tokens) -> Result<(), EvaluatorError> {
+    let mut expect_operand = true;
+    //for each open bracket, the function it calls with its position and number of arguments.
+    let mut brackets: Vec<Option<(Function, Option<usize>, usize)>> = Vec::new();
+    //the function before the current token, and whether the token is the count of a call.
+    let mut called: Option<(Function, Option<usize>)> = None;
+    let mut count_expected = false;
+    //the function whose count was the token before, the count is its own argument.
+    let mut counted: Option<Function> = None;
+    for (i, t) in tokens.iter().enumerate() {
+        let position = tokens.span(i).map(|s| s.start);
+        if let Some(function) = counted.take() {
+            if !matches!(t, Token::Comma | Token::CloseParen) {
+                return Err(EvaluatorError::at(format!("Expected ',' after the count of '{}'.", function.name()), position));
             }
         }
-        Ok(result)
-    }
-    fn evaluate_operator(operator: Token, operands_stack: &mut VecDeque::<(Option<String>,Token)>){
-        
-        let op_symbol: String;
-        let ev_result: Token;
-        let opnd_count = get_operands_count(&operator);
-        let opnd1_ =  operands_stack.pop_front().unwrap();
-        let opnd1: bool = opnd1_.1.into();
-        let mut opnd2_: (Option<String>, Token) = (None,Token::False);
-        let mut opnd2 = false;
-        if opnd_count == 2 {
-            opnd2_ = operands_stack.pop_front().unwrap();
-            opnd2 = opnd2_.1.into();
+        if let Some((function, at)) = called.take() {
+            if *t != Token::OpenParen {
+                return Err(EvaluatorError::at(format!("Expected '(' after '{}'.", function.name()), position));
+            }
+            brackets.push(Some((function, at, 1)));
+            count_expected = function.takes_count();
+            continue;
         }
-        
-        match operator {
-            Token::Not(symbol) => {
-                ev_result = Token::from(!opnd1);
-                op_symbol = symbol.into();
-            },
-            Token::Implication(symbol) =>  {
-                let opnd = if !opnd2 {
-                    true
-                }else {
-                    opnd1
-                };
-                ev_result = Token::from(opnd);
-                op_symbol = symbol.into();
-            },
-            Token::Biconditional(symbol) =>  {
-                let opnd = if (opnd1 && opnd2) || (!opnd1 && !opnd2) {
-                    true
-                }else {
-                    false
-                };
-                ev_result = Token::from(opnd);
-                op_symbol = symbol.into();
-            },
-            Token::And(symbol) => {
-                let opnd = opnd1 && opnd2;
-                ev_result = Token::from(opnd);
-                op_symbol = symbol.into();
+        if count_expected {
+            count_expected = false;
+            let function = brackets.last().copied().flatten().map(|b| b.0).expect("the call of the count");
+            if !matches!(t, Token::Number(_) | Token::True | Token::False) {
+                return Err(EvaluatorError::at(format!("Expected a number as the first argument of '{}'.", function.name()), position));
+            }
+            counted = Some(function);
+            expect_operand = false;
+            continue;
+        }
+        match t {
+            Token::Ident(_) | Token::True | Token::False |
+            Token::OpenParen | Token::OpenBracket | Token::OpenCurlyBrace |
+            Token::Not(_) | Token::Necessarily(_) | Token::Possibly(_) | Token::Function(_) |
+            Token::Temporal(Temporal::Next | Temporal::Eventually | Temporal::Always) => {
+                if !expect_operand {
+                    return Err(EvaluatorError::at(format!("Missing operator before '{}'.", t), position));
+                }
+                match t {
+                    Token::Function(function) => called = Some((*function, position)),
+                    Token::OpenParen | Token::OpenBracket | Token::OpenCurlyBrace => brackets.push(None),
+                    _ => {}
+                }
+                expect_operand = !matches!(t, Token::Ident(_) | Token::True | Token::False);
             },
-            Token::Or(symbol) => {
-                let opnd = opnd1 || opnd2;
-                ev_result = Token::from(opnd);
-                op_symbol = symbol.into();
+            Token::Number(n) => {
+                return Err(EvaluatorError::at(
+                    format!("Unexpected number '{}', numbers are only the first argument of exactly, atmost and atleast.", n),
+                    position,
+                ));
             },
-            Token::XOr(symbol) => {
-                let opnd = (opnd1 && !opnd2) || (!opnd1 && opnd2);
-                ev_result = Token::from(opnd);
-                op_symbol = symbol.into();
+            //definitions are read before the expression is built, see `Definitions::define`.
+            Token::Define => {
+                return Err(EvaluatorError::at("Unexpected ':=', a definition must be a line of its own.", position));
             },
-            Token::Equals(symbol) => {
-                let opnd = (opnd1 && opnd2) || (!opnd1 && !opnd2);
-                ev_result = Token::from(opnd);
-                op_symbol = symbol.to_string();
+            Token::Comma => {
+                if expect_operand {
+                    return Err(EvaluatorError::at(format!("Missing operand before '{}'.", t), position));
+                }
+                match brackets.last_mut() {
+                    Some(Some((_, _, arguments))) => *arguments += 1,
+                    _ => return Err(EvaluatorError::at("',' can only separate the arguments of a function.", position)),
+                }
+                expect_operand = true;
             },
-            Token::NotEquals(symbol) => {
-                let opnd = (!opnd1 && opnd2) || (opnd1 && !opnd2);
-                ev_result = Token::from(opnd);
-                op_symbol = symbol.into();
+            Token::CloseParen | Token::CloseBracket | Token::CloseCurlyBrace => {
+                if expect_operand {
+                    return Err(EvaluatorError::at(format!("Missing operand before '{}'.", t), position));
+                }
+                if let Some(Some((function, at, arguments))) = brackets.pop() {
+                    let (least, most) = function.arity();
+                    if arguments < least || most.is_some_and(|most| arguments > most) {
+                        let expected = match most {
+                            Some(most) if most == least => format!("{}", least),
+                            _ => format!("at least {}", least),
+                        };
+                        return Err(EvaluatorError::at(
+                            format!("'{}' takes {} arguments, not {}.", function.name(), expected, arguments),
+                            at,
+                        ));
+                    }
+                }
             },
-            _ => todo!(),
-        }
-        
-        if opnd_count == 2 {
-            if let (Some(nm1),Some(nm2)) = (opnd1_.0,opnd2_.0) {
-                let name = format!("({} {} {})",nm2,op_symbol,nm1);
-                operands_stack.push_front((Some(name),ev_result));
-            }else {
-                operands_stack.push_front((Some("?".into()),ev_result));
-            }
-        }else {
-            if let Some(nm) = opnd1_.0 {
-                let name = format!("{}{}",op_symbol,nm);
-                operands_stack.push_front((Some(name),ev_result));
-            }else {
-                operands_stack.push_front((Some("?".into()),ev_result));
+            _ => {
+                if expect_operand {
+                    return Err(EvaluatorError::at(format!("Missing operand before '{}'.", t), position));
+                }
+                expect_operand = true;
             }
         }
     }
+    if expect_operand || called.is_some() || count_expected {
+        let end = tokens.spans().last().map(|s| s.end);
+        return Err(EvaluatorError::at("Incomplete expression, missing operand at the end.", end));
+    }
+    Ok(())
+}
 
-    pub fn evaluate_all(&self)-> Result<EvaluatorResult,EvaluatorError> {
-        let mut result: Vec<IndexMap<String, bool>> = Vec::new();
+impl Evaluator {
+    /// the variables of the expression in the order of their first appearance.
+    pub fn variables(&self) -> Vec<String> {
         let tokens: &[Token] = &self.tokens;
-        let operands: Vec<char> = tokens.iter().filter_map(|t| {
+        tokens.iter().filter_map(|t| {
             match t {
-                Token::Ident(chr)=> {
-                    return Some(*chr);
+                Token::Ident(name)=> {
+                    Some(name.clone())
                 }
                 _ => None
             }
         }).collect::<IndexSet<_>>()
         .into_iter()
-        .collect();
+        .collect()
+    }
+
+    pub fn evaluate(&self, values: &IndexMap<String,bool>) -> Result<IndexMap<String, bool>, EvaluatorError> {
+        self.evaluate_with(values, |value| value, |operator, count, values| {
+            let mut operands: Vec<Token> = values.iter().map(|v| Token::from(*v)).collect();
+            if let Token::Function(function) = operator {
+                if function.takes_count() {
+                    operands.insert(0, Token::Number(count));
+                }
+            }
+            apply(operator, &operands)
+        })
+    }
+    /// the value of the whole expression.
+    pub fn value(&self, values: &IndexMap<String,bool>) -> Result<bool, EvaluatorError> {
+        let result = self.evaluate(values)?;
+        Ok(result.last().map(|x| *x.1).unwrap_or_default())
+    }
+
+    /// evaluates every operation of the expression with the values of any logic, each column
+    /// named as in `evaluate`. `constant` gives the value of `true` and `false` and `operation`
+    /// applies an operator or a function to the values of its operands. The count of `exactly`,
+    /// `atmost` and `atleast` is given apart and is not one of the values.
+    pub(crate) fn evaluate_with<V: Copy>(
+        &self,
+        values: &IndexMap<String, V>,
+        constant: impl Fn(bool) -> V,
+        mut operation: impl FnMut(&Token, usize, &[V]) -> V,
+    ) -> Result<IndexMap<String, V>, EvaluatorError> {
+        //validate values
+        if !self.idents.iter().all(|x| values.contains_key(x)) {
+            return Err(EvaluatorError::new("Please provide value for all idents."));
+        }
+        let mut result: IndexMap<String, V> = IndexMap::new();
+        let operand = |token: &Token| match token {
+            Token::Ident(name) => Column { name: Some(name.clone()), value: values[name], count: None },
+            Token::True => Column { name: Some("true".into()), value: constant(true), count: Some(1) },
+            Token::False => Column { name: Some("false".into()), value: constant(false), count: Some(0) },
+            Token::Number(n) => Column { name: Some(n.to_string()), value: constant(false), count: Some(*n) },
+            _ => Column { name: None, value: constant(false), count: None },
+        };
+        let last = fold(&self.tokens, operand, |operator, mut operands| {
+            let takes_count = matches!(&operator, Token::Function(f) if f.takes_count());
+            let mut count = 0;
+            //`1` reads as `true`, the count keeps its number.
+            if takes_count {
+                count = operands[0].count.unwrap_or_default();
+                operands[0].name = Some(count.to_string());
+            }
+            let names: Option<Vec<String>> = operands.iter().map(|o| o.name.clone()).collect();
+            let arguments: Vec<V> = operands[usize::from(takes_count)..].iter().map(|o| o.value).collect();
+            let value = operation(&operator, count, &arguments);
+            let name = column_name(&operator, names);
+            result.insert(name.clone(), value);
+            Column { name: Some(name), value, count: None }
+        });
+        //a single variable or constant has no operator column, its value is the result.
+        if result.is_empty() {
+            if let Some(Column { name: Some(name), value, .. }) = last {
+                result.insert(name, value);
+            }
+        }
+        Ok(result)
+    }
+
+    pub fn evaluate_all(&self)-> Result<EvaluatorResult,EvaluatorError> {
+        let mut result: Vec<IndexMap<String, bool>> = Vec::new();
+        let operands = self.variables();
         let n = operands.len();
         let rows: u64 = 1 << n;//2^n
-        for i in 0..rows {
-            let mut row: IndexMap<char,bool> = IndexMap::new();
-            for j in (0..n).rev() {
-                let v = ((i >> j) & 1) != 0;
-                row.insert(operands[n - j - 1], !v);
-            }
+        //rows start with every variable true, see `assignment`.
+        for i in (0..rows).rev() {
+            let row = assignment(&operands, i);
             if let Ok(mut eval) = self.evaluate(&row){
                 for r in row.iter().rev() {
                     eval.insert_before(0, r.0.to_string(), *r.1);
@@ -230,45 +346,206 @@ impl Evaluator {
     }
 }
 
+/// A column of a table being evaluated. Numbers and constants also carry the count they stand
+/// for as the first argument of `exactly`, `atmost` and `atleast`.
+struct Column<V> {
+    name: Option<String>,
+    value: V,
+    count: Option<usize>,
+}
+
+/// the name of the column of an operator or a function applied to operands named `names`:
+/// `f(a, b)`, `¬a` or `(a ∧ b)`.
+pub(crate) fn column_name(operator: &Token, names: Option<Vec<String>>) -> String {
+    match names {
+        Some(names) => match (operator, names.as_slice()) {
+            (Token::Function(function), _) => format!("{}({})", function.name(), names.join(", ")),
+            (Token::Temporal(_), [operand]) => format!("{} {}", operator, operand),
+            (_, [operand]) => format!("{}{}", operator, operand),
+            (_, [left, right]) => format!("({} {} {})", left, operator, right),
+            _ => "?".into(),
+        },
+        None => "?".into(),
+    }
+}
+
+fn is_open(token: &Token) -> bool {
+    matches!(token, Token::OpenParen | Token::OpenBracket | Token::OpenCurlyBrace)
+}
+
+/// runs the shunting-yard algorithm over validated tokens. `operand` gives the value of an
+/// identifier, a constant or a count, and `combine` the value of an operator or a function
+/// applied to its operands, from left to right. Returns the value of the whole expression.
+pub(crate) fn fold<T>(
+    tokens: &[Token],
+    mut operand: impl FnMut(&Token) -> T,
+    mut combine: impl FnMut(Token, Vec<T>) -> T,
+) -> Option<T> {
+    let mut operators_stack = VecDeque::<Token>::new();
+    let mut operands_stack = VecDeque::<T>::new();
+    //the number of arguments of the function calls that are open.
+    let mut calls = Vec::<usize>::new();
+    let mut reduce = |operator: Token, count: usize, operands_stack: &mut VecDeque<T>| {
+        let mut operands: Vec<T> = (0..count).map(|_| operands_stack.pop_front().unwrap()).collect();
+        operands.reverse();
+        let value = combine(operator, operands);
+        operands_stack.push_front(value);
+    };
+    for token in tokens {
+        match token {
+            Token::Ident(_) | Token::True | Token::False | Token::Number(_) => {
+                operands_stack.push_front(operand(token));
+            },
+            Token::OpenParen | Token::OpenBracket | Token::OpenCurlyBrace => {
+                if let Some(Token::Function(_)) = operators_stack.front() {
+                    calls.push(1);
+                }
+                operators_stack.push_front(token.clone());
+            },
+            Token::CloseParen | Token::CloseBracket | Token::CloseCurlyBrace | Token::Comma => {
+                while let Some(op) = operators_stack.front() {
+                    if is_open(op) {break;}
+                    let op = operators_stack.pop_front().unwrap();
+                    let count = get_operands_count(&op);
+                    reduce(op, count, &mut operands_stack);
+                }
+                if *token == Token::Comma {
+                    if let Some(count) = calls.last_mut() {
+                        *count += 1;
+                    }
+                    continue;
+                }
+                operators_stack.pop_front();
+                if let Some(Token::Function(_)) = operators_stack.front() {
+                    let function = operators_stack.pop_front().unwrap();
+                    reduce(function, calls.pop().unwrap_or(1), &mut operands_stack);
+                }
+            },
+            //prefix operators apply to what follows, so they must not evaluate the operators before them.
+            Token::Not(_) | Token::Necessarily(_) | Token::Possibly(_) | Token::Function(_) |
+            Token::Temporal(Temporal::Next | Temporal::Eventually | Temporal::Always) => {
+                operators_stack.push_front(token.clone())
+            }
+            _ => {
+                while !operators_stack.is_empty() && (get_priority(operators_stack.front().unwrap())<=get_priority(token)) {
+                    let op = operators_stack.pop_front().unwrap();
+                    let count = get_operands_count(&op);
+                    reduce(op, count, &mut operands_stack);
+                }
+                operators_stack.push_front(token.clone());
+            }
+        }
+    }
+    //without enclosing parentheses the last operators are still waiting.
+    while let Some(op) = operators_stack.pop_front() {
+        let count = get_operands_count(&op);
+        reduce(op, count, &mut operands_stack);
+    }
+    operands_stack.pop_front()
+}
+
+/// the values of `variables` for the row `index` of a truth table. The first variable is the most
+/// significant bit of `index` and a set bit means true, so `evaluate_all` (which starts with
+/// every variable true) lists the rows from index `2^n - 1` down to `0`.
+pub(crate) fn assignment(variables: &[String], index: u64) -> IndexMap<String,bool> {
+    let n = variables.len();
+    variables.iter().enumerate().map(|(k, v)| (v.clone(), (index >> (n - k - 1)) & 1 == 1)).collect()
+}
+
+/// the value of an operator or a function for its operands, from left to right. The operands
+/// are `True`, `False` or, for the count of `exactly`, `atmost` and `atleast`, a `Number`.
+pub(crate) fn apply(operator: &Token, operands: &[Token]) -> bool {
+    let values: Vec<bool> = operands.iter().map(|t| *t == Token::True).collect();
+    match (operator, values.as_slice()) {
+        (Token::Not(_), [a]) => !a,
+        (Token::And(_), [a, b]) => *a && *b,
+        (Token::Nand(_), [a, b]) => !(*a && *b),
+        (Token::Or(_), [a, b]) => *a || *b,
+        (Token::Nor(_), [a, b]) => !(*a || *b),
+        (Token::XOr(_) | Token::NotEquals(_), [a, b]) => a != b,
+        (Token::Implication(_), [a, b]) => !a || *b,
+        (Token::Converse(_), [a, b]) => *a || !b,
+        (Token::NonImplication(_), [a, b]) => *a && !b,
+        (Token::Biconditional(_) | Token::Equals(_), [a, b]) => a == b,
+        (Token::Function(function), _) => {
+            let true_count = |values: &[bool]| values.iter().filter(|v| **v).count();
+            match function {
+                Function::Ite => if values[0] { values[1] } else { values[2] },
+                Function::Maj => true_count(&values) * 2 > values.len(),
+                Function::Xor => true_count(&values) % 2 == 1,
+                Function::Exactly => true_count(&values[1..]) == count_of(&operands[0]),
+                Function::AtMost => true_count(&values[1..]) <= count_of(&operands[0]),
+                Function::AtLeast => true_count(&values[1..]) >= count_of(&operands[0]),
+            }
+        }
+        _ => panic!("{} can not be applied to {} operands", operator, operands.len()),
+    }
+}
+
+/// the number written by a count token, `0` and `1` are lexed as constants.
+fn count_of(token: &Token) -> usize {
+    match token {
+        Token::Number(n) => *n,
+        Token::True => 1,
+        _ => 0,
+    }
+}
 
 fn get_priority(op_token: &Token)-> usize {
     match op_token {
-        Token::Ident(_) => return usize::MAX,
+        Token::Ident(_) | Token::Define => usize::MAX,
         Token::OpenParen | Token::CloseParen |
         Token::OpenBracket | Token::CloseBracket |
-        Token::OpenCurlyBrace | Token::CloseCurlyBrace => return usize::MAX,
-        Token::Not(_) => return  0,
-        Token::Implication(_) => 3,
-        Token::Biconditional(_) => 3,
-        Token::And(_) => return  1,
-        Token::Or(_) => return  2,
-        Token::XOr(_) => return  2,
-        Token::Equals(_) => 4,
-        Token::NotEquals(_) => 5,
-        Token::False => return usize::MAX,
-        Token::True => return usize::MAX,
+        Token::OpenCurlyBrace | Token::CloseCurlyBrace => usize::MAX,
+        Token::Not(_) | Token::Necessarily(_) | Token::Possibly(_) => 0,
+        Token::Temporal(temporal) if temporal.is_unary() => 0,
+        //`a U b ∧ c` is `(a U b) ∧ c`.
+        Token::Temporal(_) => 1,
+        Token::Implication(_) => 4,
+        Token::Converse(_) => 4,
+        Token::NonImplication(_) => 4,
+        Token::Biconditional(_) => 4,
+        Token::And(_) => 2,
+        Token::Nand(_) => 2,
+        Token::Or(_) => 3,
+        Token::Nor(_) => 3,
+        Token::XOr(_) => 3,
+        Token::Equals(_) => 5,
+        Token::NotEquals(_) => 6,
+        Token::False => usize::MAX,
+        Token::True => usize::MAX,
+        Token::Function(_) | Token::Comma | Token::Number(_) => usize::MAX,
     }
 }
 fn get_operands_count(op_token: &Token)-> usize {
     match op_token {
-        Token::Ident(_) => return 0,
+        Token::Ident(_) | Token::Define => 0,
         Token::OpenParen | Token::CloseParen |
         Token::OpenBracket | Token::CloseBracket |
-        Token::OpenCurlyBrace | Token::CloseCurlyBrace => return 0,
-        Token::Not(_) => return  1,
+        Token::OpenCurlyBrace | Token::CloseCurlyBrace => 0,
+        Token::Not(_) | Token::Necessarily(_) | Token::Possibly(_) => 1,
+        Token::Temporal(temporal) if temporal.is_unary() => 1,
+        Token::Temporal(_) => 2,
         Token::Implication(_) => 2,
+        Token::Converse(_) => 2,
+        Token::NonImplication(_) => 2,
         Token::Biconditional(_) => 2,
-        Token::And(_) => return  2,
-        Token::Or(_) => return  2,
-        Token::XOr(_) => return  2,
+        Token::And(_) => 2,
+        Token::Nand(_) => 2,
+        Token::Or(_) => 2,
+        Token::Nor(_) => 2,
+        Token::XOr(_) => 2,
         Token::Equals(_) => 2,
         Token::NotEquals(_) => 2,
-        Token::False => return 0,
-        Token::True => return 0,
+        Token::False => 0,
+        Token::True => 0,
+        //functions take the number of arguments they are called with.
+        Token::Function(_) | Token::Comma | Token::Number(_) => 0,
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::needless_borrow)]
 mod tests {
     use crate::tokenizer::Tokens;
 
@@ -281,8 +558,8 @@ mod tests {
             let expr = format!("{} a",s);
             let tokens = Tokens::from_text(&expr);
             let evaluator = Evaluator::new(tokens).unwrap();
-            let mut values = IndexMap::<char,bool>::new();
-            values.insert('a', true);
+            let mut values = IndexMap::<String,bool>::new();
+            values.insert("a".into(), true);
             let result = evaluator.evaluate(&values).unwrap();
             assert_eq!(
                 result.get("¬a").unwrap(),&false
@@ -292,9 +569,9 @@ mod tests {
     }
 
     fn check(evaluator: &Evaluator, a: bool, b:bool, expect: bool, expr: &str) {
-        let mut values = IndexMap::<char,bool>::new();
-        values.insert('a', a);
-        values.insert('b', b);
+        let mut values = IndexMap::<String,bool>::new();
+        values.insert("a".into(), a);
+        values.insert("b".into(), b);
         let result = evaluator.evaluate(&values).unwrap();
         assert_eq!(
             result.get(expr).unwrap(),&expect
@@ -374,4 +651,69 @@ mod tests {
             check(&evaluator, false, false, true,&expr);
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn nand_nor() {
+        let evaluator = Evaluator::new(Tokens::from_text("a nand b")).unwrap();
+        check(&evaluator, true, true, false, "(a ↑ b)");
+        check(&evaluator, true, false, true, "(a ↑ b)");
+        check(&evaluator, false, false, true, "(a ↑ b)");
+        let evaluator = Evaluator::new(Tokens::from_text("a ↓ b")).unwrap();
+        check(&evaluator, true, false, false, "(a ↓ b)");
+        check(&evaluator, false, true, false, "(a ↓ b)");
+        check(&evaluator, false, false, true, "(a ↓ b)");
+    }
+
+    #[test]
+    fn converse_and_non_implication() {
+        let evaluator = Evaluator::new(Tokens::from_text("a <- b")).unwrap();
+        check(&evaluator, true, true, true, "(a ← b)");
+        check(&evaluator, true, false, true, "(a ← b)");
+        check(&evaluator, false, true, false, "(a ← b)");
+        check(&evaluator, false, false, true, "(a ← b)");
+        let evaluator = Evaluator::new(Tokens::from_text("a ↛ b")).unwrap();
+        check(&evaluator, true, true, false, "(a ↛ b)");
+        check(&evaluator, true, false, true, "(a ↛ b)");
+        check(&evaluator, false, true, false, "(a ↛ b)");
+        check(&evaluator, false, false, false, "(a ↛ b)");
+    }
+
+    fn table(expression: &str) -> Vec<bool> {
+        let evaluator = Evaluator::new(Tokens::from_text(expression)).unwrap();
+        let result = evaluator.evaluate_all().unwrap();
+        result.result.iter().map(|row| *row.last().unwrap().1).collect()
+    }
+
+    #[test]
+    fn functions() {
+        //rows go from every variable true to every variable false.
+        assert_eq!(table("ite(c, a, b)"), table("(c and a) or (not c and b)"));
+        assert_eq!(table("maj(a, b, c)"), table("(a and b) or (a and c) or (b and c)"));
+        assert_eq!(table("xor(a, b, c)"), table("a xor b xor c"));
+        assert_eq!(table("exactly(1, a, b)"), table("a xor b"));
+        assert_eq!(table("atmost(1, a, b, c)"), table("not maj(a, b, c)"));
+        assert_eq!(table("atleast(2, a, b, c)"), table("maj(a, b, c)"));
+        assert_eq!(table("exactly(2, a, b, c, d)").iter().filter(|v| **v).count(), 6);
+        assert_eq!(table("atleast(0, a)"), [true, true]);
+        assert_eq!(table("a xor xor(b, not a and c)"), table("a xor (b xor (not a and c))"));
+        let evaluator = Evaluator::new(Tokens::from_text("not ite(a, b, c) or exactly(1, a, b)")).unwrap();
+        let columns: Vec<String> = evaluator.evaluate_all().unwrap().result[0].keys().cloned().collect();
+        assert_eq!(columns[3..], ["ite(a, b, c)", "¬ite(a, b, c)", "exactly(1, a, b)", "(¬ite(a, b, c) ∨ exactly(1, a, b))"]);
+    }
+
+    #[test]
+    fn function_errors() {
+        let error = |s: &str| Evaluator::new(Tokens::from_text(s)).err().unwrap();
+        assert_eq!(error("ite(a, b)").message(), "'ite' takes 3 arguments, not 2.");
+        assert_eq!(error("p and maj a").position(), Some(10));
+        assert_eq!(error("exactly(a, b)").message(), "Expected a number as the first argument of 'exactly'.");
+        assert!(error("maj(2, a)").message().starts_with("Unexpected number"));
+        assert_eq!(error("(a, b)").message(), "',' can only separate the arguments of a function.");
+        assert!(Evaluator::new(Tokens::from_text("maj(a, b,)")).is_err());
+        assert!(Evaluator::new(Tokens::from_text("exactly(12, a)")).is_ok());
+        assert_eq!(error("exactly(2 and a, b)").message(), "Expected ',' after the count of 'exactly'.");
+        assert_eq!(error("exactly(2 and a, b)").position(), Some(10));
+        assert_eq!(error("atmost(1 b)").message(), "Expected ',' after the count of 'atmost'.");
+        assert_eq!(error("atleast(1)").message(), "'atleast' takes at least 2 arguments, not 1.");
+    }
+}
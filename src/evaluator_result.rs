@@ -2,29 +2,80 @@ use std::fmt;
 use indexmap::IndexMap;
 use prettytable::Table;
 
+use crate::render::RenderOptions;
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EvaluatorResult {
     pub result: Vec<IndexMap<String, bool>>
 }
 
+/// The kind of an expression judged by the last column of its truth table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Classification {
+    /// true in every row.
+    Tautology,
+    /// false in every row.
+    Contradiction,
+    /// true in some rows and false in others.
+    Contingency,
+}
+
+impl fmt::Display for Classification {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Classification::Tautology => write!(f, "tautology"),
+            Classification::Contradiction => write!(f, "contradiction"),
+            Classification::Contingency => write!(f, "contingency"),
+        }
+    }
+}
+
 impl fmt::Display for EvaluatorResult {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let table = self.get_table();
+        let table = self.get_table(&RenderOptions::default());
         table.printstd();
         write!(f, "")
     }
 }
 #[allow(dead_code)]
 impl EvaluatorResult {
-    pub(crate) fn get_table(&self)-> Table {
+    pub fn print_with_options(&self, options: &RenderOptions) {
+        self.get_table(options).printstd();
+    }
+
+    /// the table as printed to the terminal, without colors.
+    pub fn to_plain_text(&self, options: &RenderOptions) -> String {
+        self.get_table(options).to_string()
+    }
+
+    /// the values of the last column, which holds the value of the whole expression.
+    pub fn final_column(&self) -> Vec<bool> {
+        self.result.iter().filter_map(|row| row.last().map(|x| *x.1)).collect()
+    }
+
+    pub fn classification(&self) -> Classification {
+        let column = self.final_column();
+        if column.iter().all(|v| *v) {
+            Classification::Tautology
+        } else if column.iter().all(|v| !*v) {
+            Classification::Contradiction
+        } else {
+            Classification::Contingency
+        }
+    }
+
+    pub(crate) fn get_table(&self, options: &RenderOptions)-> Table {
         let mut table = Table::new();
         if let Some(header) = self.result.first() {
-            let header = header.iter().map(|x| x.0).collect();
+            let header = header.iter().map(|x| options.header(x.0)).collect();
             table.add_row(header);
         }
         for row in self.result.iter() {
-            let values = row.iter().map(|x| x.1.to_string()).collect();
+            let values = row.iter().map(|x| options.value(*x.1)).collect();
             table.add_row(values);
         }
         table
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,225 @@
+use std::fmt;
+
+use crate::evaluator::{apply, fold, Evaluator};
+use crate::tokenizer::{Token, Tokens};
+
+/// An expression as a tree, built from the tokens of an `Evaluator` with the same priorities.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Expression {
+    Constant(bool),
+    Variable(String),
+    /// the count of `exactly`, `atmost` and `atleast`.
+    Count(usize),
+    /// an operator or a function applied to its operands, from left to right.
+    Operation(Token, Vec<Expression>),
+}
+
+impl From<&Evaluator> for Expression {
+    fn from(evaluator: &Evaluator) -> Self {
+        let tokens = Tokens::from(evaluator.clone());
+        let operand = |token: &Token| match token {
+            Token::Ident(name) => Expression::Variable(name.clone()),
+            Token::Number(n) => Expression::Count(*n),
+            _ => Expression::Constant(*token == Token::True),
+        };
+        let combine = |operator: Token, mut operands: Vec<Expression>| {
+            //`0` and `1` are lexed as constants, as a count they are numbers.
+            if let (Token::Function(function), Some(Expression::Constant(v))) = (&operator, operands.first()) {
+                if function.takes_count() {
+                    operands[0] = Expression::Count(usize::from(*v));
+                }
+            }
+            Expression::Operation(operator, operands)
+        };
+        fold(&tokens, operand, combine).expect("an evaluator is never empty")
+    }
+}
+
+impl Expression {
+    /// the variables in order of their first appearance.
+    pub fn variables(&self) -> Vec<String> {
+        let mut variables = Vec::new();
+        self.collect_variables(&mut variables);
+        variables
+    }
+
+    fn collect_variables(&self, variables: &mut Vec<String>) {
+        match self {
+            Expression::Variable(name) if !variables.contains(name) => variables.push(name.clone()),
+            Expression::Operation(_, operands) => operands.iter().for_each(|o| o.collect_variables(variables)),
+            _ => {}
+        }
+    }
+
+    /// the expression with each variable replaced by the expression `replace` gives for its name.
+    pub(crate) fn replace_variables<E>(
+        &self,
+        replace: &mut impl FnMut(&str) -> Result<Expression, E>,
+    ) -> Result<Expression, E> {
+        match self {
+            Expression::Variable(name) => replace(name),
+            Expression::Operation(operator, operands) => {
+                let operands = operands.iter().map(|o| o.replace_variables(replace)).collect::<Result<Vec<_>, _>>()?;
+                Ok(Expression::Operation(operator.clone(), operands))
+            }
+            _ => Ok(self.clone()),
+        }
+    }
+
+    /// the value on each row of the truth table of `variables`, which include the variables of
+    /// the expression. Row `i` has the values of `assignment(variables, i)`. Each operation is
+    /// evaluated for 64 rows at once, without naming it like `Evaluator::evaluate` does.
+    pub(crate) fn truth_vector(&self, variables: &[String]) -> Vec<bool> {
+        let rows = 1usize << variables.len();
+        let words = self.truth_words(variables, rows.div_ceil(64));
+        (0..rows).map(|i| words[i / 64] >> (i % 64) & 1 == 1).collect()
+    }
+
+    /// the truth vector with row `64 * w + j` at bit `j` of word `w`.
+    fn truth_words(&self, variables: &[String], words: usize) -> Vec<u64> {
+        let bit = |row: usize, word: u64, j: usize| word | u64::from(row & 1 == 1) << j;
+        let (operator, operands) = match self {
+            Expression::Variable(name) => {
+                let position = variables.iter().position(|v| v == name).expect("a variable of the table");
+                let shift = variables.len() - position - 1;
+                return (0..words).map(|w| (0..64).fold(0, |word, j| bit((64 * w + j) >> shift, word, j))).collect();
+            }
+            Expression::Constant(value) => return vec![if *value { u64::MAX } else { 0 }; words],
+            Expression::Count(_) => return vec![0; words],
+            Expression::Operation(operator, operands) => (operator, operands),
+        };
+        let columns: Vec<Vec<u64>> = operands.iter().map(|o| o.truth_words(variables, words)).collect();
+        let values = |combination: usize| -> Vec<Token> {
+            let n = operands.len();
+            operands
+                .iter()
+                .enumerate()
+                .map(|(k, o)| match o {
+                    Expression::Count(count) => Token::Number(*count),
+                    _ => Token::from(combination >> (n - k - 1) & 1 == 1),
+                })
+                .collect()
+        };
+        if let Token::Function(_) = operator {
+            //one row at a time, a function takes any number of arguments.
+            return (0..words)
+                .map(|w| {
+                    (0..64).fold(0, |word, j| {
+                        let row = columns.iter().fold(0, |row, c| row << 1 | (c[w] >> j & 1) as usize);
+                        bit(usize::from(apply(operator, &values(row))), word, j)
+                    })
+                })
+                .collect();
+        }
+        //the rows where the operands have a combination of values that makes the operator true.
+        let true_combinations: Vec<usize> = (0..1 << operands.len()).filter(|c| apply(operator, &values(*c))).collect();
+        (0..words)
+            .map(|w| {
+                true_combinations.iter().fold(0, |word, combination| {
+                    let n = columns.len();
+                    word | columns.iter().enumerate().fold(u64::MAX, |rows, (k, c)| match combination >> (n - k - 1) & 1 {
+                        1 => rows & c[w],
+                        _ => rows & !c[w],
+                    })
+                })
+            })
+            .collect()
+    }
+
+    /// the tokens of the expression. Operands that are binary operations are put in parentheses.
+    pub fn to_tokens(&self) -> Tokens {
+        let mut tokens = Vec::new();
+        self.write(&mut tokens, false);
+        Tokens::from(tokens)
+    }
+
+    fn write(&self, tokens: &mut Vec<Token>, enclose: bool) {
+        match self {
+            Expression::Constant(value) => tokens.push(Token::from(*value)),
+            Expression::Variable(name) => tokens.push(Token::Ident(name.clone())),
+            Expression::Count(n) => tokens.push(Token::Number(*n)),
+            Expression::Operation(Token::Function(function), operands) => {
+                tokens.push(Token::Function(*function));
+                tokens.push(Token::OpenParen);
+                for (i, operand) in operands.iter().enumerate() {
+                    if i > 0 {
+                        tokens.push(Token::Comma);
+                    }
+                    operand.write(tokens, false);
+                }
+                tokens.push(Token::CloseParen);
+            }
+            Expression::Operation(operator, operands) => match operands.as_slice() {
+                [operand] => {
+                    tokens.push(operator.clone());
+                    operand.write(tokens, true);
+                }
+                [left, right] => {
+                    if enclose {
+                        tokens.push(Token::OpenParen);
+                    }
+                    left.write(tokens, true);
+                    tokens.push(operator.clone());
+                    right.write(tokens, true);
+                    if enclose {
+                        tokens.push(Token::CloseParen);
+                    }
+                }
+                _ => unreachable!("operators take one or two operands"),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_tokens())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Expression {
+        Expression::from(&Evaluator::new(Tokens::from_text(s)).unwrap())
+    }
+
+    #[test]
+    fn priorities() {
+        assert_eq!(parse("p or q and not r").to_string(), "p ∨ (q ∧ ¬r)");
+        assert_eq!(parse("(p -> q) -> r").to_string(), "(p → q) → r");
+        assert_eq!(parse("not (p nand q)").to_string(), "¬(p ↑ q)");
+        assert_eq!(parse("[p]").to_string(), "p");
+        assert_eq!(parse("p"), Expression::Variable("p".into()));
+    }
+
+    #[test]
+    fn functions() {
+        let expression = parse("exactly(1, a, b and c) or xor(a, b)");
+        assert_eq!(expression.to_string(), "exactly(1, a, b ∧ c) ∨ xor(a, b)");
+        let Expression::Operation(_, operands) = &expression else {
+            panic!("expected an operation");
+        };
+        let Expression::Operation(_, arguments) = &operands[0] else {
+            panic!("expected a function");
+        };
+        assert_eq!(arguments[0], Expression::Count(1));
+        //the printed expression reads back as the same tree.
+        assert_eq!(parse(&expression.to_string()), expression);
+    }
+
+    #[test]
+    fn truth_vector() {
+        for s in ["p or q and not r", "exactly(1, a, b and c) or xor(a, b)", "(p ↛ q) ≡ ite(q, r, p)", "1"] {
+            let evaluator = Evaluator::new(Tokens::from_text(s)).unwrap();
+            let variables = evaluator.variables();
+            assert_eq!(parse(s).truth_vector(&variables), evaluator.truth_vector().unwrap(), "{}", s);
+        }
+        let names: Vec<String> = (0..7).map(|i| format!("x{}", i)).collect();
+        let vector = parse("x0 and x6").truth_vector(&names);
+        assert_eq!(vector.iter().filter(|v| **v).count(), 32);
+        assert!(vector[127] && vector[65] && !vector[64]);
+    }
+}
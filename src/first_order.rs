@@ -0,0 +1,679 @@
+use std::fmt;
+use std::ops::Range;
+
+use indexmap::{IndexMap, IndexSet};
+
+use crate::evaluator::{Evaluator, EvaluatorError};
+use crate::expression::Expression;
+use crate::tokenizer::{Token, Tokens};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quantifier {
+    /// `forall x.` or `∀x.`
+    ForAll,
+    /// `exists x.` or `∃x.`
+    Exists,
+}
+
+impl fmt::Display for Quantifier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Quantifier::ForAll => write!(f, "∀"),
+            Quantifier::Exists => write!(f, "∃"),
+        }
+    }
+}
+
+/// A piece of a formula that is an operand of its connectives.
+#[derive(Clone)]
+enum Part {
+    /// `P(x, y)`, the arguments are variables or elements of the domain.
+    Atom(String, Vec<String>),
+    /// `x = y` between a bound variable and a variable or an element, on either side.
+    Equal(String, String),
+    Quantified(Quantifier, String, Box<Formula>),
+}
+
+/// A formula of first-order logic over a finite domain, like `forall x. P(x) -> exists y. R(x, y)`.
+/// The connectives are those of propositional expressions, a quantifier reaches as far right as
+/// possible. `x = y` compares elements when `x` or `y` is a bound variable, otherwise `=` is `≡`.
+/// Names without arguments are propositions.
+#[derive(Clone)]
+pub struct Formula {
+    /// the connectives, with the atoms and quantified formulas as variables named by their text.
+    skeleton: Evaluator,
+    parts: IndexMap<String, Part>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Lexeme {
+    Token(Token),
+    Quantifier(Quantifier),
+    Dot,
+}
+
+/// the tokens of `text` together with the quantifiers and dots, which are not propositional tokens.
+pub(crate) fn lex(text: &str) -> Result<Vec<(Lexeme, Range<usize>)>, EvaluatorError> {
+    let tokens = Tokens::from_text(text);
+    let mut lexemes: Vec<(Lexeme, Range<usize>)> = tokens
+        .iter()
+        .zip(tokens.spans())
+        .map(|(token, span)| match token {
+            Token::Ident(name) if name == "forall" => (Lexeme::Quantifier(Quantifier::ForAll), span.clone()),
+            Token::Ident(name) if name == "exists" => (Lexeme::Quantifier(Quantifier::Exists), span.clone()),
+            _ => (Lexeme::Token(token.clone()), span.clone()),
+        })
+        .collect();
+    for span in tokens.unknown() {
+        let lexeme = match &text[span.clone()] {
+            "∀" => Lexeme::Quantifier(Quantifier::ForAll),
+            "∃" => Lexeme::Quantifier(Quantifier::Exists),
+            "." => Lexeme::Dot,
+            _ => return Err(EvaluatorError::at("Unknown symbol.", Some(span.start))),
+        };
+        lexemes.push((lexeme, span.clone()));
+    }
+    lexemes.sort_by_key(|(_, span)| span.start);
+    Ok(lexemes)
+}
+
+/// the name of an argument: an identifier or a number.
+fn term(lexeme: Option<&(Lexeme, Range<usize>)>) -> Option<String> {
+    match lexeme.map(|l| &l.0) {
+        Some(Lexeme::Token(Token::Ident(name))) => Some(name.clone()),
+        Some(Lexeme::Token(Token::Number(n))) => Some(n.to_string()),
+        Some(Lexeme::Token(Token::True)) => Some("1".into()),
+        Some(Lexeme::Token(Token::False)) => Some("0".into()),
+        _ => None,
+    }
+}
+
+/// the arguments of `(a, b, ...)` starting at `start`, and the index after the closing parenthesis.
+fn arguments(lexemes: &[(Lexeme, Range<usize>)], start: usize) -> Result<(Vec<String>, usize), EvaluatorError> {
+    let mut terms = Vec::new();
+    let mut i = start + 1;
+    loop {
+        let position = lexemes.get(i).or(lexemes.last()).map(|l| l.1.start);
+        terms.push(term(lexemes.get(i)).ok_or_else(|| EvaluatorError::at("Expected a variable or an element.", position))?);
+        match lexemes.get(i + 1).map(|l| &l.0) {
+            Some(Lexeme::Token(Token::Comma)) => i += 2,
+            Some(Lexeme::Token(Token::CloseParen)) => return Ok((terms, i + 2)),
+            _ => return Err(EvaluatorError::at("Expected ',' or ')'.", lexemes.get(i + 1).map(|l| l.1.start))),
+        }
+    }
+}
+
+/// the end of the scope of a quantifier whose body starts at `start`: the `)` or `,` that closes
+/// the group it is in, or the end.
+fn scope_end(lexemes: &[(Lexeme, Range<usize>)], start: usize) -> usize {
+    let mut depth = 0usize;
+    for (i, (lexeme, _)) in lexemes.iter().enumerate().skip(start) {
+        match lexeme {
+            Lexeme::Token(Token::OpenParen | Token::OpenBracket | Token::OpenCurlyBrace) => depth += 1,
+            Lexeme::Token(Token::CloseParen | Token::CloseBracket | Token::CloseCurlyBrace | Token::Comma)
+                if depth == 0 =>
+            {
+                return i
+            }
+            Lexeme::Token(Token::CloseParen | Token::CloseBracket | Token::CloseCurlyBrace) => depth -= 1,
+            _ => {}
+        }
+    }
+    lexemes.len()
+}
+
+impl Formula {
+    /// Error positions are byte offsets in `text`.
+    pub fn from_text(text: &str) -> Result<Self, EvaluatorError> {
+        Self::parse(&lex(text)?, &[])
+    }
+
+    fn parse(lexemes: &[(Lexeme, Range<usize>)], bound: &[String]) -> Result<Self, EvaluatorError> {
+        let mut tokens = Vec::new();
+        let mut spans = Vec::new();
+        let mut parts = IndexMap::new();
+        let mut i = 0;
+        while i < lexemes.len() {
+            let (lexeme, span) = &lexemes[i];
+            let next = lexemes.get(i + 1).map(|l| &l.0);
+            match lexeme {
+                Lexeme::Quantifier(quantifier) => {
+                    //`∀x, y.` quantifies both, the dot is needed after a list.
+                    let mut variables = Vec::new();
+                    let mut j = i + 1;
+                    loop {
+                        let Some(Lexeme::Token(Token::Ident(name))) = lexemes.get(j).map(|l| &l.0) else {
+                            let position = lexemes.get(j).map_or(span.end, |l| l.1.start);
+                            return Err(EvaluatorError::at("Expected a variable after the quantifier.", Some(position)));
+                        };
+                        variables.push(name.clone());
+                        j += 1;
+                        match lexemes.get(j).map(|l| &l.0) {
+                            Some(Lexeme::Token(Token::Comma)) => j += 1,
+                            Some(Lexeme::Dot) => {
+                                j += 1;
+                                break;
+                            }
+                            _ if variables.len() == 1 => break,
+                            _ => return Err(EvaluatorError::at("Expected '.' after the variables.", lexemes.get(j).map(|l| l.1.start))),
+                        }
+                    }
+                    let end = scope_end(lexemes, j);
+                    if end == j {
+                        return Err(EvaluatorError::at("Missing the formula after the quantifier.", Some(lexemes[j - 1].1.end)));
+                    }
+                    //`∀x (...)` has the body in the parentheses.
+                    let body = match scope_end(lexemes, j + 1) {
+                        close if lexemes[j].0 == Lexeme::Token(Token::OpenParen) && close == end - 1 => j + 1..close,
+                        _ => j..end,
+                    };
+                    let mut inner = bound.to_vec();
+                    inner.extend(variables.iter().cloned());
+                    let mut formula = Self::parse(&lexemes[body], &inner)?;
+                    for variable in variables.iter().rev() {
+                        let part = Part::Quantified(*quantifier, variable.clone(), Box::new(formula));
+                        formula = Self::single(part);
+                    }
+                    let (name, part) = formula.parts.pop().expect("a quantified formula");
+                    parts.insert(name.clone(), part);
+                    tokens.push(Token::Ident(name));
+                    spans.push(span.start..lexemes[end - 1].1.end);
+                    i = end;
+                }
+                Lexeme::Token(Token::Ident(predicate)) if next == Some(&Lexeme::Token(Token::OpenParen)) => {
+                    let (terms, end) = arguments(lexemes, i + 1)?;
+                    let part = Part::Atom(predicate.clone(), terms);
+                    let name = part.to_string();
+                    parts.insert(name.clone(), part);
+                    tokens.push(Token::Ident(name));
+                    spans.push(span.start..lexemes[end - 1].1.end);
+                    i = end;
+                }
+                Lexeme::Token(Token::Ident(variable))
+                    if matches!(next, Some(Lexeme::Token(Token::Equals(_) | Token::NotEquals(_))))
+                        && (bound.contains(variable) || term(lexemes.get(i + 2)).is_some_and(|t| bound.contains(&t))) =>
+                {
+                    let other = term(lexemes.get(i + 2))
+                        .ok_or_else(|| EvaluatorError::at("Expected a variable or an element.", Some(lexemes[i + 1].1.end)))?;
+                    if matches!(next, Some(Lexeme::Token(Token::NotEquals(_)))) {
+                        tokens.push(Token::Not('¬'));
+                        spans.push(lexemes[i + 1].1.clone());
+                    }
+                    let part = Part::Equal(variable.clone(), other);
+                    let name = part.to_string();
+                    parts.insert(name.clone(), part);
+                    tokens.push(Token::Ident(name));
+                    spans.push(span.start..lexemes[i + 2].1.end);
+                    i += 3;
+                }
+                Lexeme::Token(token) => {
+                    tokens.push(token.clone());
+                    spans.push(span.clone());
+                    i += 1;
+                }
+                Lexeme::Dot => return Err(EvaluatorError::at("'.' only follows the variables of a quantifier.", Some(span.start))),
+            }
+        }
+        let skeleton = Evaluator::new(Tokens::with_spans(tokens, spans))?;
+        Ok(Formula { skeleton, parts })
+    }
+
+    /// a formula that is a single part.
+    fn single(part: Part) -> Self {
+        let name = part.to_string();
+        let skeleton = Evaluator::new(Tokens::from(vec![Token::Ident(name.clone())])).expect("a single variable");
+        Formula { skeleton, parts: [(name, part)].into() }
+    }
+
+    /// whether the formula is true in `model`. Every predicate must be a relation of the model and
+    /// every variable must be bound.
+    pub fn holds(&self, model: &Model) -> Result<bool, EvaluatorError> {
+        self.holds_with(model, &mut IndexMap::new())
+    }
+
+    fn holds_with(&self, model: &Model, bound: &mut IndexMap<String, String>) -> Result<bool, EvaluatorError> {
+        let mut values = IndexMap::new();
+        for name in self.skeleton.variables() {
+            let value = match self.parts.get(&name) {
+                Some(Part::Atom(predicate, terms)) => {
+                    let tuple = terms.iter().map(|t| element(t, bound, model.domain())).collect::<Result<Vec<_>, _>>()?;
+                    model.contains(predicate, &tuple)?
+                }
+                Some(Part::Equal(a, b)) => element(a, bound, model.domain())? == element(b, bound, model.domain())?,
+                Some(Part::Quantified(quantifier, variable, body)) => {
+                    let shadowed = bound.get(variable).cloned();
+                    let mut value = *quantifier == Quantifier::ForAll;
+                    for e in model.domain() {
+                        bound.insert(variable.clone(), e.clone());
+                        if body.holds_with(model, bound)? != value {
+                            value = !value;
+                            break;
+                        }
+                    }
+                    restore(bound, variable, shadowed);
+                    value
+                }
+                None => {
+                    proposition(&name, bound)?;
+                    model.contains(&name, &[])?
+                }
+            };
+            values.insert(name, value);
+        }
+        self.skeleton.value(&values)
+    }
+
+    /// the propositional formula over the ground atoms of `domain`: a quantifier becomes the
+    /// conjunction or the disjunction of its instances and `x = y` a constant.
+    pub fn ground(&self, domain: &[String]) -> Result<Grounding, EvaluatorError> {
+        if domain.is_empty() {
+            return Err(EvaluatorError::new("The domain is empty."));
+        }
+        let mut grounding = Grounding { domain: domain.to_vec(), atoms: IndexMap::new(), expression: Expression::Constant(true) };
+        grounding.expression = self.ground_with(&mut grounding, &mut IndexMap::new())?.substitute(&IndexMap::new());
+        Ok(grounding)
+    }
+
+    fn ground_with(&self, grounding: &mut Grounding, bound: &mut IndexMap<String, String>) -> Result<Expression, EvaluatorError> {
+        Expression::from(&self.skeleton).replace_variables(&mut |name| match self.parts.get(name) {
+            Some(Part::Atom(predicate, terms)) => {
+                let tuple = terms.iter().map(|t| element(t, bound, &grounding.domain)).collect::<Result<Vec<_>, _>>()?;
+                Ok(Expression::Variable(grounding.atom(predicate, tuple)))
+            }
+            Some(Part::Equal(a, b)) => {
+                Ok(Expression::Constant(element(a, bound, &grounding.domain)? == element(b, bound, &grounding.domain)?))
+            }
+            Some(Part::Quantified(quantifier, variable, body)) => {
+                let shadowed = bound.get(variable).cloned();
+                let mut instances = Vec::new();
+                for e in grounding.domain.clone() {
+                    bound.insert(variable.clone(), e);
+                    instances.push(body.ground_with(grounding, bound)?);
+                }
+                restore(bound, variable, shadowed);
+                let connective = match quantifier {
+                    Quantifier::ForAll => Token::And('∧'),
+                    Quantifier::Exists => Token::Or('∨'),
+                };
+                Ok(instances.into_iter().reduce(|a, b| Expression::Operation(connective.clone(), vec![a, b])).expect("a nonempty domain"))
+            }
+            None => {
+                proposition(name, bound)?;
+                Ok(Expression::Variable(grounding.atom(name, Vec::new())))
+            }
+        })
+    }
+
+    /// a model over `domain` in which the formula holds, found with the grounding.
+    pub fn find_model(&self, domain: &[String]) -> Result<Option<Model>, EvaluatorError> {
+        let grounding = self.ground(domain)?;
+        let evaluator = grounding.evaluator()?;
+        Ok(evaluator.models().next().map(|values| grounding.to_model(&values)))
+    }
+}
+
+/// the element named by a bound variable or by itself.
+fn element(term: &str, bound: &IndexMap<String, String>, domain: &[String]) -> Result<String, EvaluatorError> {
+    match bound.get(term) {
+        Some(e) => Ok(e.clone()),
+        None if domain.iter().any(|e| e == term) => Ok(term.to_string()),
+        None => Err(EvaluatorError::new(format!("'{}' is neither a bound variable nor an element of the domain.", term))),
+    }
+}
+
+/// checks that a name used as a formula is not a variable.
+fn proposition(name: &str, bound: &IndexMap<String, String>) -> Result<(), EvaluatorError> {
+    match bound.contains_key(name) {
+        true => Err(EvaluatorError::new(format!("'{}' is a variable, it is used as an argument like P({}).", name, name))),
+        false => Ok(()),
+    }
+}
+
+fn restore(bound: &mut IndexMap<String, String>, variable: &str, shadowed: Option<String>) {
+    match shadowed {
+        Some(e) => bound.insert(variable.to_string(), e),
+        None => bound.shift_remove(variable),
+    };
+}
+
+impl fmt::Display for Part {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Part::Atom(predicate, terms) => write!(f, "{}({})", predicate, terms.join(", ")),
+            Part::Equal(a, b) => write!(f, "{} = {}", a, b),
+            Part::Quantified(quantifier, variable, body) if Tokens::from(body.skeleton.clone()).len() == 1 => {
+                write!(f, "{}{}. {}", quantifier, variable, body)
+            }
+            Part::Quantified(quantifier, variable, body) => write!(f, "{}{}. ({})", quantifier, variable, body),
+        }
+    }
+}
+
+impl fmt::Display for Formula {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let tokens = Tokens::from(self.skeleton.clone());
+        //a quantifier among other tokens is put in parentheses, it would reach to the end.
+        let enclosed = |i: usize| i > 0 && tokens[i - 1] == Token::OpenParen && tokens.get(i + 1) == Some(&Token::CloseParen);
+        let tokens: Vec<Token> = (0..tokens.len())
+            .map(|i| match &tokens[i] {
+                Token::Ident(name)
+                    if tokens.len() > 1 && !enclosed(i) && matches!(self.parts.get(name), Some(Part::Quantified(..))) =>
+                {
+                    Token::Ident(format!("({})", name))
+                }
+                t => t.clone(),
+            })
+            .collect();
+        write!(f, "{}", Tokens::from(tokens))
+    }
+}
+
+/// The propositional form of a formula over a finite domain. Its variables are named after the
+/// ground atoms, `R(a, b)` is `R_a_b`.
+#[derive(Debug, Clone)]
+pub struct Grounding {
+    domain: Vec<String>,
+    /// the ground atom of each variable.
+    atoms: IndexMap<String, (String, Vec<String>)>,
+    expression: Expression,
+}
+
+impl Grounding {
+    /// the variable of a ground atom, a new one when it is first seen.
+    fn atom(&mut self, predicate: &str, tuple: Vec<String>) -> String {
+        let atom = (predicate.to_string(), tuple);
+        if let Some((name, _)) = self.atoms.iter().find(|(_, a)| **a == atom) {
+            return name.clone();
+        }
+        let base = std::iter::once(predicate).chain(atom.1.iter().map(|e| e.as_str())).collect::<Vec<_>>().join("_");
+        let mut name = base.clone();
+        let mut n = 1;
+        while self.atoms.contains_key(&name) {
+            n += 1;
+            name = format!("{}_{}", base, n);
+        }
+        self.atoms.insert(name.clone(), atom);
+        name
+    }
+
+    pub fn to_tokens(&self) -> Tokens {
+        self.expression.to_tokens()
+    }
+
+    pub fn evaluator(&self) -> Result<Evaluator, EvaluatorError> {
+        Evaluator::new(self.to_tokens())
+    }
+
+    /// the predicate and the elements of a variable of the grounding.
+    pub fn atom_of(&self, variable: &str) -> Option<(&str, &[String])> {
+        self.atoms.get(variable).map(|(p, t)| (p.as_str(), t.as_slice()))
+    }
+
+    /// the model whose relations hold the atoms that are true in `values`.
+    pub fn to_model(&self, values: &IndexMap<String, bool>) -> Model {
+        let mut model = Model::new(self.domain.clone());
+        for (name, (predicate, tuple)) in &self.atoms {
+            model.declare(predicate);
+            if values.get(name).copied().unwrap_or_default() {
+                model.add(predicate, tuple.clone()).expect("elements of the domain");
+            }
+        }
+        model
+    }
+}
+
+/// A finite model: a domain and the tuples of each relation. A proposition is a relation of
+/// arity 0, it is true when it holds the empty tuple.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Model {
+    domain: Vec<String>,
+    relations: IndexMap<String, IndexSet<Vec<String>>>,
+}
+
+impl Model {
+    pub fn new(domain: Vec<String>) -> Self {
+        Model { domain, relations: IndexMap::new() }
+    }
+
+    pub fn domain(&self) -> &[String] {
+        &self.domain
+    }
+
+    pub fn relations(&self) -> impl Iterator<Item = (&String, &IndexSet<Vec<String>>)> {
+        self.relations.iter()
+    }
+
+    /// adds a relation with no tuples, or keeps the one with that name.
+    pub fn declare(&mut self, relation: &str) {
+        self.relations.entry(relation.to_string()).or_default();
+    }
+
+    /// adds a tuple to a relation. Its elements must be in the domain and all the tuples of a
+    /// relation must have the same length.
+    pub fn add(&mut self, relation: &str, tuple: Vec<String>) -> Result<(), EvaluatorError> {
+        if let Some(e) = tuple.iter().find(|e| !self.domain.contains(e)) {
+            return Err(EvaluatorError::new(format!("'{}' is not an element of the domain.", e)));
+        }
+        let tuples = self.relations.entry(relation.to_string()).or_default();
+        if tuples.first().is_some_and(|t| t.len() != tuple.len()) {
+            return Err(EvaluatorError::new(format!("'{}' takes {} arguments, not {}.", relation, tuples[0].len(), tuple.len())));
+        }
+        tuples.insert(tuple);
+        Ok(())
+    }
+
+    pub fn contains(&self, relation: &str, tuple: &[String]) -> Result<bool, EvaluatorError> {
+        let tuples = self
+            .relations
+            .get(relation)
+            .ok_or_else(|| EvaluatorError::new(format!("'{}' is not a relation of the model.", relation)))?;
+        if tuples.first().is_some_and(|t| t.len() != tuple.len()) {
+            return Err(EvaluatorError::new(format!("'{}' takes {} arguments, not {}.", relation, tuples[0].len(), tuple.len())));
+        }
+        Ok(tuples.contains(tuple))
+    }
+
+    /// reads one declaration per line, empty lines and lines starting with `#` are skipped:
+    /// `domain = {a, b, c}`, a relation table `R = {(a, b), (b, c)}` or `P = {a}`, a fact
+    /// `R(a, b)` and a proposition `p = true`, `p` or `p = false`. Without a `domain` line the
+    /// domain is made of the elements of the relations.
+    pub fn from_text(text: &str) -> Result<Self, EvaluatorError> {
+        let mut facts = Vec::new();
+        let mut domain: Option<Vec<String>> = None;
+        let mut offset = 0;
+        for line in text.split_inclusive('\n') {
+            let trimmed = line.trim();
+            if !trimmed.is_empty() && !trimmed.starts_with('#') {
+                let tokens = Tokens::from_text(line).shifted(offset);
+                let (name, tuples) = declaration(&tokens)?;
+                match name.as_str() {
+                    "domain" => domain = Some(tuples.into_iter().flatten().collect()),
+                    _ => facts.push((name, tuples)),
+                }
+            }
+            offset += line.len();
+        }
+        let domain = domain.unwrap_or_else(|| {
+            let elements: IndexSet<&String> = facts.iter().flat_map(|(_, tuples)| tuples.iter().flatten()).collect();
+            elements.into_iter().cloned().collect()
+        });
+        let mut model = Model::new(domain);
+        for (name, tuples) in facts {
+            model.declare(&name);
+            for tuple in tuples {
+                model.add(&name, tuple)?;
+            }
+        }
+        Ok(model)
+    }
+
+    pub fn from_file(file_name: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self::from_text(&std::fs::read_to_string(file_name)?)?)
+    }
+}
+
+/// the name and the tuples of a line of a model.
+fn declaration(tokens: &Tokens) -> Result<(String, Vec<Vec<String>>), EvaluatorError> {
+    if let Some(span) = tokens.unknown().first() {
+        return Err(EvaluatorError::at("Unknown symbol.", Some(span.start)));
+    }
+    let lexemes: Vec<(Lexeme, Range<usize>)> =
+        tokens.iter().zip(tokens.spans()).map(|(t, s)| (Lexeme::Token(t.clone()), s.clone())).collect();
+    let at = |i: usize, message: &str| {
+        let position = lexemes.get(i).or(lexemes.last()).map(|l| if i < lexemes.len() { l.1.start } else { l.1.end });
+        EvaluatorError::at(message, position)
+    };
+    let Some(Token::Ident(name)) = tokens.first() else {
+        return Err(at(0, "Expected the name of a relation."));
+    };
+    let rest: &[Token] = &tokens[1..];
+    let tuples = match rest {
+        [] | [Token::Equals(_), Token::True] => vec![Vec::new()],
+        [Token::Equals(_), Token::False] => Vec::new(),
+        [Token::OpenParen, ..] => {
+            let (tuple, end) = arguments(&lexemes, 1)?;
+            if end != lexemes.len() {
+                return Err(at(end, "Expected the end of the line."));
+            }
+            vec![tuple]
+        }
+        [Token::Equals(_), Token::OpenCurlyBrace, ..] => {
+            let mut tuples = Vec::new();
+            let mut i = 3;
+            if rest.get(2) != Some(&Token::CloseCurlyBrace) {
+                loop {
+                    match term(lexemes.get(i)) {
+                        Some(e) => {
+                            tuples.push(vec![e]);
+                            i += 1;
+                        }
+                        None if lexemes.get(i).map(|l| &l.0) == Some(&Lexeme::Token(Token::OpenParen)) => {
+                            let (tuple, end) = arguments(&lexemes, i)?;
+                            tuples.push(tuple);
+                            i = end;
+                        }
+                        None => return Err(at(i, "Expected an element or a tuple.")),
+                    }
+                    match lexemes.get(i).map(|l| &l.0) {
+                        Some(Lexeme::Token(Token::Comma)) => i += 1,
+                        Some(Lexeme::Token(Token::CloseCurlyBrace)) => break,
+                        _ => return Err(at(i, "Expected ',' or '}'.")),
+                    }
+                }
+            }
+            if i + 1 != lexemes.len() {
+                return Err(at(i + 1, "Expected the end of the line."));
+            }
+            tuples
+        }
+        _ => return Err(at(1, "Expected '= {...}', '= true', '= false' or arguments.")),
+    };
+    Ok((name.clone(), tuples))
+}
+
+impl fmt::Display for Model {
+    /// the text read by `from_text`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "domain = {{{}}}", self.domain.join(", "))?;
+        for (name, tuples) in &self.relations {
+            match tuples.first().map(|t| t.len()) {
+                Some(0) => writeln!(f, "{} = true", name)?,
+                Some(1) => writeln!(f, "{} = {{{}}}", name, tuples.iter().map(|t| t[0].clone()).collect::<Vec<_>>().join(", "))?,
+                _ => {
+                    let tuples: Vec<String> = tuples.iter().map(|t| format!("({})", t.join(", "))).collect();
+                    writeln!(f, "{} = {{{}}}", name, tuples.join(", "))?
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model() -> Model {
+        Model::from_text(
+            "# who likes whom
+            domain = {alice, bob, carol}
+            Person = {alice, bob, carol}
+            Likes = {(alice, bob), (bob, bob), (carol, bob)}
+            Happy(bob)
+            sunny = true",
+        )
+        .unwrap()
+    }
+
+    fn holds(s: &str) -> bool {
+        Formula::from_text(s).unwrap().holds(&model()).unwrap()
+    }
+
+    #[test]
+    fn quantifiers() {
+        assert!(holds("forall x. exists y. Likes(x, y)"));
+        assert!(holds("∃y ∀x. Likes(x, y)"));
+        assert!(!holds("forall x. Likes(x, x)"));
+        assert!(holds("forall x, y. Likes(x, y) -> y = bob"));
+        assert!(holds("exists x. Happy(x) and sunny"));
+        assert!(holds("not exists x. x != bob and Happy(x)"));
+        //the bound variable can be on either side.
+        assert!(holds("exists x. bob = x and Happy(x)"));
+        assert!(holds("forall x. Likes(alice, x) -> bob = x"));
+        assert!(!holds("forall x. alice != x"));
+        assert!(holds("maj(Happy(alice), Likes(alice, bob), forall x. Person(x))"));
+        //the quantifier reaches to the end, the parentheses end it.
+        assert!(holds("(forall x. Happy(x)) or Happy(bob)"));
+        assert!(!holds("forall x. Happy(x) or Happy(bob) and false"));
+        //an inner quantifier hides the outer variable.
+        assert!(holds("exists x. Happy(x) and exists x. not Happy(x)"));
+    }
+
+    #[test]
+    fn errors() {
+        let error = Formula::from_text("forall . P(x)").err().unwrap();
+        assert_eq!(error.position(), Some(7));
+        assert!(Formula::from_text("forall x.").is_err());
+        assert!(Formula::from_text("P(x,) and q").is_err());
+        assert!(Formula::from_text("P(x) and").is_err());
+        let model = model();
+        assert!(Formula::from_text("Happy(x)").unwrap().holds(&model).is_err());
+        assert!(Formula::from_text("exists x. Friend(x)").unwrap().holds(&model).is_err());
+        assert!(Formula::from_text("exists x. Likes(x)").unwrap().holds(&model).is_err());
+        assert!(Formula::from_text("exists x. x").unwrap().holds(&model).is_err());
+        assert!(Model::from_text("domain = {a}\nP(b)").is_err());
+    }
+
+    #[test]
+    fn display() {
+        let formula = Formula::from_text("forall x. P(x) -> exists y. R(x, y) and x != y").unwrap();
+        assert_eq!(formula.to_string(), "∀x. (P(x) → (∃y. (R(x, y) ∧ ¬x = y)))");
+        assert_eq!(Formula::from_text("∀x (P(x) → Q(x))").unwrap().to_string(), "∀x. (P(x) → Q(x))");
+        let formula = Formula::from_text("(∃x P(x)) ∧ q").unwrap();
+        assert_eq!(formula.to_string(), "(∃x. P(x)) ∧ q");
+        let model = model();
+        assert_eq!(Model::from_text(&model.to_string()).unwrap(), model);
+    }
+
+    #[test]
+    fn grounding() {
+        let domain: Vec<String> = ["a", "b"].iter().map(|s| s.to_string()).collect();
+        let formula = Formula::from_text("forall x. P(x) -> exists y. R(x, y) and x != y").unwrap();
+        let grounding = formula.ground(&domain).unwrap();
+        assert_eq!(grounding.to_tokens().to_string(), "(P_a → R_a_b) ∧ (P_b → R_b_a)");
+        assert_eq!(grounding.atom_of("R_a_b"), Some(("R", &domain[..])));
+        //the models of the grounding are the models of the formula.
+        let formula = Formula::from_text("(forall x. exists y. R(x, y)) and not exists x. R(x, x)").unwrap();
+        let found = formula.find_model(&domain).unwrap().unwrap();
+        assert!(formula.holds(&found).unwrap());
+        let evaluator = formula.ground(&domain).unwrap().evaluator().unwrap();
+        for values in evaluator.models() {
+            assert!(formula.holds(&formula.ground(&domain).unwrap().to_model(&values)).unwrap());
+        }
+        let contradiction = Formula::from_text("(exists x. P(x)) and forall x. not P(x)").unwrap();
+        assert!(contradiction.find_model(&domain).unwrap().is_none());
+        assert!(formula.ground(&[]).is_err());
+        let equal = Formula::from_text("exists x. a = x and P(x)").unwrap();
+        assert_eq!(equal.ground(&domain).unwrap().to_tokens().to_string(), "P_a");
+    }
+}
@@ -0,0 +1,207 @@
+use std::fmt;
+use std::str::FromStr;
+
+use indexmap::IndexMap;
+
+use crate::evaluator::{Evaluator, EvaluatorError};
+use crate::tokenizer::{Function, Token};
+
+/// The conjunction of a fuzzy logic. The disjunction is its dual `1 - t(1 - a, 1 - b)`, the
+/// implication its residuum and the negation is `1 - a`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum TNorm {
+    /// `min(a, b)`, with `max(a, b)` and `a → b = 1` if `a ≤ b`, else `b`.
+    #[default]
+    Godel,
+    /// `a · b`, with `a + b - a · b` and `a → b = 1` if `a ≤ b`, else `b / a`.
+    Product,
+    /// `max(0, a + b - 1)`, with `min(1, a + b)` and `a → b = min(1, 1 - a + b)`.
+    Lukasiewicz,
+}
+
+impl fmt::Display for TNorm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TNorm::Godel => write!(f, "Gödel"),
+            TNorm::Product => write!(f, "product"),
+            TNorm::Lukasiewicz => write!(f, "Łukasiewicz"),
+        }
+    }
+}
+
+impl FromStr for TNorm {
+    type Err = String;
+    /// `godel` (or `min`), `product` or `lukasiewicz`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "godel" | "gödel" | "min" | "minimum" => Ok(TNorm::Godel),
+            "product" | "goguen" => Ok(TNorm::Product),
+            "lukasiewicz" | "łukasiewicz" => Ok(TNorm::Lukasiewicz),
+            _ => Err(format!("unknown t-norm '{}'", s)),
+        }
+    }
+}
+
+impl TNorm {
+    pub fn and(&self, a: f64, b: f64) -> f64 {
+        match self {
+            TNorm::Godel => a.min(b),
+            TNorm::Product => a * b,
+            TNorm::Lukasiewicz => (a + b - 1.0).max(0.0),
+        }
+    }
+
+    pub fn or(&self, a: f64, b: f64) -> f64 {
+        1.0 - self.and(1.0 - a, 1.0 - b)
+    }
+
+    /// the largest `c` with `and(a, c) ≤ b`.
+    pub fn implies(&self, a: f64, b: f64) -> f64 {
+        match self {
+            _ if a <= b => 1.0,
+            TNorm::Godel => b,
+            TNorm::Product => b / a,
+            TNorm::Lukasiewicz => 1.0 - a + b,
+        }
+    }
+
+    /// both implications hold, `min(a → b, b → a)`.
+    pub fn equivalent(&self, a: f64, b: f64) -> f64 {
+        self.implies(a, b).min(self.implies(b, a))
+    }
+
+    /// the degree of an operator or a function, `count` is the count of `exactly`, `atmost` and
+    /// `atleast`.
+    pub fn apply(&self, operator: &Token, count: usize, values: &[f64]) -> f64 {
+        if let Token::Function(function) = operator {
+            return self.apply_function(*function, count, values);
+        }
+        let (a, b) = match values {
+            [a] => return 1.0 - a,
+            [a, b] => (*a, *b),
+            _ => return 0.0,
+        };
+        match operator {
+            Token::And(_) => self.and(a, b),
+            Token::Nand(_) => 1.0 - self.and(a, b),
+            Token::Or(_) => self.or(a, b),
+            Token::Nor(_) => 1.0 - self.or(a, b),
+            Token::Implication(_) => self.implies(a, b),
+            Token::Converse(_) => self.implies(b, a),
+            Token::NonImplication(_) => 1.0 - self.implies(a, b),
+            Token::Biconditional(_) | Token::Equals(_) => self.equivalent(a, b),
+            Token::XOr(_) | Token::NotEquals(_) => 1.0 - self.equivalent(a, b),
+            _ => 0.0,
+        }
+    }
+
+    fn apply_function(&self, function: Function, count: usize, values: &[f64]) -> f64 {
+        match function {
+            Function::Ite => match values {
+                [c, a, b] => self.or(self.and(*c, *a), self.and(1.0 - c, *b)),
+                _ => 0.0,
+            },
+            Function::Xor => values.iter().copied().reduce(|a, b| 1.0 - self.equivalent(a, b)).unwrap_or(0.0),
+            Function::Maj => self.at_least(values.len() / 2 + 1, values),
+            Function::AtLeast => self.at_least(count, values),
+            Function::AtMost => 1.0 - self.at_least(count + 1, values),
+            Function::Exactly => self.and(self.at_least(count, values), 1.0 - self.at_least(count + 1, values)),
+        }
+    }
+
+    /// the best conjunction of `k` of the values, which is the one of the `k` largest.
+    fn at_least(&self, k: usize, values: &[f64]) -> f64 {
+        if k > values.len() {
+            return 0.0;
+        }
+        let mut sorted = values.to_vec();
+        sorted.sort_by(|a, b| b.total_cmp(a));
+        sorted[..k].iter().fold(1.0, |degree, v| self.and(degree, *v))
+    }
+}
+
+impl Evaluator {
+    /// the degree of every column for truth degrees between 0 and 1. On the degrees 0 and 1 every
+    /// t-norm gives the values of `evaluate`.
+    pub fn evaluate_fuzzy(
+        &self,
+        tnorm: TNorm,
+        values: &IndexMap<String, f64>,
+    ) -> Result<IndexMap<String, f64>, EvaluatorError> {
+        if let Some((name, degree)) = values.iter().find(|(_, d)| !(0.0..=1.0).contains(*d)) {
+            return Err(EvaluatorError::new(format!("The degree of {} is {}, not between 0 and 1.", name, degree)));
+        }
+        let constant = |value: bool| if value { 1.0 } else { 0.0 };
+        self.evaluate_with(values, constant, |operator, count, values| tnorm.apply(operator, count, values))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::assignment;
+    use crate::tokenizer::Tokens;
+    use TNorm::*;
+
+    fn degree(s: &str, tnorm: TNorm, values: &[(&str, f64)]) -> f64 {
+        let evaluator = Evaluator::new(Tokens::from_text(s)).unwrap();
+        let values = values.iter().map(|(k, v)| (k.to_string(), *v)).collect();
+        let result = evaluator.evaluate_fuzzy(tnorm, &values).unwrap();
+        *result.last().unwrap().1
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn connectives() {
+        let v = [("p", 0.7), ("q", 0.4)];
+        assert!(close(degree("p and q", Godel, &v), 0.4));
+        assert!(close(degree("p and q", Product, &v), 0.28));
+        assert!(close(degree("p and q", Lukasiewicz, &v), 0.1));
+        assert!(close(degree("p or q", Product, &v), 0.82));
+        assert!(close(degree("p or q", Lukasiewicz, &v), 1.0));
+        assert!(close(degree("p -> q", Godel, &v), 0.4));
+        assert!(close(degree("p -> q", Product, &v), 0.4 / 0.7));
+        assert!(close(degree("p -> q", Lukasiewicz, &v), 0.7));
+        assert!(close(degree("q -> p", Godel, &v), 1.0));
+        assert!(close(degree("p <-> q", Lukasiewicz, &v), 0.7));
+        assert!(close(degree("not p", Godel, &v), 0.3));
+        assert!(close(degree("p nand true", Godel, &v), 0.3));
+    }
+
+    #[test]
+    fn functions() {
+        let v = [("a", 0.9), ("b", 0.5), ("c", 0.2)];
+        assert!(close(degree("maj(a, b, c)", Godel, &v), 0.5));
+        assert!(close(degree("maj(a, b, c)", Product, &v), 0.45));
+        assert!(close(degree("atleast(1, a, b, c)", Godel, &v), 0.9));
+        assert!(close(degree("atmost(0, a, b, c)", Godel, &v), 0.1));
+        assert!(close(degree("ite(a, b, c)", Godel, &v), 0.5));
+        assert!(close(degree("exactly(3, a, b, c)", Godel, &v), 0.2));
+    }
+
+    #[test]
+    fn crisp_degrees() {
+        //on 0 and 1 every t-norm is boolean logic.
+        let s = "(a xor b) -> ite(c, a nor b, exactly(1, a, b, c)) ≠ (b ← c)";
+        let evaluator = Evaluator::new(Tokens::from_text(s)).unwrap();
+        let variables = evaluator.variables();
+        for tnorm in [Godel, Product, Lukasiewicz] {
+            for index in 0..1u64 << variables.len() {
+                let row = assignment(&variables, index);
+                let degrees = row.iter().map(|(k, v)| (k.clone(), if *v { 1.0 } else { 0.0 })).collect();
+                let fuzzy = evaluator.evaluate_fuzzy(tnorm, &degrees).unwrap();
+                let crisp = evaluator.evaluate(&row).unwrap();
+                for (name, value) in crisp {
+                    assert_eq!(fuzzy[&name], if value { 1.0 } else { 0.0 }, "{} in {:?}", name, row);
+                }
+            }
+        }
+        let out_of_range = [("p".to_string(), 1.5)].into();
+        assert!(Evaluator::new(Tokens::from_text("p")).unwrap().evaluate_fuzzy(Godel, &out_of_range).is_err());
+    }
+}
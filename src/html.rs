@@ -1,12 +1,166 @@
 use std::fs::File;
+use std::io::Write;
 
-use crate::evaluator_result::EvaluatorResult;
+use crate::evaluator_result::{Classification, EvaluatorResult};
+use crate::render::{RenderOptions, Theme};
+
+const LIGHT: &str = "--bg:#ffffff;--fg:#1f2328;--border:#d0d7de;--head:#f6f8fa;--true:#dafbe1;--true-fg:#116329;--false:#ffebe9;--false-fg:#a40e26;--accent:#0969da;";
+const DARK: &str = "--bg:#0d1117;--fg:#e6edf3;--border:#30363d;--head:#161b22;--true:#12261e;--true-fg:#3fb950;--false:#2d1214;--false-fg:#f85149;--accent:#58a6ff;";
+
+const STYLE: &str = "
+body { background: var(--bg); color: var(--fg); font-family: system-ui, -apple-system, \"Segoe UI\", \"DejaVu Sans\", sans-serif; margin: 2rem; }
+h1 { font-size: 1.4rem; font-weight: 600; }
+h2 { font-size: 1.15rem; font-weight: 600; margin-top: 2rem; }
+table { border-collapse: collapse; margin: 1rem 0; }
+th, td { border: 1px solid var(--border); padding: .35rem .8rem; text-align: center; white-space: nowrap; }
+th { background: var(--head); font-weight: 600; }
+td.t { background: var(--true); color: var(--true-fg); }
+td.f { background: var(--false); color: var(--false-fg); }
+.result { border-left: 3px solid var(--accent); border-right: 3px solid var(--accent); font-weight: 700; }
+p.summary { font-size: 1.05rem; }
+p.summary strong { color: var(--accent); }
+";
 
 #[allow(dead_code)]
 impl EvaluatorResult {
+    /// a self-contained html5 document with the truth table, the expression as the heading
+    /// and the classification of the expression.
+    pub fn to_html(&self, options: &RenderOptions) -> String {
+        let expression = self
+            .result
+            .first()
+            .and_then(|row| row.last())
+            .map(|x| options.header(x.0))
+            .unwrap_or_default();
+        let body = format!("<h1>{}</h1>\n{}", escape(&expression), self.html_table(options));
+        html_document(&format!("Truth table: {}", expression), options, &body)
+    }
+
+    /// the table and the classification summary, without the document around them.
+    pub(crate) fn html_table(&self, options: &RenderOptions) -> String {
+        let mut out = String::new();
+        if let Some(header) = self.result.first().filter(|row| !row.is_empty()) {
+            let last = header.len() - 1;
+            let class = |index: usize| {
+                if options.highlight_result && index == last {
+                    " class=\"result\""
+                } else {
+                    ""
+                }
+            };
+            out.push_str("<table>\n<thead>\n<tr>");
+            for (i, h) in header.keys().enumerate() {
+                out.push_str(&format!("<th{}>{}</th>", class(i), escape(&options.header(h))));
+            }
+            out.push_str("</tr>\n</thead>\n<tbody>\n");
+            for row in self.result.iter() {
+                out.push_str("<tr>");
+                for (i, v) in row.values().enumerate() {
+                    let mut classes = vec![if *v { "t" } else { "f" }];
+                    if options.highlight_result && i == last {
+                        classes.push("result");
+                    }
+                    out.push_str(&format!(
+                        "<td class=\"{}\">{}</td>",
+                        classes.join(" "),
+                        escape(options.value(*v))
+                    ));
+                }
+                out.push_str("</tr>\n");
+            }
+            out.push_str("</tbody>\n</table>\n");
+
+            let column = self.final_column();
+            let trues = column.iter().filter(|v| **v).count();
+            let summary = match self.classification() {
+                Classification::Tautology => "true in every row",
+                Classification::Contradiction => "false in every row",
+                Classification::Contingency => "true in some rows and false in others",
+            };
+            out.push_str(&format!(
+                "<p class=\"summary\">The expression is a <strong>{}</strong>: {} ({} of {} rows are true).</p>\n",
+                self.classification(),
+                summary,
+                trues,
+                column.len()
+            ));
+        }
+        out
+    }
+
+    /// saves the html report with the final column highlighted.
     pub fn save_to_html(&self,file_name: &str) {
-        let table = self.get_table();
+        self.save_to_html_with_options(file_name, &RenderOptions::default().with_highlight_result(true));
+    }
+
+    pub fn save_to_html_with_options(&self,file_name: &str, options: &RenderOptions) {
         let mut out = File::create(file_name).unwrap();
-        table.print_html(&mut out).unwrap();
+        out.write_all(self.to_html(options).as_bytes()).unwrap();
+    }
+}
+
+/// wraps `body` in a html5 document with the styles of the report.
+pub(crate) fn html_document(title: &str, options: &RenderOptions, body: &str) -> String {
+    let theme = match options.theme {
+        Theme::Light => format!(":root {{ {} }}", LIGHT),
+        Theme::Dark => format!(":root {{ {} }}", DARK),
+        Theme::Auto => format!(
+            ":root {{ {} }}\n@media (prefers-color-scheme: dark) {{ :root {{ {} }} }}",
+            LIGHT, DARK
+        ),
+    };
+    let mut out = String::new();
+    out.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n");
+    out.push_str(&format!("<title>{}</title>\n", escape(title)));
+    out.push_str(&format!("<style>\n{}{}</style>\n</head>\n<body>\n", theme, STYLE));
+    out.push_str(body);
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+pub(crate) fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::Evaluator;
+    use crate::tokenizer::Tokens;
+    use indexmap::IndexMap;
+
+    #[test]
+    fn report() {
+        let result = Evaluator::new(Tokens::from_text("p -> q")).unwrap().evaluate_all().unwrap();
+        let options = RenderOptions::default().with_highlight_result(true);
+        let html = result.to_html(&options);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<meta charset=\"utf-8\">"));
+        assert!(html.contains("<h1>(p → q)</h1>"));
+        assert!(html.contains("<th>p</th><th>q</th><th class=\"result\">(p → q)</th>"));
+        assert!(html.contains("<td class=\"f result\">false</td>"));
+        assert!(html.contains("<strong>contingency</strong>"));
+        let ascii = result.to_html(&RenderOptions::default().with_symbols(crate::render::SymbolSet::Ascii));
+        assert!(ascii.contains("<h1>(p -&gt; q)</h1>"));
+        assert!(!ascii.contains("class=\"result\""));
+        assert_eq!(escape("<a & 'b'>"), "&lt;a &amp; &#39;b&#39;&gt;");
+    }
+
+    #[test]
+    fn empty_rows() {
+        let result = EvaluatorResult { result: vec![IndexMap::new()] };
+        assert_eq!(result.html_table(&RenderOptions::default()), "");
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,128 @@
+use indexmap::IndexMap;
+
+use crate::completeness::BooleanFunction;
+use crate::evaluator::{Evaluator, EvaluatorError};
+
+impl BooleanFunction {
+    /// the bit of the argument `k` in a row index, the first argument being the most significant.
+    fn bit(&self, k: usize) -> usize {
+        1 << (self.arity() - k - 1)
+    }
+
+    /// the fraction of the rows where flipping the argument `k` flips the value.
+    pub fn influence(&self, k: usize) -> f64 {
+        let table = self.table();
+        let bit = self.bit(k);
+        let flips = (0..table.len()).filter(|x| table[*x] != table[x ^ bit]).count();
+        flips as f64 / table.len() as f64
+    }
+
+    pub fn influences(&self) -> Vec<f64> {
+        (0..self.arity()).map(|k| self.influence(k)).collect()
+    }
+
+    /// whether some row changes its value when the argument `k` is flipped.
+    pub fn depends_on(&self, k: usize) -> bool {
+        self.influence(k) > 0.0
+    }
+
+    /// the number of arguments that flip the value of the row `x` when flipped alone.
+    pub fn sensitivity_at(&self, x: usize) -> usize {
+        let table = self.table();
+        (0..self.arity()).filter(|k| table[x] != table[x ^ self.bit(*k)]).count()
+    }
+
+    /// the largest sensitivity of a row.
+    pub fn sensitivity(&self) -> usize {
+        (0..self.table().len()).map(|x| self.sensitivity_at(x)).max().unwrap_or(0)
+    }
+
+    /// the sum of the influences, which is also the average sensitivity of the rows.
+    pub fn total_sensitivity(&self) -> f64 {
+        self.influences().iter().sum()
+    }
+
+    /// the largest number of disjoint sets of arguments that each flip the value of the row `x`
+    /// when flipped together. Every subset is tried, so this is meant for a handful of variables.
+    pub fn block_sensitivity_at(&self, x: usize) -> usize {
+        let table = self.table();
+        let sensitive: Vec<usize> = (1..table.len()).filter(|b| table[x] != table[x ^ b]).collect();
+        //packing[mask] is the most disjoint sensitive blocks inside mask.
+        let mut packing = vec![0usize; table.len()];
+        for mask in 1..table.len() {
+            let lowest = mask & mask.wrapping_neg();
+            let mut best = packing[mask ^ lowest];
+            for block in sensitive.iter().filter(|b| *b & lowest != 0 && *b & mask == **b) {
+                best = best.max(1 + packing[mask ^ block]);
+            }
+            packing[mask] = best;
+        }
+        packing[table.len() - 1]
+    }
+
+    /// the largest block sensitivity of a row.
+    pub fn block_sensitivity(&self) -> usize {
+        (0..self.table().len()).map(|x| self.block_sensitivity_at(x)).max().unwrap_or(0)
+    }
+}
+
+impl Evaluator {
+    /// the influence of each variable, in order of appearance.
+    pub fn influences(&self) -> Result<IndexMap<String, f64>, EvaluatorError> {
+        let function = BooleanFunction::from_evaluator(self)?;
+        Ok(self.variables().into_iter().zip(function.influences()).collect())
+    }
+
+    /// the variables that never change the value of the expression.
+    pub fn irrelevant_variables(&self) -> Result<Vec<String>, EvaluatorError> {
+        Ok(self.influences()?.into_iter().filter(|(_, i)| *i == 0.0).map(|(v, _)| v).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::Tokens;
+
+    fn evaluator(s: &str) -> Evaluator {
+        Evaluator::new(Tokens::from_text(s)).unwrap()
+    }
+
+    fn function(s: &str) -> BooleanFunction {
+        BooleanFunction::from_evaluator(&evaluator(s)).unwrap()
+    }
+
+    #[test]
+    fn irrelevant_variables() {
+        assert_eq!(evaluator("p or p and q").irrelevant_variables().unwrap(), ["q"]);
+        assert_eq!(evaluator("(p or not p) and q").irrelevant_variables().unwrap(), ["p"]);
+        //`and` binds tighter, this is p ∨ q.
+        assert!(evaluator("p or not p and q").irrelevant_variables().unwrap().is_empty());
+        assert_eq!(evaluator("ite(c, a, a) xor b").irrelevant_variables().unwrap(), ["c"]);
+        let influences = evaluator("p and q").influences().unwrap();
+        assert_eq!(influences["p"], 0.5);
+        assert!(!function("p or p and q").depends_on(1));
+    }
+
+    #[test]
+    fn sensitivity() {
+        let xor = function("xor(a, b, c)");
+        assert_eq!(xor.influences(), [1.0, 1.0, 1.0]);
+        assert_eq!(xor.sensitivity(), 3);
+        assert_eq!(xor.total_sensitivity(), 3.0);
+        let or = function("a or b or c");
+        assert_eq!(or.sensitivity(), 3);
+        assert_eq!(or.block_sensitivity(), 3);
+        assert_eq!(or.total_sensitivity(), 0.75);
+        //a single flip is a block, so block sensitivity is never smaller.
+        for s in ["(a and b) or (c and d)", "exactly(2, a, b, c, d)", "ite(a, b, c) -> d"] {
+            let f = function(s);
+            assert!(f.block_sensitivity() >= f.sensitivity());
+        }
+        let maj = function("maj(a, b, c)");
+        assert_eq!(maj.sensitivity(), 2);
+        //at 000 every sensitive block needs two of the three variables.
+        assert_eq!(maj.block_sensitivity_at(0), 1);
+        assert_eq!(maj.block_sensitivity(), 2);
+    }
+}
@@ -0,0 +1,93 @@
+use std::fs::File;
+
+use serde::{Deserialize, Serialize};
+
+use crate::evaluator::{Evaluator, EvaluatorError};
+use crate::evaluator_result::{Classification, EvaluatorResult};
+use crate::tokenizer::Tokens;
+
+/// The json layout of a truth table.
+///
+/// ```json
+/// {
+///   "expression": "p ∧ q",
+///   "variables": ["p", "q"],
+///   "columns": ["p", "q", "(p ∧ q)"],
+///   "rows": [[1, 1, 1], [1, 0, 0], [0, 1, 0], [0, 0, 0]],
+///   "classification": "contingency"
+/// }
+/// ```
+/// `rows` are in the order of `Evaluator::evaluate_all` and hold one bit per column. The
+/// variables are the first columns and the last column is the value of the whole expression.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TruthTable {
+    pub expression: String,
+    pub variables: Vec<String>,
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<u8>>,
+    pub classification: Classification,
+}
+
+impl TruthTable {
+    pub fn new(evaluator: &Evaluator) -> Result<Self, EvaluatorError> {
+        Ok(Self::from_result(evaluator, &evaluator.evaluate_all()?))
+    }
+
+    /// the layout of `result`, a full truth table of `evaluator` that may have extra columns
+    /// (like the named definitions of `Definitions::evaluate_all`).
+    pub fn from_result(evaluator: &Evaluator, result: &EvaluatorResult) -> Self {
+        let columns = result
+            .result
+            .first()
+            .map(|row| row.keys().cloned().collect())
+            .unwrap_or_default();
+        let rows = result
+            .result
+            .iter()
+            .map(|row| row.values().map(|v| u8::from(*v)).collect())
+            .collect();
+        TruthTable {
+            expression: Tokens::from(evaluator.clone()).to_string(),
+            variables: evaluator.variables(),
+            columns,
+            rows,
+            classification: result.classification(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn save_to_json(&self, file_name: &str) {
+        let out = File::create(file_name).unwrap();
+        serde_json::to_writer_pretty(out, self).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schema() {
+        let evaluator = Evaluator::new(Tokens::from_text("p and q")).unwrap();
+        let table = TruthTable::new(&evaluator).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&table.to_json()).unwrap();
+        assert_eq!(json["variables"], serde_json::json!(["p", "q"]));
+        assert_eq!(json["columns"], serde_json::json!(["p", "q", "(p ∧ q)"]));
+        assert_eq!(json["rows"][1], serde_json::json!([1, 0, 0]));
+        assert_eq!(json["classification"], "contingency");
+        let back: TruthTable = serde_json::from_value(json).unwrap();
+        assert_eq!(back, table);
+    }
+
+    #[test]
+    fn evaluator_round_trip() {
+        let evaluator = Evaluator::new(Tokens::from_text("p -> q")).unwrap();
+        let json = serde_json::to_string(&evaluator).unwrap();
+        let back: Evaluator = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&back).unwrap(), json);
+        assert!(serde_json::from_str::<Evaluator>(r#"[{"Ident":"p"},"CloseParen"]"#).is_err());
+    }
+}
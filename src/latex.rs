@@ -0,0 +1,168 @@
+use std::fs::File;
+use std::io::Write;
+
+use crate::evaluator_result::EvaluatorResult;
+use crate::render::{RenderOptions, ValueStyle};
+
+#[allow(dead_code)]
+impl EvaluatorResult {
+    /// renders a `tabular` environment. headers are typeset in math mode with latex operators,
+    /// so the symbol set of the options is not used.
+    pub fn to_latex(&self, options: &RenderOptions) -> String {
+        let mut out = String::new();
+        let Some(header) = self.result.first() else {
+            return out;
+        };
+        let last = header.len().saturating_sub(1);
+        let highlight = |index: usize| options.highlight_result && index == last;
+        out.push_str(&format!("\\begin{{tabular}}{{|{}}}\n\\hline\n", "c|".repeat(header.len())));
+        let header: Vec<String> = header
+            .keys()
+            .enumerate()
+            .map(|(i, h)| {
+                if highlight(i) {
+                    format!("{{\\boldmath ${}$}}", math(h))
+                } else {
+                    format!("${}$", math(h))
+                }
+            })
+            .collect();
+        out.push_str(&format!("{} \\\\\n\\hline\n", header.join(" & ")));
+        for row in self.result.iter() {
+            let values: Vec<String> = row
+                .values()
+                .enumerate()
+                .map(|(i, v)| {
+                    let value = value(options, *v);
+                    if highlight(i) {
+                        format!("\\textbf{{{}}}", value)
+                    } else {
+                        value
+                    }
+                })
+                .collect();
+            out.push_str(&format!("{} \\\\\n", values.join(" & ")));
+        }
+        out.push_str("\\hline\n\\end{tabular}\n");
+        out
+    }
+
+    pub fn save_to_latex(&self, file_name: &str) {
+        self.save_to_latex_with_options(file_name, &RenderOptions::default());
+    }
+
+    pub fn save_to_latex_with_options(&self, file_name: &str, options: &RenderOptions) {
+        let mut out = File::create(file_name).unwrap();
+        out.write_all(self.to_latex(options).as_bytes()).unwrap();
+    }
+}
+
+fn latex_symbol(symbol: char) -> Option<&'static str> {
+    match symbol {
+        '¬' => Some("\\neg "),
+        '□' => Some("\\Box "),
+        '◇' => Some("\\Diamond "),
+        '∧' => Some("\\land"),
+        '∨' => Some("\\lor"),
+        '⊕' => Some("\\oplus"),
+        '→' => Some("\\rightarrow"),
+        '←' => Some("\\leftarrow"),
+        '↛' => Some("\\not\\rightarrow"),
+        '↑' => Some("\\uparrow"),
+        '↓' => Some("\\downarrow"),
+        '↔' => Some("\\leftrightarrow"),
+        '≡' => Some("\\equiv"),
+        '≠' => Some("\\neq"),
+        _ => None,
+    }
+}
+
+fn value(options: &RenderOptions, value: bool) -> String {
+    match (&options.values, value) {
+        (ValueStyle::Symbols, true) => "$\\top$".into(),
+        (ValueStyle::Symbols, false) => "$\\bot$".into(),
+        _ => escape(options.value(value)),
+    }
+}
+
+/// escapes text for use outside math mode.
+pub(crate) fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '~' => out.push_str("\\textasciitilde{}"),
+            '^' => out.push_str("\\textasciicircum{}"),
+            '\\' => out.push_str("\\textbackslash{}"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// converts a column header to math mode. single letters stay as they are, longer names
+/// (like `true`) are set upright.
+fn math(header: &str) -> String {
+    let mut out = String::with_capacity(header.len());
+    let mut word = String::new();
+    fn flush(word: &mut String, out: &mut String) {
+        match word.chars().count() {
+            0 => {}
+            1 => out.push_str(word),
+            _ => out.push_str(&format!("\\mathrm{{{}}}", escape(word))),
+        }
+        word.clear();
+    }
+    for c in header.chars() {
+        if c.is_alphanumeric() || c == '_' {
+            word.push(c);
+            continue;
+        }
+        flush(&mut word, &mut out);
+        match latex_symbol(c) {
+            Some(s) => out.push_str(s),
+            None => match c {
+                '{' | '}' | '#' | '%' | '&' | '$' => {
+                    out.push('\\');
+                    out.push(c);
+                }
+                '\\' => out.push_str("\\backslash "),
+                _ => out.push(c),
+            },
+        }
+    }
+    flush(&mut word, &mut out);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::evaluator::Evaluator;
+    use crate::tokenizer::Tokens;
+
+    use super::*;
+
+    #[test]
+    fn headers() {
+        assert_eq!(math("((P ∧ ¬Q) ↔ (P → Q))"), "((P \\land \\neg Q) \\leftrightarrow (P \\rightarrow Q))");
+        assert_eq!(math("(true ∨ p)"), "(\\mathrm{true} \\lor p)");
+    }
+
+    #[test]
+    fn table() {
+        let evaluator = Evaluator::new(Tokens::from_text("not p")).unwrap();
+        let result = evaluator.evaluate_all().unwrap();
+        let options = RenderOptions::default()
+            .with_values(ValueStyle::Custom { t: "100%".into(), f: "a_b".into() })
+            .with_highlight_result(true);
+        let latex = result.to_latex(&options);
+        assert_eq!(
+            latex,
+            "\\begin{tabular}{|c|c|}\n\\hline\n$p$ & {\\boldmath $\\neg p$} \\\\\n\\hline\n\
+             100\\% & \\textbf{a\\_b} \\\\\na\\_b & \\textbf{100\\%} \\\\\n\\hline\n\\end{tabular}\n"
+        );
+    }
+}
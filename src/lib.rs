@@ -1,9 +1,37 @@
+pub mod analysis;
+pub mod anf;
+pub mod basis;
+pub mod batch;
+pub mod canonical;
+pub mod completeness;
+pub mod definitions;
 pub mod evaluator;
 pub mod evaluator_result;
+pub mod expression;
+pub mod first_order;
+pub mod fuzzy;
+pub mod influence;
+pub mod ltl;
+pub mod modal;
+pub mod models;
+pub mod partial;
+pub mod qbf;
+pub mod render;
+pub mod repl;
+pub mod simplify;
+pub mod synthesis;
+pub mod three_valued;
 pub mod tokenizer;
+pub mod walsh;
 #[cfg(feature = "pdf")]
 pub mod pdf;
 #[cfg(feature = "csv")]
 pub mod csv;
 #[cfg(feature = "html")]
-pub mod html;
\ No newline at end of file
+pub mod html;
+#[cfg(feature = "markdown")]
+pub mod markdown;
+#[cfg(feature = "latex")]
+pub mod latex;
+#[cfg(feature = "serde")]
+pub mod json;
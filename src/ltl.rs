@@ -0,0 +1,336 @@
+use std::fmt;
+
+use indexmap::IndexMap;
+
+use crate::evaluator::{apply, Evaluator, EvaluatorError};
+use crate::tokenizer::{Temporal, Token, Tokens};
+
+/// A formula of linear temporal logic over finite traces (LTLf): the propositional connectives
+/// with `X`, `F` and `G` before an operand and `U` and `R` between two. `□` is read as `G` and
+/// `◇` as `F`. The letters stay names where they can not be operators, as in `F ∧ G`.
+#[derive(Clone)]
+pub struct LtlFormula {
+    evaluator: Evaluator,
+}
+
+/// The values of the variables at each step of a run, steps count from 0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trace {
+    variables: Vec<String>,
+    steps: Vec<Vec<bool>>,
+}
+
+/// a column of the evaluation: the operator, the indexes of its operands and the value per step.
+struct Column {
+    operator: Option<Token>,
+    operands: Vec<usize>,
+    steps: Vec<bool>,
+}
+
+impl LtlFormula {
+    pub fn new(tokens: Tokens) -> Result<Self, EvaluatorError> {
+        Ok(LtlFormula { evaluator: Evaluator::with_modalities(temporal(tokens))? })
+    }
+
+    pub fn from_text(text: &str) -> Result<Self, EvaluatorError> {
+        Self::new(Tokens::from_text(text))
+    }
+
+    /// the variables in order of appearance.
+    pub fn variables(&self) -> Vec<String> {
+        self.evaluator.variables()
+    }
+
+    /// the value of each column of the formula at each step, the last column is the formula.
+    pub fn evaluate(&self, trace: &Trace) -> Result<IndexMap<String, Vec<bool>>, EvaluatorError> {
+        let (names, mut columns) = self.columns(trace)?;
+        Ok(names.into_iter().map(|(name, i)| (name, std::mem::take(&mut columns[i].steps))).collect())
+    }
+
+    /// whether the formula is true at the first step.
+    pub fn holds(&self, trace: &Trace) -> Result<bool, EvaluatorError> {
+        Ok(self.first_failing_step(trace)?.is_none())
+    }
+
+    /// the step that makes the formula false, `None` when it is true at the first step. For
+    /// `G a` it is the step that makes `a` false first, for `X a` the one that makes `a` false
+    /// and for `a ∧ b` the earlier one of `a` and `b`; any other false formula fails at step 0.
+    pub fn first_failing_step(&self, trace: &Trace) -> Result<Option<usize>, EvaluatorError> {
+        let (names, columns) = self.columns(trace)?;
+        let root = names.last().map(|(_, i)| *i).unwrap_or_default();
+        Ok(failing(&columns, root, 0))
+    }
+
+    fn columns(&self, trace: &Trace) -> Result<(IndexMap<String, usize>, Vec<Column>), EvaluatorError> {
+        if trace.is_empty() {
+            return Err(EvaluatorError::new("The trace has no steps."));
+        }
+        let n = trace.len();
+        let constant = |value: bool| Column { operator: None, operands: Vec::new(), steps: vec![value; n] };
+        let mut columns = vec![constant(false), constant(true)];
+        let mut values = IndexMap::new();
+        for variable in self.variables() {
+            let steps = trace
+                .column(&variable)
+                .ok_or_else(|| EvaluatorError::new(format!("'{}' is not a variable of the trace.", variable)))?;
+            values.insert(variable, columns.len());
+            columns.push(Column { operator: None, operands: Vec::new(), steps });
+        }
+        let names = self.evaluator.evaluate_with(&values, |value| value as usize, |operator, count, operands| {
+            let steps = step_values(operator, count, operands.iter().map(|i| &columns[*i].steps[..]).collect());
+            columns.push(Column { operator: Some(operator.clone()), operands: operands.to_vec(), steps });
+            columns.len() - 1
+        })?;
+        Ok((names, columns))
+    }
+}
+
+impl fmt::Display for LtlFormula {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Tokens::from(self.evaluator.clone()))
+    }
+}
+
+/// the values of an operator at each step. The temporal ones are computed from the last step
+/// back, the next step of the last one does not exist.
+fn step_values(operator: &Token, count: usize, operands: Vec<&[bool]>) -> Vec<bool> {
+    let n = operands.first().map(|o| o.len()).unwrap_or_default();
+    let mut steps = vec![false; n];
+    let next = |steps: &[bool], i: usize| i + 1 < n && steps[i + 1];
+    match (operator, operands.as_slice()) {
+        (Token::Temporal(Temporal::Next), [a]) => (0..n).for_each(|i| steps[i] = next(a, i)),
+        (Token::Temporal(Temporal::Eventually), [a]) => {
+            (0..n).rev().for_each(|i| steps[i] = a[i] || next(&steps, i))
+        }
+        (Token::Temporal(Temporal::Always), [a]) => {
+            (0..n).rev().for_each(|i| steps[i] = a[i] && (i + 1 == n || steps[i + 1]))
+        }
+        (Token::Temporal(Temporal::Until), [a, b]) => {
+            (0..n).rev().for_each(|i| steps[i] = b[i] || (a[i] && next(&steps, i)))
+        }
+        (Token::Temporal(Temporal::Release), [a, b]) => {
+            (0..n).rev().for_each(|i| steps[i] = b[i] && (a[i] || i + 1 == n || steps[i + 1]))
+        }
+        _ => {
+            for (i, step) in steps.iter_mut().enumerate() {
+                let mut values: Vec<Token> = operands.iter().map(|o| Token::from(o[i])).collect();
+                if matches!(operator, Token::Function(f) if f.takes_count()) {
+                    values.insert(0, Token::Number(count));
+                }
+                *step = apply(operator, &values);
+            }
+        }
+    }
+    steps
+}
+
+/// the step that makes the column false where it has to be true from `from` on.
+fn failing(columns: &[Column], column: usize, from: usize) -> Option<usize> {
+    let Column { operator, operands, steps } = &columns[column];
+    if steps[from] {
+        return None;
+    }
+    match (operator, operands.as_slice()) {
+        (Some(Token::Temporal(Temporal::Always)), [a]) => {
+            let step = (from..steps.len()).find(|i| !columns[*a].steps[*i])?;
+            failing(columns, *a, step)
+        }
+        (Some(Token::Temporal(Temporal::Next)), [a]) if from + 1 < steps.len() => failing(columns, *a, from + 1),
+        (Some(Token::And(_)), [a, b]) => [*a, *b].iter().filter_map(|o| failing(columns, *o, from)).min(),
+        _ => Some(from),
+    }
+}
+
+/// reads the letters of the temporal operators as operators where they fit: `X`, `F` and `G`
+/// at the start of an operand followed by one, `U` and `R` between two operands.
+fn temporal(mut tokens: Tokens) -> Tokens {
+    let starts_operand = |t: Option<&Token>| {
+        matches!(
+            t,
+            Some(
+                Token::Ident(_) | Token::True | Token::False |
+                Token::OpenParen | Token::OpenBracket | Token::OpenCurlyBrace |
+                Token::Not(_) | Token::Necessarily(_) | Token::Possibly(_) | Token::Function(_) | Token::XOr(_)
+            )
+        )
+    };
+    for i in 0..tokens.len() {
+        let ends_operand = i > 0 && matches!(
+            tokens[i - 1],
+            Token::Ident(_) | Token::True | Token::False | Token::Number(_) |
+            Token::CloseParen | Token::CloseBracket | Token::CloseCurlyBrace
+        );
+        let operator = match &tokens[i] {
+            Token::Necessarily(_) => Temporal::Always,
+            Token::Possibly(_) => Temporal::Eventually,
+            Token::Ident(name) if starts_operand(tokens.get(i + 1)) => match (name.as_str(), ends_operand) {
+                ("X", false) => Temporal::Next,
+                ("F", false) => Temporal::Eventually,
+                ("G", false) => Temporal::Always,
+                ("U", true) => Temporal::Until,
+                ("R", true) => Temporal::Release,
+                _ => continue,
+            },
+            _ => continue,
+        };
+        tokens[i] = Token::Temporal(operator);
+    }
+    tokens
+}
+
+impl Trace {
+    /// the steps in order, each with the value of every variable of the first one.
+    pub fn new(steps: &[IndexMap<String, bool>]) -> Result<Self, EvaluatorError> {
+        let variables: Vec<String> = steps.first().map(|s| s.keys().cloned().collect()).unwrap_or_default();
+        let mut rows = Vec::new();
+        for (i, step) in steps.iter().enumerate() {
+            let row = variables
+                .iter()
+                .map(|v| step.get(v).copied())
+                .collect::<Option<Vec<bool>>>()
+                .ok_or_else(|| EvaluatorError::new(format!("Step {} does not have the variables of step 0.", i)))?;
+            rows.push(row);
+        }
+        Ok(Trace { variables, steps: rows })
+    }
+
+    /// a table saved by `save_to_csv`, or a log in the same layout, one step per row. The
+    /// columns that are not a single name, like `(p ∧ q)`, and the columns with other values than
+    /// true/false, T/F, 1/0 or ⊤/⊥, like a time stamp, are left out.
+    #[cfg(feature = "csv")]
+    pub fn from_csv(text: &str) -> Result<Self, EvaluatorError> {
+        use crate::synthesis::{csv_fields, parse_value};
+        let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+        let header = lines.next().map(csv_fields).unwrap_or_default();
+        let mut rows = Vec::new();
+        for (i, line) in lines.enumerate() {
+            let row = csv_fields(line);
+            if row.len() != header.len() {
+                return Err(EvaluatorError::new(format!(
+                    "Row {} has {} values for {} columns.",
+                    i + 1,
+                    row.len(),
+                    header.len()
+                )));
+            }
+            rows.push(row);
+        }
+        let mut variables = Vec::new();
+        let mut columns = Vec::new();
+        for (k, name) in header.iter().enumerate() {
+            let tokens = Tokens::from_text(name);
+            if tokens.len() != 1 || !tokens.unknown().is_empty() || !matches!(tokens[0], Token::Ident(_)) {
+                continue;
+            }
+            if let Some(column) = rows.iter().map(|row| parse_value(&row[k])).collect::<Option<Vec<bool>>>() {
+                variables.push(name.trim().to_string());
+                columns.push(column);
+            }
+        }
+        let steps = (0..rows.len()).map(|i| columns.iter().map(|column| column[i]).collect()).collect();
+        Ok(Trace { variables, steps })
+    }
+
+    #[cfg(feature = "csv")]
+    pub fn from_csv_file(file_name: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self::from_csv(&std::fs::read_to_string(file_name)?)?)
+    }
+
+    pub fn variables(&self) -> &[String] {
+        &self.variables
+    }
+
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    pub fn step(&self, index: usize) -> Option<IndexMap<String, bool>> {
+        let row = self.steps.get(index)?;
+        Some(self.variables.iter().cloned().zip(row.iter().copied()).collect())
+    }
+
+    fn column(&self, variable: &str) -> Option<Vec<bool>> {
+        let k = self.variables.iter().position(|v| v == variable)?;
+        Some(self.steps.iter().map(|row| row[k]).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a trace over `variables` with one string of `0` and `1` per step.
+    fn trace(variables: &str, steps: &[&str]) -> Trace {
+        let variables: Vec<&str> = variables.split_whitespace().collect();
+        let steps: Vec<IndexMap<String, bool>> = steps
+            .iter()
+            .map(|step| variables.iter().zip(step.chars()).map(|(v, c)| (v.to_string(), c == '1')).collect())
+            .collect();
+        Trace::new(&steps).unwrap()
+    }
+
+    fn steps(formula: &str, trace: &Trace) -> String {
+        let result = LtlFormula::from_text(formula).unwrap().evaluate(trace).unwrap();
+        result.last().unwrap().1.iter().map(|v| if *v { '1' } else { '0' }).collect()
+    }
+
+    #[test]
+    fn operators() {
+        let t = trace("p q", &["10", "10", "01", "00", "10"]);
+        assert_eq!(steps("X p", &t), "10010");
+        assert_eq!(steps("F q", &t), "11100");
+        assert_eq!(steps("G !q", &t), "00011");
+        assert_eq!(steps("p U q", &t), "11100");
+        assert_eq!(steps("q R !q", &t), "00011");
+        assert_eq!(steps("G(p -> F q)", &t), "00000");
+        assert_eq!(steps("X p ∧ p U q", &t), "10000");
+        assert_eq!(steps("□◇p", &t), "11111");
+        assert_eq!(steps("exactly(1, p, X p)", &t), "01011");
+    }
+
+    #[test]
+    fn letters() {
+        let formula = LtlFormula::from_text("G(req -> F ack) and X X(F or R)").unwrap();
+        assert_eq!(formula.to_string(), "G (req → F ack) ∧ X X (F ∨ R)");
+        assert_eq!(formula.variables(), ["req", "ack", "F", "R"]);
+        assert_eq!(LtlFormula::from_text(&formula.to_string()).unwrap().to_string(), formula.to_string());
+        let error = Evaluator::new(LtlFormula::from_text("p U q").unwrap().evaluator.into()).err().unwrap();
+        assert!(error.to_string().contains("temporal"));
+        assert!(LtlFormula::from_text("p U").is_err());
+    }
+
+    #[test]
+    fn failing_steps() {
+        let t = trace("req ack", &["10", "00", "01", "10", "00"]);
+        let check = |s: &str| LtlFormula::from_text(s).unwrap().first_failing_step(&t).unwrap();
+        assert_eq!(check("G(req -> F ack)"), Some(3));
+        assert_eq!(check("G(req -> X(!req U ack))"), Some(3));
+        assert_eq!(check("G !ack ∧ G(ack -> X req)"), Some(2));
+        assert_eq!(check("X X G !req"), Some(3));
+        assert_eq!(check("F ack"), None);
+        assert_eq!(check("ack"), Some(0));
+        let missing = LtlFormula::from_text("G r").unwrap().first_failing_step(&t);
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "csv")]
+    fn csv() {
+        use crate::render::{RenderOptions, ValueStyle};
+        let result = Evaluator::new(Tokens::from_text("p and q")).unwrap().evaluate_all().unwrap();
+        let csv = result.to_csv(&RenderOptions::default().with_values(ValueStyle::Digits));
+        let trace = Trace::from_csv(&csv).unwrap();
+        assert_eq!(trace.variables(), ["p", "q"]);
+        assert_eq!(trace.len(), 4);
+        assert_eq!(trace.step(0).unwrap(), IndexMap::from([("p".to_string(), true), ("q".to_string(), true)]));
+        let formula = LtlFormula::from_text("G(p or q) ∧ F(p ≠ q)").unwrap();
+        assert_eq!(formula.first_failing_step(&trace).unwrap(), Some(3));
+        let log = Trace::from_csv("time,p,q\n10:00,1,0\n10:05,0,0\n").unwrap();
+        assert_eq!(log.variables(), ["p", "q"]);
+        assert!(Trace::from_csv("p,q\n1,0,1\n").is_err());
+    }
+}
@@ -1,73 +1,950 @@
+use std::io::{BufRead, Read, Write};
+use std::process::ExitCode;
+
+use boolean_logic::anf::Anf;
+use boolean_logic::basis::Basis;
+use boolean_logic::batch::{BatchReport, ReportKind};
+use boolean_logic::canonical::{maxterm_notation, minterm_notation};
+use boolean_logic::completeness::BooleanFunction;
+use boolean_logic::definitions::Definitions;
 use boolean_logic::evaluator::Evaluator;
-use boolean_logic::evaluator_result;
+use boolean_logic::evaluator_result::{Classification, EvaluatorResult};
+use boolean_logic::first_order::{Formula, Model};
+use boolean_logic::fuzzy::TNorm;
+use boolean_logic::ltl::{LtlFormula, Trace};
+use boolean_logic::modal::{KripkeModel, ModalFormula, System};
+use boolean_logic::qbf::Qbf;
+use boolean_logic::render::RenderOptions;
+use boolean_logic::synthesis::Specification;
+use boolean_logic::repl::{Output, Session};
+use boolean_logic::three_valued::Logic;
 use boolean_logic::tokenizer::Tokens;
+use indexmap::IndexMap;
+
+const USAGE: &str = "\
+Usage: main [COMMAND] [OPTIONS] [EXPRESSION...]
+
+Commands:
+  table     print the truth table of the expression (default)
+  check     check that the expression is a tautology (see --expect)
+  equiv     check that two expressions are equivalent: main equiv EXPR1 EXPR2
+  sat       print an assignment that makes the expression true
+  models    print every assignment that makes the expression true (see --project)
+  count     print the number of models, exact for any number of variables (see --project)
+  residual  print the expression with the values of --set put in and folded away
+  fuzzy     print the degree of every column for the truth degrees of --degrees (see --tnorm)
+  simplify  print an equivalent minimal sum of products
+  canonical print the minterms (Σm), the maxterms (ΠM), the sum of minterms and the product of maxterms
+  anf       print the algebraic normal form (xor of and-monomials) and its degree
+  walsh     print the Walsh spectrum, nonlinearity, correlation immunity, resiliency, algebraic
+            immunity and autocorrelation of an expression or a truth vector like 0110
+  influence print the influence of each variable, the irrelevant ones and the sensitivities
+  rewrite   print an equivalent expression that only uses the connectives of --basis
+  synth     print formulas for a truth table given as bits (0110), minterms (Σm(1,2)), maxterms
+            (ΠM(0,3)) or a csv file saved by the csv format: main synth --inputs p,q 0110
+  fol       check a first-order formula in the model of --model, or find a model over --domain:
+            main fol --model people.txt 'forall x. exists y. Likes(x, y)'
+  qbf       decide a quantified formula like '∀p ∃q. p ↔ q' and print the functions of the
+            winning side: Skolem functions when it is true, a counter-strategy when it is false
+  modal     evaluate a formula with □ (box) and ◇ (dia) at each world of the Kripke model of
+            --model and list the properties of its frame: main modal --model kripke.txt 'box p -> p'
+  ltl       check a temporal formula with X, F, G, U and R against the steps of --trace and print
+            the first failing step: main ltl --trace log.csv 'G(req -> F ack)'
+  repl      start an interactive session (history is kept in .boolean_logic_history)
+  batch     evaluate every line of a file: main batch FILE
+
+The expression is read from the arguments, or from stdin when it is missing or `-`.
+
+Options:
+  -f, --format FORMAT   terminal, csv, html, pdf, json, markdown or latex (default terminal),
+                        each but terminal needs the cargo feature of the same name (serde for json).
+                        Only for tables (table, sat, batch), the other commands write text
+  -o, --output FILE     write to FILE instead of stdout (required for pdf)
+  -d, --definitions FILE  read `name := EXPR` lines, the names can be used in the expressions
+      --named           add a column for each definition used by the expression (table, batch)
+  -v, --values STYLE    words, letters, digits, symbols or a custom pair like yes/no
+  -s, --symbols SET     unicode or ascii operators in the headers
+      --highlight       emphasize the result column
+      --theme THEME     light, dark or auto (html)
+      --expect KIND     tautology, contradiction, contingency or satisfiable (check)
+      --basis BASIS     and ({¬, ∧}), or ({¬, ∨}), implication ({→, ¬}), nand or nor (rewrite, default nand)
+      --inputs NAMES    comma separated names of the variables of a bit vector or minterms (synth)
+      --project NAMES   comma separated variables to list or count the models on (models, count)
+      --set VALUES      known values like p=1,q=false, the other variables stay (residual)
+      --degrees VALUES  truth degrees between 0 and 1 like p=0.7,q=0.2 (fuzzy)
+      --tnorm NORM      godel (min and max), product or lukasiewicz (fuzzy, default godel)
+      --model FILE      a finite model: `domain = {a, b}`, `R = {(a, b)}`, `P(a)`, `p = true` lines (fol),
+                        or worlds, `R` for the accessibility and `p = {w1}` per variable (modal)
+      --domain NAMES    comma separated elements to search a model over (fol)
+      --trace FILE      a csv file with a column per variable and a row per step, like the csv
+                        format writes (ltl)
+      --logic LOGIC     kleene, bochvar or lukasiewicz: a table over true, unknown and false (table)
+      --summary         one line with the classification per expression instead of tables (batch)
+  -h, --help            print this help
+
+Exit codes: 0 success, 1 the check failed, 2 invalid arguments or expression, 3 output error.
+A batch reports every line it can and exits with 2 when some lines are invalid.";
+
+const EXIT_FAILED: u8 = 1;
+const EXIT_INVALID: u8 = 2;
+const EXIT_OUTPUT: u8 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Command {
+    /// a command on the expression of the arguments, see `Action`.
+    Expression(Action),
+    Walsh,
+    Synth,
+    Fol,
+    Qbf,
+    Modal,
+    Ltl,
+    Repl,
+    Batch,
+}
+
+/// the commands that compile the expression of the arguments (two for `equiv`) with the
+/// definitions of `--definitions`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Action {
+    Table,
+    Check,
+    Equiv,
+    Sat,
+    Models,
+    Count,
+    Residual,
+    Fuzzy,
+    Simplify,
+    Canonical,
+    Anf,
+    Influence,
+    Rewrite,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Terminal,
+    #[cfg(feature = "csv")]
+    Csv,
+    #[cfg(feature = "html")]
+    Html,
+    #[cfg(feature = "pdf")]
+    Pdf,
+    #[cfg(feature = "serde")]
+    Json,
+    #[cfg(feature = "markdown")]
+    Markdown,
+    #[cfg(feature = "latex")]
+    Latex,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Expectation {
+    Is(Classification),
+    Satisfiable,
+}
+
+struct Args {
+    command: Command,
+    expressions: Vec<String>,
+    format: Format,
+    output: Option<String>,
+    options: RenderOptions,
+    expect: Expectation,
+    report: ReportKind,
+    definitions: Option<String>,
+    named: bool,
+    basis: Basis,
+    inputs: Vec<String>,
+    project: Option<Vec<String>>,
+    values: IndexMap<String, bool>,
+    logic: Option<Logic>,
+    tnorm: TNorm,
+    degrees: IndexMap<String, f64>,
+    model: Option<String>,
+    domain: Vec<String>,
+    trace: Option<String>,
+}
+
+/// an error message together with the exit code of the process.
+#[derive(Debug)]
+struct Failure(u8, String);
+
+impl Failure {
+    fn invalid(message: impl ToString) -> Self {
+        Failure(EXIT_INVALID, message.to_string())
+    }
+}
+
+/// `-` followed by a letter, like `-q`.
+fn is_short_option(arg: &str) -> bool {
+    let mut chars = arg.chars();
+    chars.next() == Some('-') && chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+}
+
+fn parse_args(args: Vec<String>) -> Result<Option<Args>, Failure> {
+    let mut parsed = Args {
+        command: Command::Expression(Action::Table),
+        expressions: Vec::new(),
+        format: Format::Terminal,
+        output: None,
+        options: RenderOptions::default(),
+        expect: Expectation::Is(Classification::Tautology),
+        report: ReportKind::Tables,
+        definitions: None,
+        named: false,
+        basis: Basis::Nand,
+        inputs: Vec::new(),
+        project: None,
+        values: IndexMap::new(),
+        logic: None,
+        tnorm: TNorm::Godel,
+        degrees: IndexMap::new(),
+        model: None,
+        domain: Vec::new(),
+        trace: None,
+    };
+    let mut args = args.into_iter().peekable();
+    if let Some(first) = args.peek() {
+        let command = match first.as_str() {
+            "table" => Some(Command::Expression(Action::Table)),
+            "check" => Some(Command::Expression(Action::Check)),
+            "equiv" => Some(Command::Expression(Action::Equiv)),
+            "sat" => Some(Command::Expression(Action::Sat)),
+            "models" => Some(Command::Expression(Action::Models)),
+            "count" => Some(Command::Expression(Action::Count)),
+            "residual" => Some(Command::Expression(Action::Residual)),
+            "fuzzy" => Some(Command::Expression(Action::Fuzzy)),
+            "simplify" => Some(Command::Expression(Action::Simplify)),
+            "canonical" => Some(Command::Expression(Action::Canonical)),
+            "anf" => Some(Command::Expression(Action::Anf)),
+            "walsh" => Some(Command::Walsh),
+            "influence" => Some(Command::Expression(Action::Influence)),
+            "rewrite" => Some(Command::Expression(Action::Rewrite)),
+            "synth" => Some(Command::Synth),
+            "fol" => Some(Command::Fol),
+            "qbf" => Some(Command::Qbf),
+            "modal" => Some(Command::Modal),
+            "ltl" => Some(Command::Ltl),
+            "repl" => Some(Command::Repl),
+            "batch" => Some(Command::Batch),
+            _ => None,
+        };
+        if let Some(command) = command {
+            parsed.command = command;
+            args.next();
+        }
+    }
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| Failure::invalid(format!("{} needs a value", name)));
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-f" | "--format" => {
+                parsed.format = match value(&arg)?.as_str() {
+                    "terminal" => Format::Terminal,
+                    #[cfg(feature = "csv")]
+                    "csv" => Format::Csv,
+                    #[cfg(feature = "html")]
+                    "html" => Format::Html,
+                    #[cfg(feature = "pdf")]
+                    "pdf" => Format::Pdf,
+                    #[cfg(feature = "serde")]
+                    "json" => Format::Json,
+                    #[cfg(feature = "markdown")]
+                    "markdown" | "md" => Format::Markdown,
+                    #[cfg(feature = "latex")]
+                    "latex" | "tex" => Format::Latex,
+                    other => return Err(Failure::invalid(format!("unsupported format '{}'", other))),
+                }
+            }
+            "-o" | "--output" => parsed.output = Some(value(&arg)?),
+            "-d" | "--definitions" => parsed.definitions = Some(value(&arg)?),
+            "--named" => parsed.named = true,
+            "-v" | "--values" => parsed.options.values = value(&arg)?.parse().map_err(Failure::invalid)?,
+            "-s" | "--symbols" => parsed.options.symbols = value(&arg)?.parse().map_err(Failure::invalid)?,
+            "--theme" => parsed.options.theme = value(&arg)?.parse().map_err(Failure::invalid)?,
+            "--highlight" => parsed.options.highlight_result = true,
+            "--summary" => parsed.report = ReportKind::Summary,
+            "--inputs" => parsed.inputs = value(&arg)?.split(',').map(|s| s.trim().to_string()).collect(),
+            "--project" => parsed.project = Some(value(&arg)?.split(',').map(|s| s.trim().to_string()).collect()),
+            "--set" => parsed.values.extend(parse_values(&value(&arg)?)?),
+            "--degrees" => parsed.degrees.extend(parse_degrees(&value(&arg)?)?),
+            "--tnorm" => parsed.tnorm = value(&arg)?.parse().map_err(Failure::invalid)?,
+            "--model" => parsed.model = Some(value(&arg)?),
+            "--trace" => parsed.trace = Some(value(&arg)?),
+            "--domain" => parsed.domain = value(&arg)?.split(',').map(|s| s.trim().to_string()).collect(),
+            "--logic" => parsed.logic = Some(value(&arg)?.parse().map_err(Failure::invalid)?),
+            "--basis" => parsed.basis = value(&arg)?.parse().map_err(Failure::invalid)?,
+            "--expect" => {
+                parsed.expect = match value(&arg)?.as_str() {
+                    "tautology" => Expectation::Is(Classification::Tautology),
+                    "contradiction" => Expectation::Is(Classification::Contradiction),
+                    "contingency" => Expectation::Is(Classification::Contingency),
+                    "satisfiable" => Expectation::Satisfiable,
+                    other => return Err(Failure::invalid(format!("unknown classification '{}'", other))),
+                }
+            }
+            "--" => parsed.expressions.extend(args.by_ref()),
+            //`->` and `-/>` are operators, and a lone `-` reads stdin.
+            _ if arg.starts_with("--") || is_short_option(&arg) => {
+                return Err(Failure::invalid(format!("unknown option '{}'", arg)))
+            }
+            _ => parsed.expressions.push(arg),
+        }
+    }
+    //the other commands write text, a format only applies to tables.
+    let tables = [Command::Expression(Action::Table), Command::Expression(Action::Sat), Command::Batch];
+    if parsed.format != Format::Terminal && !tables.contains(&parsed.command) {
+        return Err(Failure::invalid("--format only applies to the tables of table, sat and batch"));
+    }
+    if parsed.command == Command::Repl {
+        return Ok(Some(parsed));
+    }
+    if parsed.command == Command::Batch {
+        if parsed.expressions.len() != 1 {
+            return Err(Failure::invalid("batch needs exactly one file"));
+        }
+        return Ok(Some(parsed));
+    }
+    if parsed.command == Command::Expression(Action::Equiv) {
+        if parsed.expressions.len() != 2 {
+            return Err(Failure::invalid("equiv needs exactly two expressions"));
+        }
+    } else if parsed.expressions.is_empty() || parsed.expressions == ["-"] {
+        let mut input = String::new();
+        std::io::stdin()
+            .read_to_string(&mut input)
+            .map_err(|e| Failure::invalid(format!("can not read stdin: {}", e)))?;
+        parsed.expressions = vec![input.trim().to_string()];
+    } else {
+        parsed.expressions = vec![parsed.expressions.join(" ")];
+    }
+    Ok(Some(parsed))
+}
+
+/// `p=1,q=false` as values of variables.
+fn parse_values(text: &str) -> Result<Vec<(String, bool)>, Failure> {
+    text.split(',')
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').ok_or_else(|| Failure::invalid(format!("expected NAME=VALUE, not '{}'", pair)))?;
+            let value = match value.trim() {
+                "1" | "true" => true,
+                "0" | "false" => false,
+                other => return Err(Failure::invalid(format!("'{}' is not a value, use 1, 0, true or false", other))),
+            };
+            Ok((name.trim().to_string(), value))
+        })
+        .collect()
+}
+
+/// `p=0.7,q=0.2` as truth degrees of variables.
+fn parse_degrees(text: &str) -> Result<Vec<(String, f64)>, Failure> {
+    text.split(',')
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').ok_or_else(|| Failure::invalid(format!("expected NAME=DEGREE, not '{}'", pair)))?;
+            let degree = value.trim().parse().map_err(|_| Failure::invalid(format!("'{}' is not a degree", value.trim())))?;
+            Ok((name.trim().to_string(), degree))
+        })
+        .collect()
+}
+
+fn compile(definitions: &Definitions, expression: &str) -> Result<Evaluator, Failure> {
+    definitions
+        .compile(&Tokens::from_text(expression))
+        .map_err(|e| Failure::invalid(format!("invalid expression: {}", e.annotate(expression))))
+}
+
+fn load_definitions(args: &Args) -> Result<Definitions, Failure> {
+    let Some(file) = &args.definitions else {
+        return Ok(Definitions::new());
+    };
+    let text = std::fs::read_to_string(file).map_err(|e| Failure::invalid(format!("can not read '{}': {}", file, e)))?;
+    Definitions::from_text(&text).map_err(|e| {
+        //point at the line of the error rather than the whole file.
+        let position = e.position().unwrap_or_default().min(text.len());
+        let start = text[..position].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let line = text[start..].lines().next().unwrap_or_default();
+        let number = text[..start].matches('\n').count() + 1;
+        let error = match e.position() {
+            Some(_) => format!("{}\n  {}\n  {}^", e.message(), line, " ".repeat(text[start..position].chars().count())),
+            None => e.message().to_string(),
+        };
+        Failure::invalid(format!("invalid definition in '{}' line {}: {}", file, number, error))
+    })
+}
+
+const HISTORY_FILE: &str = ".boolean_logic_history";
+
+fn repl(args: &Args) -> Result<u8, Failure> {
+    let mut session = Session::new();
+    session.options = args.options.clone();
+    if let Ok(history) = std::fs::read_to_string(HISTORY_FILE) {
+        history.lines().for_each(|line| session.push_history(line));
+    }
+    let mut history = std::fs::OpenOptions::new().create(true).append(true).open(HISTORY_FILE).ok();
+    let stdin = std::io::stdin();
+    println!("Type an expression to see its truth table, :help for the commands.");
+    loop {
+        print!("> ");
+        std::io::stdout().flush().map_err(|e| Failure(EXIT_OUTPUT, e.to_string()))?;
+        let mut line = String::new();
+        match stdin.lock().read_line(&mut line) {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(e) => return Err(Failure::invalid(format!("can not read stdin: {}", e))),
+        }
+        let line = line.trim_end_matches(['\n', '\r']);
+        if !line.trim().is_empty() && !line.trim().starts_with(":history") {
+            if let Some(file) = history.as_mut() {
+                let _ = writeln!(file, "{}", line.trim());
+            }
+        }
+        match session.execute(line) {
+            Ok(Output::Table(result)) => result.print_with_options(&session.options),
+            Ok(Output::Text(text)) => println!("{}", text),
+            Ok(Output::Nothing) => {}
+            Ok(Output::Quit) => break,
+            Err(e) => println!("error: {}", e.annotate(line)),
+        }
+    }
+    Ok(0)
+}
+
+/// a truth table with the single row of `values`.
+fn single_row(evaluator: &Evaluator, values: &IndexMap<String, bool>) -> Result<EvaluatorResult, Failure> {
+    let mut row = evaluator.evaluate(values).map_err(Failure::invalid)?;
+    for v in values.iter().rev() {
+        row.insert_before(0, v.0.clone(), *v.1);
+    }
+    Ok(EvaluatorResult { result: vec![row] })
+}
+
+fn describe(options: &RenderOptions, values: &IndexMap<String, bool>) -> String {
+    values
+        .iter()
+        .map(|(k, v)| format!("{} = {}", k, options.value(*v)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn write_text(args: &Args, text: &str) -> Result<(), Failure> {
+    match &args.output {
+        Some(file) => std::fs::write(file, text)
+            .map_err(|e| Failure(EXIT_OUTPUT, format!("can not write '{}': {}", file, e))),
+        None => {
+            print!("{}", text);
+            Ok(())
+        }
+    }
+}
+
+#[cfg(feature = "pdf")]
+fn pdf_failure(file: &str, error: genpdf::error::Error) -> Failure {
+    Failure(EXIT_OUTPUT, format!("can not write '{}': {}", file, error))
+}
 
+/// writes a table in the requested format. `evaluator` is the expression of a full truth table,
+/// it is used for the json layout.
+#[allow(unused_variables)]
+fn emit(args: &Args, result: &EvaluatorResult, evaluator: Option<&Evaluator>) -> Result<(), Failure> {
+    let options = &args.options;
+    match args.format {
+        Format::Terminal => match &args.output {
+            Some(_) => write_text(args, &result.to_plain_text(options)),
+            None => {
+                result.print_with_options(options);
+                Ok(())
+            }
+        },
+        #[cfg(feature = "csv")]
+        Format::Csv => write_text(args, &result.to_csv(options)),
+        #[cfg(feature = "html")]
+        Format::Html => write_text(args, &result.to_html(options)),
+        #[cfg(feature = "markdown")]
+        Format::Markdown => write_text(args, &result.to_markdown(options)),
+        #[cfg(feature = "latex")]
+        Format::Latex => write_text(args, &result.to_latex(options)),
+        #[cfg(feature = "pdf")]
+        Format::Pdf => match &args.output {
+            Some(file) => result.try_save_to_pdf_with_options(file, options).map_err(|e| pdf_failure(file, e)),
+            None => Err(Failure::invalid("pdf output needs --output FILE")),
+        },
+        #[cfg(feature = "serde")]
+        Format::Json => {
+            let json = match evaluator {
+                Some(evaluator) => boolean_logic::json::TruthTable::from_result(evaluator, result).to_json(),
+                None => serde_json::to_string_pretty(result).map_err(Failure::invalid)?,
+            };
+            write_text(args, &(json + "\n"))
+        }
+    }
+}
 
-#[allow(dead_code)]
-fn main() {
-    // let s = "(p <-> q) = (p -> q) and (q -> p)";
-    // let s = "(p and q) -> p";
-    // let s = "((p->q) and ((not p) -> q) -> q)";
-    // let s = "((p->q) = ((not q) -> (not p)))";
-    // let s = "([p->q] = not p or q)";
-    // let s = "{((q and p) or {q or p})}";
-    // let s = "(p and q & r)";
-    // let s = "P ∧ (∼ Q)";
-    // let s = "P ⇒ Q";
-    // let s = "(P ∧ Q) ∧ (Q ⇒ ∼ P)";
-    // let s = "(P ∧ Q) ⇒ (P ∨ Q)";
-    // let s = "(P ∧ (∼ Q)) ⇔ (P ⇒ Q)";
-    // let s = "P ⇒ Q ≡ ∼ P ∨ Q";
-    // let s = "P ∧ Q ≡ Q ∧ P";
-    // let s= "(P ∨ Q) ∧ (P ∨ R)";
-    // let s = "P ∨ (Q ∧ R) ≡ (P ∨ Q) ∧ (P ∨ R)";
-    // let s = "[(p -> q) and (r -> s)] eq [(not q or not s) -> (not p or not r)]";
-    // let s = "not true or not false";
-    // let s = "true and not p";
-    // let s = "(P and not Q) <-> (P -> Q)";//"(P ∧ (∼ Q)) ⇔ (P ⇒ Q)";
-    // let s = "P ⇒ Q eq (∼ P) ∨ Q";
-    // let s = "P ⇔ Q eq (P ⇒ Q) ∧ (Q ⇒ P)";
-    // let s = "∼ (P ∧ Q) eq (∼ P) ∨ (∼ Q)";
-    // let s = "∼ (P ∨ Q) eq (∼ P) ∧ (∼ Q)";
-    // let s = "∼ (P ⇒ Q) eq P ∧ (∼ Q)";
-    // let s = "∼ (P ∧ Q) eq P ⇒ ∼ Q";
-    // let s = "P ∧ (Q ∨ R) eq (P ∧ Q) ∨ (P ∧ R)";
-    // let s = "P ∨ (Q ∧ R) eq (P ∨ Q) ∧ (P ∨ R)";
-    // let s = "{P ⇒ (∼Q) ∨ R} ⇔ {(∼ R) ∧ T}";//"P ⇒ ∼ Q ∨ R ⇔ ∼ R ∧ T";
-    let s = "P ⇒ Q ≡ (¬Q) ⇒ (¬P)";
-    let tokens = Tokens::from_text(s);
-    match Evaluator::new(tokens) {
-        Ok(evaluator) => {
-            match evaluator.evaluate_all() {
-                Ok(result) => {
-                    print!("{}\n",result);
-                    // result.save_to_html("test.html");
-                    // result.save_to_csv("test.csv");
-                    // result.save_to_pdf("test.pdf");
-                },
-                Err(er) => {
-                    println!("{}",er);
-                },
+/// writes the table of `--logic` over true, unknown and false, as text or csv.
+fn three_valued(args: &Args, evaluator: &Evaluator) -> Result<(), Failure> {
+    let logic = args.logic.unwrap_or_default();
+    let result = evaluator.evaluate_all_three_valued(logic).map_err(Failure::invalid)?;
+    let options = &args.options;
+    match args.format {
+        Format::Terminal => match &args.output {
+            Some(_) => write_text(args, &result.to_plain_text(options)),
+            None => {
+                result.print_with_options(options);
+                Ok(())
             }
         },
-        Err(er) => {
-            println!("{}",er);
+        #[cfg(feature = "csv")]
+        Format::Csv => write_text(args, &result.to_csv(options)),
+        #[allow(unreachable_patterns)]
+        _ => Err(Failure::invalid("--logic tables are written in the terminal or csv format")),
+    }
+}
+
+fn batch(args: &Args) -> Result<u8, Failure> {
+    let file = &args.expressions[0];
+    let text = std::fs::read_to_string(file).map_err(|e| Failure::invalid(format!("can not read '{}': {}", file, e)))?;
+    let report = BatchReport::with_definitions(&text, load_definitions(args)?, args.named);
+    let (options, kind) = (&args.options, args.report);
+    match args.format {
+        Format::Terminal => write_text(args, &report.to_plain_text(options, kind))?,
+        #[cfg(feature = "csv")]
+        Format::Csv => write_text(args, &report.to_csv(options, kind))?,
+        #[cfg(feature = "html")]
+        Format::Html => write_text(args, &report.to_html(options, kind))?,
+        #[cfg(feature = "markdown")]
+        Format::Markdown => write_text(args, &report.to_markdown(options, kind))?,
+        #[cfg(feature = "latex")]
+        Format::Latex => write_text(args, &report.to_latex(options, kind))?,
+        #[cfg(feature = "pdf")]
+        Format::Pdf => match &args.output {
+            Some(file) => report.try_save_to_pdf(file, options, kind).map_err(|e| pdf_failure(file, e))?,
+            None => return Err(Failure::invalid("pdf output needs --output FILE")),
         },
+        #[cfg(feature = "serde")]
+        Format::Json => write_text(args, &(report.to_json(kind) + "\n"))?,
     }
+    Ok(if report.errors.is_empty() { 0 } else { EXIT_INVALID })
+}
 
-    //evaluate for specific values only.
-    use indexmap::IndexMap;
-    let s = "(∼ P) ∨ (∼ Q)";
-    let tokens = Tokens::from_text(s);
-    let evaluator = Evaluator::new(tokens).unwrap();
-    let mut values = IndexMap::<char,bool>::new();
-    values.insert('P', false);
-    values.insert('Q', true);
-    let mut result = evaluator.evaluate(&values).unwrap();
-    for v in values.iter().rev() {
-        result.insert_before(0, (*v.0).into(), *v.1);
+/// reads the table of `synth`: a csv file, or a bit vector, minterms or maxterms over `--inputs`.
+fn specification(args: &Args) -> Result<Specification, Failure> {
+    let table = &args.expressions[0];
+    #[cfg(feature = "csv")]
+    if table.ends_with(".csv") {
+        return Specification::from_csv_file(table).map_err(|e| Failure::invalid(format!("can not read '{}': {}", table, e)));
+    }
+    Specification::from_notation(args.inputs.clone(), table).map_err(Failure::invalid)
+}
+
+fn synth(args: &Args) -> Result<u8, Failure> {
+    let specification = specification(args)?;
+    let formulas = [
+        ("canonical dnf", specification.canonical_dnf()),
+        ("canonical cnf", specification.canonical_cnf()),
+        ("minimal dnf", specification.minimized()),
+        ("minimal cnf", specification.minimized_cnf()),
+        ("anf", Anf::from_specification(&specification).to_tokens()),
+    ];
+    let text: String = formulas
+        .iter()
+        .map(|(name, tokens)| format!("{}: {}\n", name, args.options.header(&tokens.to_string())))
+        .collect();
+    write_text(args, &text)?;
+    Ok(0)
+}
+
+fn walsh(args: &Args) -> Result<u8, Failure> {
+    let text = &args.expressions[0];
+    let bits: Vec<bool> = text.chars().filter(|c| *c != '_' && !c.is_whitespace()).map(|c| c == '1').collect();
+    let function = if bits.len() > 1 && text.chars().all(|c| matches!(c, '0' | '1' | '_') || c.is_whitespace()) {
+        BooleanFunction::from_table(bits)
+    } else {
+        let evaluator = compile(&load_definitions(args)?, text)?;
+        BooleanFunction::from_evaluator(&evaluator)
+    }
+    .map_err(Failure::invalid)?;
+    let list = |values: Vec<i64>| values.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", ");
+    let resiliency = match function.resiliency() {
+        Some(order) => order.to_string(),
+        None => "not balanced".to_string(),
+    };
+    let report = [
+        format!("walsh spectrum: {}", list(function.walsh_spectrum())),
+        format!("nonlinearity: {}", function.nonlinearity()),
+        format!("balanced: {}", args.options.value(function.is_balanced())),
+        format!("correlation immunity: {}", function.correlation_immunity()),
+        format!("resiliency: {}", resiliency),
+        format!("algebraic degree: {}", function.algebraic_degree()),
+        format!("algebraic immunity: {}", function.algebraic_immunity()),
+        format!("autocorrelation: {}", list(function.autocorrelation())),
+    ];
+    write_text(args, &(report.join("\n") + "\n"))?;
+    Ok(0)
+}
+
+/// checks a formula in the model of `--model`, or prints a model over `--domain`. Exits with
+/// `1` when the formula is false or has no model.
+fn first_order(args: &Args) -> Result<u8, Failure> {
+    let text = &args.expressions[0];
+    let formula =
+        Formula::from_text(text).map_err(|e| Failure::invalid(format!("invalid formula: {}", e.annotate(text))))?;
+    if let Some(file) = &args.model {
+        let model = Model::from_file(file).map_err(|e| Failure::invalid(format!("can not read '{}': {}", file, e)))?;
+        let holds = formula.holds(&model).map_err(Failure::invalid)?;
+        write_text(args, &format!("{}\n", args.options.value(holds)))?;
+        return Ok(if holds { 0 } else { EXIT_FAILED });
+    }
+    if args.domain.is_empty() {
+        return Err(Failure::invalid("fol needs --model FILE or --domain NAMES"));
+    }
+    match formula.find_model(&args.domain).map_err(Failure::invalid)? {
+        Some(model) => {
+            write_text(args, &model.to_string())?;
+            Ok(0)
+        }
+        None => {
+            write_text(args, &format!("no model over {{{}}}\n", args.domain.join(", ")))?;
+            Ok(EXIT_FAILED)
+        }
+    }
+}
+
+/// prints the value of a quantified formula and the strategy of the winning side. Exits with `1`
+/// when the formula is false.
+fn quantified(args: &Args) -> Result<u8, Failure> {
+    let text = &args.expressions[0];
+    let qbf = Qbf::from_text(text).map_err(|e| Failure::invalid(format!("invalid formula: {}", e.annotate(text))))?;
+    let options = &args.options;
+    let mut lines = Vec::new();
+    let value = match qbf.strategy().map_err(Failure::invalid)? {
+        Some(strategy) => {
+            lines.push(options.value(strategy.value).to_string());
+            for (name, function) in &strategy.functions {
+                lines.push(format!("{} = {}", options.header(name), options.header(&function.to_string())));
+            }
+            strategy.value
+        }
+        None => {
+            let value = qbf.is_true().map_err(Failure::invalid)?;
+            lines.push(options.value(value).to_string());
+            value
+        }
+    };
+    write_text(args, &(lines.join("\n") + "\n"))?;
+    Ok(if value { 0 } else { EXIT_FAILED })
+}
+
+/// prints the value of a modal formula at each world and the properties of the frame. Exits with
+/// `1` when the formula is false at some world.
+fn modal(args: &Args) -> Result<u8, Failure> {
+    let text = &args.expressions[0];
+    let formula =
+        ModalFormula::from_text(text).map_err(|e| Failure::invalid(format!("invalid formula: {}", e.annotate(text))))?;
+    let Some(file) = &args.model else {
+        return Err(Failure::invalid("modal needs --model FILE"));
+    };
+    let model =
+        KripkeModel::from_file(file).map_err(|e| Failure::invalid(format!("can not read '{}': {}", file, e)))?;
+    let truth_set = model.truth_set(&formula).map_err(Failure::invalid)?;
+    let frame = model.frame();
+    let mut lines: Vec<String> = frame
+        .worlds()
+        .iter()
+        .map(|world| format!("{} = {}", world, args.options.value(truth_set.contains(world))))
+        .collect();
+    let properties = [
+        (frame.is_reflexive(), "reflexive"),
+        (frame.is_symmetric(), "symmetric"),
+        (frame.is_transitive(), "transitive"),
+        (frame.is_euclidean(), "euclidean"),
+        (frame.is_serial(), "serial"),
+    ];
+    let properties: Vec<&str> = properties.iter().filter(|(has, _)| *has).map(|(_, name)| *name).collect();
+    let systems: Vec<String> =
+        System::ALL.iter().filter(|s| frame.is_frame_of(**s)).map(|s| s.to_string()).collect();
+    lines.push(format!("frame: {} ({})", properties.join(", "), systems.join(", ")));
+    write_text(args, &(lines.join("\n") + "\n"))?;
+    Ok(if truth_set.len() == frame.worlds().len() { 0 } else { EXIT_FAILED })
+}
+
+/// checks a temporal formula against the trace of `--trace`. Exits with `1` when it is false and
+/// prints the step that makes it false with the values at that step.
+fn temporal(args: &Args) -> Result<u8, Failure> {
+    let text = &args.expressions[0];
+    let formula =
+        LtlFormula::from_text(text).map_err(|e| Failure::invalid(format!("invalid formula: {}", e.annotate(text))))?;
+    let Some(file) = &args.trace else {
+        return Err(Failure::invalid("ltl needs --trace FILE"));
+    };
+    let trace = read_trace(file)?;
+    let options = &args.options;
+    match formula.first_failing_step(&trace).map_err(Failure::invalid)? {
+        None => {
+            write_text(args, &format!("{}\n", options.value(true)))?;
+            Ok(0)
+        }
+        Some(step) => {
+            let values: Vec<String> = trace
+                .step(step)
+                .unwrap_or_default()
+                .iter()
+                .map(|(name, value)| format!("{} = {}", name, options.value(*value)))
+                .collect();
+            write_text(args, &format!("{} at step {}: {}\n", options.value(false), step, values.join(", ")))?;
+            Ok(EXIT_FAILED)
+        }
     }
-    let result = evaluator_result::EvaluatorResult{ result: vec![result] };
-    println!("{}",result);
+}
+
+#[cfg(feature = "csv")]
+fn read_trace(file: &str) -> Result<Trace, Failure> {
+    Trace::from_csv_file(file).map_err(|e| Failure::invalid(format!("can not read '{}': {}", file, e)))
+}
+
+#[cfg(not(feature = "csv"))]
+fn read_trace(_file: &str) -> Result<Trace, Failure> {
+    Err(Failure::invalid("reading a trace needs the csv feature"))
+}
+
+fn run(args: &Args) -> Result<u8, Failure> {
+    match args.command {
+        Command::Expression(action) => expression(args, action),
+        Command::Repl => repl(args),
+        Command::Batch => batch(args),
+        Command::Synth => synth(args),
+        Command::Walsh => walsh(args),
+        Command::Fol => first_order(args),
+        Command::Qbf => quantified(args),
+        Command::Modal => modal(args),
+        Command::Ltl => temporal(args),
+    }
+}
 
+fn expression(args: &Args, action: Action) -> Result<u8, Failure> {
+    let options = &args.options;
+    let definitions = load_definitions(args)?;
+    let evaluator = compile(&definitions, &args.expressions[0])?;
+    match action {
+        Action::Table if args.logic.is_some() => {
+            three_valued(args, &evaluator)?;
+            Ok(0)
+        }
+        Action::Table => {
+            let result = definitions
+                .evaluate_all(&Tokens::from_text(&args.expressions[0]), args.named)
+                .map_err(Failure::invalid)?;
+            emit(args, &result, Some(&evaluator))?;
+            Ok(0)
+        }
+        Action::Check => {
+            let classification = evaluator.classify().map_err(Failure::invalid)?;
+            let passed = match args.expect {
+                Expectation::Is(expected) => classification == expected,
+                Expectation::Satisfiable => classification != Classification::Contradiction,
+            };
+            let mut text = format!("{}\n", classification);
+            if !passed {
+                let witness = match args.expect {
+                    Expectation::Is(Classification::Tautology) => evaluator.counterexample(),
+                    _ => evaluator.satisfying_assignment(),
+                }
+                .map_err(Failure::invalid)?;
+                if let Some(row) = witness.filter(|row| !row.is_empty()) {
+                    let value = evaluator.value(&row).map_err(Failure::invalid)?;
+                    text += &format!("{} when {}\n", options.value(value), describe(options, &row));
+                }
+            }
+            write_text(args, &text)?;
+            Ok(if passed { 0 } else { EXIT_FAILED })
+        }
+        Action::Equiv => {
+            let other = compile(&definitions, &args.expressions[1])?;
+            match evaluator.difference(&other).map_err(Failure::invalid)? {
+                None => {
+                    write_text(args, "equivalent\n")?;
+                    Ok(0)
+                }
+                Some(row) => {
+                    write_text(args, &format!("not equivalent, they differ when {}\n", describe(options, &row)))?;
+                    Ok(EXIT_FAILED)
+                }
+            }
+        }
+        Action::Sat => match evaluator.satisfying_assignment().map_err(Failure::invalid)? {
+            Some(row) => {
+                emit(args, &single_row(&evaluator, &row)?, None)?;
+                Ok(0)
+            }
+            None => {
+                write_text(args, "unsatisfiable\n")?;
+                Ok(EXIT_FAILED)
+            }
+        },
+        Action::Models => {
+            let projection = args.project.clone().unwrap_or_else(|| evaluator.variables());
+            let models = evaluator.projected_models(&projection).map_err(Failure::invalid)?;
+            let text: String = models.map(|row| describe(options, &row) + "\n").collect();
+            write_text(args, &text)?;
+            Ok(0)
+        }
+        Action::Count => {
+            let projection = args.project.clone().unwrap_or_else(|| evaluator.variables());
+            let count = evaluator.count_projected_models(&projection).map_err(Failure::invalid)?;
+            write_text(args, &format!("{}\n", count))?;
+            Ok(0)
+        }
+        Action::Residual => {
+            let residual = evaluator.partial_evaluate(&args.values).map_err(Failure::invalid)?;
+            write_text(args, &format!("{}\n", options.header(&residual.to_string())))?;
+            Ok(0)
+        }
+        Action::Fuzzy => {
+            let result = evaluator.evaluate_fuzzy(args.tnorm, &args.degrees).map_err(Failure::invalid)?;
+            let lines: Vec<String> = args
+                .degrees
+                .iter()
+                .filter(|(name, _)| evaluator.variables().contains(name))
+                .chain(result.iter())
+                //six decimals hide the rounding of the arithmetic, like 0.30000000000000004.
+                .map(|(name, degree)| format!("{} = {}", options.header(name), (degree * 1e6).round() / 1e6))
+                .collect();
+            write_text(args, &(lines.join("\n") + "\n"))?;
+            Ok(0)
+        }
+        Action::Canonical => {
+            let text = format!(
+                "{}\n{}\n{}\n{}\n",
+                minterm_notation(&evaluator.minterms().map_err(Failure::invalid)?),
+                maxterm_notation(&evaluator.maxterms().map_err(Failure::invalid)?),
+                options.header(&evaluator.sum_of_minterms().map_err(Failure::invalid)?.to_string()),
+                options.header(&evaluator.product_of_maxterms().map_err(Failure::invalid)?.to_string()),
+            );
+            write_text(args, &text)?;
+            Ok(0)
+        }
+        Action::Anf => {
+            let anf = evaluator.anf().map_err(Failure::invalid)?;
+            write_text(args, &format!("{}\ndegree {}\n", options.header(&anf.to_string()), anf.degree()))?;
+            Ok(0)
+        }
+        Action::Influence => {
+            let function = BooleanFunction::from_evaluator(&evaluator).map_err(Failure::invalid)?;
+            let mut lines: Vec<String> = evaluator
+                .variables()
+                .iter()
+                .zip(function.influences())
+                .map(|(v, i)| format!("{}: {}", v, i))
+                .collect();
+            let irrelevant = evaluator.irrelevant_variables().map_err(Failure::invalid)?;
+            lines.push(format!("irrelevant: {}", if irrelevant.is_empty() { "none".to_string() } else { irrelevant.join(", ") }));
+            lines.push(format!("sensitivity: {}", function.sensitivity()));
+            lines.push(format!("total sensitivity: {}", function.total_sensitivity()));
+            lines.push(format!("block sensitivity: {}", function.block_sensitivity()));
+            write_text(args, &(lines.join("\n") + "\n"))?;
+            Ok(0)
+        }
+        Action::Rewrite => {
+            let rewritten = evaluator.rewrite(args.basis).map_err(Failure::invalid)?;
+            write_text(args, &format!("{}\n", options.header(&rewritten.to_string())))?;
+            Ok(0)
+        }
+        Action::Simplify => {
+            let simplified = evaluator.simplify().map_err(Failure::invalid)?;
+            write_text(args, &format!("{}\n", options.header(&simplified.to_string())))?;
+            Ok(0)
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1).collect()) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(Failure(code, message)) => {
+            eprintln!("error: {}\n\n{}", message, USAGE);
+            return ExitCode::from(code);
+        }
+    };
+    match run(&args) {
+        Ok(code) => ExitCode::from(code),
+        Err(Failure(code, message)) => {
+            eprintln!("error: {}", message);
+            ExitCode::from(code)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Option<Args>, Failure> {
+        parse_args(args.iter().map(|s| s.to_string()).collect())
+    }
+
+    fn exit_code(args: &[&str]) -> u8 {
+        let args = match parse(args) {
+            Ok(args) => args.unwrap(),
+            Err(Failure(code, _)) => return code,
+        };
+        match run(&args) {
+            Ok(code) => code,
+            Err(Failure(code, _)) => code,
+        }
+    }
+
+    #[test]
+    fn arguments() {
+        let args = parse(&["check", "--expect", "satisfiable", "p", "->", "q"]).unwrap().unwrap();
+        assert_eq!(args.command, Command::Expression(Action::Check));
+        assert_eq!(args.expect, Expectation::Satisfiable);
+        assert_eq!(args.expressions, ["p -> q"]);
+        let args = parse(&["p", "and", "q", "-v", "digits", "-o", "table.txt"]).unwrap().unwrap();
+        assert_eq!(args.command, Command::Expression(Action::Table));
+        assert_eq!(args.output.as_deref(), Some("table.txt"));
+        assert_eq!(args.options.value(true), "1");
+        let args = parse(&["equiv", "p", "--", "-p"]).unwrap().unwrap();
+        assert_eq!(args.expressions, ["p", "-p"]);
+        assert!(parse(&["--help"]).unwrap().is_none());
+        assert_eq!(parse(&["-q", "p"]).err().unwrap().0, EXIT_INVALID);
+        assert_eq!(parse(&["--quiet", "p"]).err().unwrap().0, EXIT_INVALID);
+        assert_eq!(parse(&["p", "-v"]).err().unwrap().0, EXIT_INVALID);
+        assert_eq!(parse(&["equiv", "p"]).err().unwrap().0, EXIT_INVALID);
+        assert_eq!(parse(&["batch"]).err().unwrap().0, EXIT_INVALID);
+        assert_eq!(parse(&["modal", "-f", "csv", "--model", "kripke.txt", "p"]).err().unwrap().0, EXIT_INVALID);
+        assert_eq!(parse(&["check", "-f", "csv", "p"]).err().unwrap().0, EXIT_INVALID);
+        #[cfg(feature = "csv")]
+        assert!(parse(&["sat", "-f", "csv", "p"]).is_ok());
+    }
+
+    #[test]
+    fn exit_codes() {
+        assert_eq!(exit_code(&["check", "p or not p"]), 0);
+        assert_eq!(exit_code(&["check", "p and not p"]), EXIT_FAILED);
+        assert_eq!(exit_code(&["check", "--expect", "contradiction", "p and not p"]), 0);
+        assert_eq!(exit_code(&["equiv", "p -> q", "not q -> not p"]), 0);
+        assert_eq!(exit_code(&["equiv", "p -> q", "q -> p"]), EXIT_FAILED);
+        assert_eq!(exit_code(&["sat", "p and not p"]), EXIT_FAILED);
+        assert_eq!(exit_code(&["check", "p and"]), EXIT_INVALID);
+        assert_eq!(exit_code(&["table", "p # q"]), EXIT_INVALID);
+        assert_eq!(exit_code(&["-f", "unknown", "p"]), EXIT_INVALID);
+        assert_eq!(exit_code(&["count", "-o", "/nonexistent/count.txt", "p or q"]), EXIT_OUTPUT);
+        assert_eq!(exit_code(&["check", "-o", "/nonexistent/check.txt", "p"]), EXIT_OUTPUT);
+        assert_eq!(exit_code(&["equiv", "-o", "/nonexistent/equiv.txt", "p", "q"]), EXIT_OUTPUT);
+    }
+
+    #[test]
+    fn text_output() {
+        let file = std::env::temp_dir().join("boolean_logic_check.txt");
+        let path = file.to_str().unwrap();
+        assert_eq!(exit_code(&["check", "-o", path, "p and not p"]), EXIT_FAILED);
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "contradiction\nfalse when p = true\n");
+        assert_eq!(exit_code(&["sat", "-o", path, "p and not p"]), EXIT_FAILED);
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "unsatisfiable\n");
+        std::fs::remove_file(file).unwrap();
+    }
 }
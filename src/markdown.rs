@@ -0,0 +1,85 @@
+use std::fs::File;
+use std::io::Write;
+
+use crate::evaluator_result::EvaluatorResult;
+use crate::render::RenderOptions;
+
+#[allow(dead_code)]
+impl EvaluatorResult {
+    pub fn to_markdown(&self, options: &RenderOptions) -> String {
+        let mut out = String::new();
+        let Some(header) = self.result.first() else {
+            return out;
+        };
+        let last = header.len().saturating_sub(1);
+        let cell = |index: usize, text: String| {
+            if options.highlight_result && index == last {
+                format!("**{}**", text)
+            } else {
+                text
+            }
+        };
+        let header: Vec<String> = header
+            .keys()
+            .enumerate()
+            .map(|(i, h)| cell(i, escape(&options.header(h))))
+            .collect();
+        out.push_str(&format!("| {} |\n", header.join(" | ")));
+        let rule: Vec<String> = header.iter().map(|h| "-".repeat(h.chars().count().max(3))).collect();
+        out.push_str(&format!("|-{}-|\n", rule.join("-|-")));
+        for row in self.result.iter() {
+            let values: Vec<String> = row
+                .values()
+                .enumerate()
+                .map(|(i, v)| cell(i, escape(options.value(*v))))
+                .collect();
+            out.push_str(&format!("| {} |\n", values.join(" | ")));
+        }
+        out
+    }
+
+    pub fn save_to_markdown(&self, file_name: &str) {
+        self.save_to_markdown_with_options(file_name, &RenderOptions::default());
+    }
+
+    pub fn save_to_markdown_with_options(&self, file_name: &str, options: &RenderOptions) {
+        let mut out = File::create(file_name).unwrap();
+        out.write_all(self.to_markdown(options).as_bytes()).unwrap();
+    }
+}
+
+/// escapes the characters that would otherwise end a cell or start inline markup.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '|' | '*' | '_' | '`' | '<' | '>' | '[' | ']') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::evaluator::Evaluator;
+    use crate::render::{SymbolSet, ValueStyle};
+    use crate::tokenizer::Tokens;
+
+    use super::*;
+
+    #[test]
+    fn ascii_or_is_escaped() {
+        let evaluator = Evaluator::new(Tokens::from_text("p or q")).unwrap();
+        let result = evaluator.evaluate_all().unwrap();
+        let options = RenderOptions::default()
+            .with_symbols(SymbolSet::Ascii)
+            .with_values(ValueStyle::Digits)
+            .with_highlight_result(true);
+        let md = result.to_markdown(&options);
+        let lines: Vec<&str> = md.lines().collect();
+        assert_eq!(lines[0], "| p | q | **(p \\| q)** |");
+        assert_eq!(lines[2], "| 1 | 1 | **1** |");
+        assert_eq!(lines.len(), 6);
+    }
+}
@@ -0,0 +1,447 @@
+use std::fmt;
+use std::str::FromStr;
+
+use indexmap::IndexMap;
+
+use crate::evaluator::{apply, Evaluator, EvaluatorError};
+use crate::first_order::Model;
+use crate::tokenizer::{Token, Tokens};
+
+/// a set of worlds, the bit `i` is the world `i`.
+type Worlds = u128;
+
+const MAX_WORLDS: usize = Worlds::BITS as usize;
+
+/// the most bits of valuation (worlds times variables) `Frame::counter_model` tries.
+const VALUATION_LIMIT: usize = 20;
+
+/// A formula of propositional modal logic: the propositional connectives with `□` (`box`) and
+/// `◇` (`dia`), which bind like `¬`.
+#[derive(Clone)]
+pub struct ModalFormula {
+    evaluator: Evaluator,
+}
+
+impl ModalFormula {
+    pub fn new(tokens: Tokens) -> Result<Self, EvaluatorError> {
+        Ok(ModalFormula { evaluator: Evaluator::with_modalities(tokens)? })
+    }
+
+    pub fn from_text(text: &str) -> Result<Self, EvaluatorError> {
+        Self::new(Tokens::from_text(text))
+    }
+
+    /// the variables in order of appearance.
+    pub fn variables(&self) -> Vec<String> {
+        self.evaluator.variables()
+    }
+}
+
+impl fmt::Display for ModalFormula {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Tokens::from(self.evaluator.clone()))
+    }
+}
+
+/// The normal modal logics whose axioms hold on a class of frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum System {
+    /// `□(p → q) → (□p → □q)`, valid on every frame.
+    K,
+    /// K and `□p → p`, reflexive frames.
+    T,
+    /// T and `□p → □□p`, reflexive and transitive frames.
+    S4,
+    /// T and `◇p → □◇p`, reflexive and Euclidean frames.
+    S5,
+}
+
+impl System {
+    pub const ALL: [System; 4] = [System::K, System::T, System::S4, System::S5];
+
+    /// the axioms added to propositional logic, besides necessitation and modus ponens.
+    pub fn axioms(&self) -> Vec<&'static str> {
+        let mut axioms = vec!["□(p → q) → (□p → □q)"];
+        match self {
+            System::K => {}
+            System::T => axioms.push("□p → p"),
+            System::S4 => axioms.extend(["□p → p", "□p → □□p"]),
+            System::S5 => axioms.extend(["□p → p", "◇p → □◇p"]),
+        }
+        axioms
+    }
+}
+
+impl fmt::Display for System {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            System::K => "K",
+            System::T => "T",
+            System::S4 => "S4",
+            System::S5 => "S5",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for System {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "K" => Ok(System::K),
+            "T" => Ok(System::T),
+            "S4" => Ok(System::S4),
+            "S5" => Ok(System::S5),
+            _ => Err(format!("unknown modal system '{}'", s)),
+        }
+    }
+}
+
+/// Worlds and the accessibility relation between them, from 1 to 128 worlds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    worlds: Vec<String>,
+    /// the worlds accessible from each world.
+    successors: Vec<Worlds>,
+}
+
+impl Frame {
+    /// `relation` holds the pairs `(w, v)` where `v` is accessible from `w`.
+    pub fn new(worlds: Vec<String>, relation: &[(String, String)]) -> Result<Self, EvaluatorError> {
+        if worlds.is_empty() {
+            return Err(EvaluatorError::new("A frame has at least one world."));
+        }
+        if worlds.len() > MAX_WORLDS {
+            return Err(EvaluatorError::new(format!("A frame has at most {} worlds, not {}.", MAX_WORLDS, worlds.len())));
+        }
+        for (i, world) in worlds.iter().enumerate() {
+            if worlds[..i].contains(world) {
+                return Err(EvaluatorError::new(format!("The world '{}' is given twice.", world)));
+            }
+        }
+        let mut frame = Frame { successors: vec![0; worlds.len()], worlds };
+        for (from, to) in relation {
+            let (from, to) = (frame.index(from)?, frame.index(to)?);
+            frame.successors[from] |= 1 << to;
+        }
+        Ok(frame)
+    }
+
+    fn index(&self, world: &str) -> Result<usize, EvaluatorError> {
+        self.worlds
+            .iter()
+            .position(|w| w == world)
+            .ok_or_else(|| EvaluatorError::new(format!("'{}' is not a world of the frame.", world)))
+    }
+
+    fn all(&self) -> Worlds {
+        Worlds::MAX.checked_shr((MAX_WORLDS - self.worlds.len()) as u32).unwrap_or(0)
+    }
+
+    fn set(&self, worlds: Worlds) -> Vec<String> {
+        (0..self.worlds.len()).filter(|i| worlds >> i & 1 == 1).map(|i| self.worlds[i].clone()).collect()
+    }
+
+    pub fn worlds(&self) -> &[String] {
+        &self.worlds
+    }
+
+    pub fn accessible(&self, from: &str, to: &str) -> Result<bool, EvaluatorError> {
+        Ok(self.successors[self.index(from)?] >> self.index(to)? & 1 == 1)
+    }
+
+    /// every world sees itself.
+    pub fn is_reflexive(&self) -> bool {
+        (0..self.worlds.len()).all(|i| self.successors[i] >> i & 1 == 1)
+    }
+
+    /// `w → v` implies `v → w`.
+    pub fn is_symmetric(&self) -> bool {
+        (0..self.worlds.len()).all(|i| self.set_indices(self.successors[i]).all(|j| self.successors[j] >> i & 1 == 1))
+    }
+
+    /// `w → v → u` implies `w → u`.
+    pub fn is_transitive(&self) -> bool {
+        (0..self.worlds.len()).all(|i| {
+            self.set_indices(self.successors[i]).all(|j| self.successors[j] & !self.successors[i] == 0)
+        })
+    }
+
+    /// `w → v` and `w → u` imply `v → u`.
+    pub fn is_euclidean(&self) -> bool {
+        (0..self.worlds.len()).all(|i| {
+            self.set_indices(self.successors[i]).all(|j| self.successors[i] & !self.successors[j] == 0)
+        })
+    }
+
+    /// every world sees some world.
+    pub fn is_serial(&self) -> bool {
+        self.successors.iter().all(|s| *s != 0)
+    }
+
+    /// whether the frame has the properties that make the axioms of `system` valid on it.
+    pub fn is_frame_of(&self, system: System) -> bool {
+        match system {
+            System::K => true,
+            System::T => self.is_reflexive(),
+            System::S4 => self.is_reflexive() && self.is_transitive(),
+            System::S5 => self.is_reflexive() && self.is_euclidean(),
+        }
+    }
+
+    fn set_indices(&self, worlds: Worlds) -> impl Iterator<Item = usize> {
+        (0..self.worlds.len()).filter(move |i| worlds >> i & 1 == 1)
+    }
+
+    /// a valuation and a world where the formula is false, `None` when the formula is valid on
+    /// the frame. Every valuation is tried, so worlds times variables is at most 20.
+    pub fn counter_model(&self, formula: &ModalFormula) -> Result<Option<(KripkeModel, String)>, EvaluatorError> {
+        let variables = formula.variables();
+        let (n, bits) = (self.worlds.len(), self.worlds.len() * variables.len());
+        if bits > VALUATION_LIMIT {
+            return Err(EvaluatorError::new(format!(
+                "{} worlds and {} variables are too many valuations to try.",
+                n,
+                variables.len()
+            )));
+        }
+        for valuation in 0..1u64 << bits {
+            let valuation = variables
+                .iter()
+                .enumerate()
+                .map(|(k, v)| (v.clone(), Worlds::from(valuation >> (k * n)) & self.all()))
+                .collect();
+            let model = KripkeModel { frame: self.clone(), valuation };
+            let holds = model.worlds(formula)?;
+            if holds != self.all() {
+                let world = self.worlds[(!holds & self.all()).trailing_zeros() as usize].clone();
+                return Ok(Some((model, world)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// whether the formula is true at every world for every valuation.
+    pub fn validates(&self, formula: &ModalFormula) -> Result<bool, EvaluatorError> {
+        Ok(self.counter_model(formula)?.is_none())
+    }
+
+    /// whether every axiom of `system` is valid on the frame.
+    pub fn validates_system(&self, system: System) -> Result<bool, EvaluatorError> {
+        for axiom in system.axioms() {
+            if !self.validates(&ModalFormula::from_text(axiom)?)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// A frame with the worlds where each variable is true.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KripkeModel {
+    frame: Frame,
+    valuation: IndexMap<String, Worlds>,
+}
+
+impl KripkeModel {
+    pub fn new(frame: Frame, valuation: &IndexMap<String, Vec<String>>) -> Result<Self, EvaluatorError> {
+        let mut worlds = IndexMap::new();
+        for (variable, set) in valuation {
+            let mut bits: Worlds = 0;
+            for world in set {
+                bits |= 1 << frame.index(world)?;
+            }
+            worlds.insert(variable.clone(), bits);
+        }
+        Ok(KripkeModel { frame, valuation: worlds })
+    }
+
+    /// reads the lines of `first_order::Model::from_text`: `worlds = {w1, w2}` (or `domain`),
+    /// the accessibility relation `R = {(w1, w2), (w2, w2)}` and a set of worlds per variable
+    /// like `p = {w2}`.
+    pub fn from_text(text: &str) -> Result<Self, EvaluatorError> {
+        let model = Model::from_text(text)?;
+        let mut worlds = model.domain().to_vec();
+        let mut relation = Vec::new();
+        let mut valuation = IndexMap::new();
+        for (name, tuples) in model.relations() {
+            match (name.as_str(), tuples.first().map(|t| t.len())) {
+                ("worlds", _) => worlds = tuples.iter().flatten().cloned().collect(),
+                ("R", Some(2) | None) => relation.extend(tuples.iter().map(|t| (t[0].clone(), t[1].clone()))),
+                ("R", _) => return Err(EvaluatorError::new("'R' is a set of pairs of worlds.")),
+                (_, Some(1) | None) => {
+                    valuation.insert(name.clone(), tuples.iter().flatten().cloned().collect());
+                }
+                _ => return Err(EvaluatorError::new(format!("'{}' is not a set of worlds.", name))),
+            }
+        }
+        Self::new(Frame::new(worlds, &relation)?, &valuation)
+    }
+
+    pub fn from_file(file_name: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self::from_text(&std::fs::read_to_string(file_name)?)?)
+    }
+
+    pub fn frame(&self) -> &Frame {
+        &self.frame
+    }
+
+    /// the worlds where each column of the formula is true, the last column is the formula.
+    pub fn evaluate(&self, formula: &ModalFormula) -> Result<IndexMap<String, Vec<String>>, EvaluatorError> {
+        Ok(self.columns(formula)?.into_iter().map(|(name, worlds)| (name, self.frame.set(worlds))).collect())
+    }
+
+    /// the value of each column of the formula at a world, like `Evaluator::evaluate`.
+    pub fn evaluate_at(&self, formula: &ModalFormula, world: &str) -> Result<IndexMap<String, bool>, EvaluatorError> {
+        let i = self.frame.index(world)?;
+        Ok(self.columns(formula)?.into_iter().map(|(name, worlds)| (name, worlds >> i & 1 == 1)).collect())
+    }
+
+    pub fn holds(&self, formula: &ModalFormula, world: &str) -> Result<bool, EvaluatorError> {
+        Ok(self.worlds(formula)? >> self.frame.index(world)? & 1 == 1)
+    }
+
+    /// the worlds where the formula is true.
+    pub fn truth_set(&self, formula: &ModalFormula) -> Result<Vec<String>, EvaluatorError> {
+        Ok(self.frame.set(self.worlds(formula)?))
+    }
+
+    /// whether the formula is true at every world.
+    pub fn is_true(&self, formula: &ModalFormula) -> Result<bool, EvaluatorError> {
+        Ok(self.worlds(formula)? == self.frame.all())
+    }
+
+    fn worlds(&self, formula: &ModalFormula) -> Result<Worlds, EvaluatorError> {
+        Ok(self.columns(formula)?.last().map(|(_, worlds)| *worlds).unwrap_or_default())
+    }
+
+    fn columns(&self, formula: &ModalFormula) -> Result<IndexMap<String, Worlds>, EvaluatorError> {
+        if let Some(v) = formula.variables().iter().find(|v| !self.valuation.contains_key(*v)) {
+            return Err(EvaluatorError::new(format!("'{}' has no valuation in the model.", v)));
+        }
+        let (n, all, successors) = (self.frame.worlds.len(), self.frame.all(), &self.frame.successors);
+        let constant = |value: bool| if value { all } else { 0 };
+        formula.evaluator.evaluate_with(&self.valuation, constant, |operator, count, values| match operator {
+            //□a holds where every successor is in a, ◇a where some successor is.
+            Token::Necessarily(_) => (0..n).filter(|i| successors[*i] & !values[0] == 0).fold(0, |s, i| s | 1 << i),
+            Token::Possibly(_) => (0..n).filter(|i| successors[*i] & values[0] != 0).fold(0, |s, i| s | 1 << i),
+            _ => (0..n)
+                .filter(|i| {
+                    let mut operands: Vec<Token> = values.iter().map(|v| Token::from(v >> i & 1 == 1)).collect();
+                    if matches!(operator, Token::Function(f) if f.takes_count()) {
+                        operands.insert(0, Token::Number(count));
+                    }
+                    apply(operator, &operands)
+                })
+                .fold(0, |s, i| s | 1 << i),
+        })
+    }
+}
+
+impl fmt::Display for KripkeModel {
+    /// the text read by `from_text`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut model = Model::new(self.frame.worlds.clone());
+        model.declare("R");
+        for (i, successors) in self.frame.successors.iter().enumerate() {
+            for j in self.frame.set_indices(*successors) {
+                let pair = vec![self.frame.worlds[i].clone(), self.frame.worlds[j].clone()];
+                model.add("R", pair).map_err(|_| fmt::Error)?;
+            }
+        }
+        for (variable, worlds) in &self.valuation {
+            model.declare(variable);
+            for world in self.frame.set(*worlds) {
+                model.add(variable, vec![world]).map_err(|_| fmt::Error)?;
+            }
+        }
+        write!(f, "{}", model)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn formula(s: &str) -> ModalFormula {
+        ModalFormula::from_text(s).unwrap()
+    }
+
+    /// the frame on the worlds `0..n` with the pairs of the set bits of `relation`, `i * n + j`
+    /// for `i → j`.
+    fn frame(n: usize, relation: u32) -> Frame {
+        let worlds: Vec<String> = (0..n).map(|i| format!("w{}", i)).collect();
+        let pairs: Vec<(String, String)> = (0..n * n)
+            .filter(|b| relation >> b & 1 == 1)
+            .map(|b| (worlds[b / n].clone(), worlds[b % n].clone()))
+            .collect();
+        Frame::new(worlds, &pairs).unwrap()
+    }
+
+    #[test]
+    fn worlds() {
+        let model = KripkeModel::from_text(
+            "worlds = {w1, w2, w3}
+            R = {(w1, w2), (w1, w3), (w2, w3), (w3, w3)}
+            p = {w2, w3}
+            q = {w3}",
+        )
+        .unwrap();
+        assert!(model.holds(&formula("□p"), "w1").unwrap());
+        assert!(!model.holds(&formula("box q"), "w1").unwrap());
+        assert!(model.holds(&formula("dia q and not q"), "w1").unwrap());
+        assert_eq!(model.truth_set(&formula("□q")).unwrap(), ["w2", "w3"]);
+        assert_eq!(model.truth_set(&formula("◇◇◇p → p")).unwrap(), ["w2", "w3"]);
+        assert!(model.is_true(&formula("□(p → q) → (□p → □q)")).unwrap());
+        let columns = model.evaluate_at(&formula("□p ∧ ¬p"), "w1").unwrap();
+        assert_eq!(columns.keys().collect::<Vec<_>>(), ["□p", "¬p", "(□p ∧ ¬p)"]);
+        assert_eq!(model.evaluate(&formula("◇q")).unwrap()["◇q"], ["w1", "w2", "w3"]);
+        assert_eq!(KripkeModel::from_text(&model.to_string()).unwrap(), model);
+        assert!(model.holds(&formula("r"), "w1").is_err());
+        assert!(KripkeModel::from_text("worlds = {a}\nR = {(a, b)}").is_err());
+    }
+
+    #[test]
+    fn propositional_evaluator() {
+        let error = Evaluator::new(Tokens::from_text("p and box p")).err().unwrap();
+        assert_eq!(error.position(), Some(6));
+        assert!(ModalFormula::from_text("p □").is_err());
+    }
+
+    #[test]
+    fn properties() {
+        //w0 → w1 → w2
+        let chain = frame(3, 0b000_100_010);
+        assert!(!chain.is_reflexive() && !chain.is_transitive() && !chain.is_symmetric() && !chain.is_serial());
+        assert!(chain.accessible("w0", "w1").unwrap());
+        let equivalence = frame(3, 0b100_011_011);
+        assert!(equivalence.is_reflexive() && equivalence.is_symmetric() && equivalence.is_transitive());
+        assert!(equivalence.is_euclidean() && equivalence.is_frame_of(System::S5));
+        let (model, world) = chain.counter_model(&formula("□p → p")).unwrap().unwrap();
+        assert!(!model.holds(&formula("□p → p"), &world).unwrap());
+    }
+
+    #[test]
+    fn empty_frame() {
+        assert!(Frame::new(Vec::new(), &[]).is_err());
+        assert!(KripkeModel::from_text("worlds = {}").is_err());
+    }
+
+    #[test]
+    fn correspondence() {
+        let (t, four, five) = (formula("□p → p"), formula("□p → □□p"), formula("◇p → □◇p"));
+        for relation in 0..1 << 9 {
+            let frame = frame(3, relation);
+            assert_eq!(frame.validates(&t).unwrap(), frame.is_reflexive());
+            assert_eq!(frame.validates(&four).unwrap(), frame.is_transitive());
+            assert_eq!(frame.validates(&five).unwrap(), frame.is_euclidean());
+        }
+        for relation in 0..1 << 4 {
+            let frame = frame(2, relation);
+            for system in System::ALL {
+                assert_eq!(frame.validates_system(system).unwrap(), frame.is_frame_of(system), "{} {:?}", system, frame);
+            }
+        }
+    }
+}
@@ -0,0 +1,464 @@
+use std::collections::{HashMap, HashSet};
+
+use indexmap::IndexMap;
+pub use num_bigint::BigUint;
+
+use crate::evaluator::{Evaluator, EvaluatorError};
+use crate::expression::Expression;
+use crate::tokenizer::{Function, Token};
+
+/// expressions with at most this many variables are counted with their truth table.
+const TABLE_LIMIT: usize = 16;
+
+/// a clause of signed variable numbers, `-v` is the negation of `v`.
+pub(crate) type Clause = Vec<i32>;
+
+/// A formula in conjunctive normal form over the variables `1..=count`.
+#[derive(Debug, Clone)]
+pub(crate) struct Cnf {
+    pub count: usize,
+    pub clauses: Vec<Clause>,
+}
+
+impl Cnf {
+    /// the Tseitin encoding of an expression: `variables` are numbered `1..=n` in order, every
+    /// gate gets a new variable that is equivalent to it. Each assignment of `variables` extends
+    /// in exactly one way, so the models are the same and so is their count. Operands are
+    /// encoded once, so the size is linear in the expression and in `n·k` for the counts.
+    pub(crate) fn tseitin(expression: &Expression, variables: &[String]) -> Cnf {
+        let mut cnf = Cnf { count: variables.len(), clauses: Vec::new() };
+        let numbers = variables.iter().enumerate().map(|(i, v)| (v.as_str(), i as i32 + 1)).collect();
+        match cnf.encode(expression, &numbers) {
+            Literal::Constant(true) => {}
+            Literal::Constant(false) => cnf.clauses.push(Vec::new()),
+            Literal::Variable(root) => cnf.clauses.push(vec![root]),
+        }
+        cnf
+    }
+
+    fn encode(&mut self, e: &Expression, numbers: &HashMap<&str, i32>) -> Literal {
+        let (operator, operands) = match e {
+            Expression::Constant(value) => return Literal::Constant(*value),
+            Expression::Variable(name) => return Literal::Variable(numbers[name.as_str()]),
+            Expression::Count(_) => unreachable!("counts are arguments of functions"),
+            Expression::Operation(operator, operands) => (operator, operands),
+        };
+        let count = match operands.first() {
+            Some(Expression::Count(k)) => *k,
+            _ => 0,
+        };
+        let literals: Vec<Literal> = operands
+            .iter()
+            .filter(|o| !matches!(o, Expression::Count(_)))
+            .map(|o| self.encode(o, numbers))
+            .collect();
+        if let Token::Function(function) = operator {
+            return match function {
+                Function::Ite => {
+                    let [c, a, b] = literals[..] else { unreachable!("ite takes 3 arguments") };
+                    let (then, otherwise) = (self.and(c, a), self.and(c.not(), b));
+                    self.or(then, otherwise)
+                }
+                Function::Xor => literals.into_iter().fold(Literal::Constant(false), |a, b| self.xor(a, b)),
+                Function::Maj => self.at_least(&literals, literals.len() / 2 + 1)[literals.len() / 2 + 1],
+                Function::AtLeast => self.at_least(&literals, count)[count],
+                Function::AtMost => self.at_least(&literals, count + 1)[count + 1].not(),
+                Function::Exactly => {
+                    let row = self.at_least(&literals, count + 1);
+                    self.and(row[count], row[count + 1].not())
+                }
+            };
+        }
+        let (a, b) = match literals[..] {
+            [a] if matches!(operator, Token::Not(_)) => return a.not(),
+            [a, b] => (a, b),
+            _ => panic!("{} is not a connective of propositional logic", operator),
+        };
+        match operator {
+            Token::And(_) => self.and(a, b),
+            Token::Nand(_) => self.and(a, b).not(),
+            Token::Or(_) => self.or(a, b),
+            Token::Nor(_) => self.or(a, b).not(),
+            Token::Implication(_) => self.or(a.not(), b),
+            Token::Converse(_) => self.or(a, b.not()),
+            Token::NonImplication(_) => self.and(a, b.not()),
+            Token::Biconditional(_) | Token::Equals(_) => self.xor(a, b).not(),
+            Token::XOr(_) | Token::NotEquals(_) => self.xor(a, b),
+            _ => panic!("{} is not a connective of propositional logic", operator),
+        }
+    }
+
+    fn gate(&mut self, clauses: impl Fn(i32) -> Vec<Clause>) -> Literal {
+        self.count += 1;
+        let x = self.count as i32;
+        self.clauses.extend(clauses(x));
+        Literal::Variable(x)
+    }
+
+    fn and(&mut self, a: Literal, b: Literal) -> Literal {
+        match (a, b) {
+            (Literal::Constant(false), _) | (_, Literal::Constant(false)) => Literal::Constant(false),
+            (Literal::Constant(true), e) | (e, Literal::Constant(true)) => e,
+            (a, b) if a == b => a,
+            (a, b) if a == b.not() => Literal::Constant(false),
+            (Literal::Variable(a), Literal::Variable(b)) => self.gate(|x| vec![vec![-x, a], vec![-x, b], vec![x, -a, -b]]),
+        }
+    }
+
+    fn or(&mut self, a: Literal, b: Literal) -> Literal {
+        self.and(a.not(), b.not()).not()
+    }
+
+    fn xor(&mut self, a: Literal, b: Literal) -> Literal {
+        match (a, b) {
+            (Literal::Constant(v), e) | (e, Literal::Constant(v)) => if v { e.not() } else { e },
+            (a, b) if a == b => Literal::Constant(false),
+            (a, b) if a == b.not() => Literal::Constant(true),
+            (Literal::Variable(a), Literal::Variable(b)) => {
+                self.gate(|x| vec![vec![-x, a, b], vec![-x, -a, -b], vec![x, -a, b], vec![x, a, -b]])
+            }
+        }
+    }
+
+    /// whether at least `j` of the literals are true, for each `j` in `0..=k`. Each entry is a
+    /// gate over the entries for the literals after the first, so there are about `n·k` gates.
+    fn at_least(&mut self, literals: &[Literal], k: usize) -> Vec<Literal> {
+        let mut row: Vec<Literal> = (0..=k).map(|j| Literal::Constant(j == 0)).collect();
+        for l in literals.iter().rev() {
+            let mut next = vec![Literal::Constant(true)];
+            for j in 1..=k {
+                let with = self.and(*l, row[j - 1]);
+                next.push(self.or(with, row[j]));
+            }
+            row = next;
+        }
+        row
+    }
+}
+
+/// a literal of the encoding, or a constant that is folded into the gates that use it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Literal {
+    Constant(bool),
+    Variable(i32),
+}
+
+impl Literal {
+    fn not(self) -> Literal {
+        match self {
+            Literal::Constant(value) => Literal::Constant(!value),
+            Literal::Variable(v) => Literal::Variable(-v),
+        }
+    }
+}
+
+/// the clauses once `literal` is true, `None` when one of them can not be satisfied anymore.
+pub(crate) fn assign(clauses: &[Clause], literal: i32) -> Option<Vec<Clause>> {
+    let mut result = Vec::with_capacity(clauses.len());
+    for clause in clauses {
+        if clause.contains(&literal) {
+            continue;
+        }
+        let reduced: Clause = clause.iter().copied().filter(|l| *l != -literal).collect();
+        if reduced.is_empty() {
+            return None;
+        }
+        result.push(reduced);
+    }
+    Some(result)
+}
+
+/// assigns the literals of unit clauses until there are none, they are added to `forced`.
+fn propagate(mut clauses: Vec<Clause>, forced: &mut Vec<i32>) -> Option<Vec<Clause>> {
+    if clauses.iter().any(|c| c.is_empty()) {
+        return None;
+    }
+    while let Some(unit) = clauses.iter().find(|c| c.len() == 1).map(|c| c[0]) {
+        forced.push(unit);
+        clauses = assign(&clauses, unit)?;
+    }
+    Some(clauses)
+}
+
+pub(crate) fn is_satisfiable(clauses: &[Clause]) -> bool {
+    let Some(clauses) = propagate(clauses.to_vec(), &mut Vec::new()) else {
+        return false;
+    };
+    let Some(literal) = clauses.first().map(|c| c[0]) else {
+        return true;
+    };
+    [literal, -literal].iter().any(|l| assign(&clauses, *l).is_some_and(|c| is_satisfiable(&c)))
+}
+
+/// DPLL model counting with component decomposition and caching. Only the assignments of the
+/// `projected` variables are counted, two models that differ elsewhere count once.
+struct Counter {
+    projected: HashSet<i32>,
+    cache: HashMap<Vec<Clause>, BigUint>,
+}
+
+impl Counter {
+    fn projected_in(&self, clauses: &[Clause]) -> HashSet<i32> {
+        clauses.iter().flatten().map(|l| l.abs()).filter(|v| self.projected.contains(v)).collect()
+    }
+
+    /// the number of assignments of the projected variables of `clauses` that extend to a model.
+    fn count(&mut self, clauses: Vec<Clause>) -> BigUint {
+        let before = self.projected_in(&clauses);
+        let mut forced = Vec::new();
+        let Some(clauses) = propagate(clauses, &mut forced) else {
+            return BigUint::ZERO;
+        };
+        let after = self.projected_in(&clauses);
+        let forced: HashSet<i32> = forced.iter().map(|l| l.abs()).collect();
+        //the variables that disappeared without being forced can take both values.
+        let free = before.iter().filter(|v| !forced.contains(v) && !after.contains(v)).count();
+        let count = if after.is_empty() {
+            BigUint::from(u8::from(is_satisfiable(&clauses)))
+        } else {
+            components(clauses).into_iter().map(|c| self.count_component(c)).product()
+        };
+        count << free
+    }
+
+    fn count_component(&mut self, mut clauses: Vec<Clause>) -> BigUint {
+        clauses.iter_mut().for_each(|c| c.sort_unstable());
+        clauses.sort_unstable();
+        if let Some(count) = self.cache.get(&clauses) {
+            return count.clone();
+        }
+        let projected = self.projected_in(&clauses);
+        if projected.is_empty() {
+            return BigUint::from(u8::from(is_satisfiable(&clauses)));
+        }
+        //branch on the projected variable that appears most in the shortest clauses.
+        let mut occurrences: HashMap<i32, (usize, usize)> = HashMap::new();
+        for clause in &clauses {
+            for v in clause.iter().map(|l| l.abs()).filter(|v| projected.contains(v)) {
+                let (binary, all) = occurrences.entry(v).or_default();
+                *binary += usize::from(clause.len() == 2);
+                *all += 1;
+            }
+        }
+        let (v, _) = occurrences.into_iter().max_by_key(|(v, n)| (*n, -v)).unwrap();
+        let mut count = BigUint::ZERO;
+        for literal in [v, -v] {
+            if let Some(branch) = assign(&clauses, literal) {
+                let remaining = self.projected_in(&branch);
+                let free = projected.iter().filter(|p| **p != v && !remaining.contains(p)).count();
+                count += self.count(branch) << free;
+            }
+        }
+        self.cache.insert(clauses, count.clone());
+        count
+    }
+}
+
+/// splits clauses into groups that share no variable.
+fn components(clauses: Vec<Clause>) -> Vec<Vec<Clause>> {
+    let mut parent: HashMap<i32, i32> = HashMap::new();
+    fn find(parent: &mut HashMap<i32, i32>, v: i32) -> i32 {
+        let p = *parent.entry(v).or_insert(v);
+        if p == v {
+            return v;
+        }
+        let root = find(parent, p);
+        parent.insert(v, root);
+        root
+    }
+    for clause in &clauses {
+        let first = find(&mut parent, clause[0].abs());
+        for l in &clause[1..] {
+            let root = find(&mut parent, l.abs());
+            parent.insert(root, first);
+        }
+    }
+    let mut groups: IndexMap<i32, Vec<Clause>> = IndexMap::new();
+    for clause in clauses {
+        let root = find(&mut parent, clause[0].abs());
+        groups.entry(root).or_default().push(clause);
+    }
+    groups.into_values().collect()
+}
+
+/// The models of an expression, in the order of the rows of `evaluate_all`. Every branch of the
+/// search is checked to have a model, so each step finds the next model without trying the
+/// rows in between one by one.
+pub struct Models {
+    names: Vec<String>,
+    projected: Vec<i32>,
+    stack: Vec<(Vec<Clause>, Vec<bool>)>,
+}
+
+impl Iterator for Models {
+    type Item = IndexMap<String, bool>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((clauses, values)) = self.stack.pop() {
+            if values.len() == self.projected.len() {
+                return Some(self.names.iter().cloned().zip(values).collect());
+            }
+            let v = self.projected[values.len()];
+            //false is pushed first so that true comes out first.
+            for value in [false, true] {
+                if let Some(branch) = assign(&clauses, if value { v } else { -v }) {
+                    if is_satisfiable(&branch) {
+                        let mut values = values.clone();
+                        values.push(value);
+                        self.stack.push((branch, values));
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+impl Evaluator {
+    /// the assignments that make the expression true.
+    pub fn models(&self) -> Models {
+        self.projected_models(&self.variables()).expect("the variables of the expression")
+    }
+
+    /// the assignments of `variables` that extend to a model, each of them once.
+    pub fn projected_models(&self, variables: &[String]) -> Result<Models, EvaluatorError> {
+        let names = self.projection(variables)?;
+        let all = self.variables();
+        let cnf = Cnf::tseitin(&Expression::from(self), &all);
+        let projected = names.iter().map(|n| all.iter().position(|v| v == n).unwrap() as i32 + 1).collect();
+        let stack = if is_satisfiable(&cnf.clauses) { vec![(cnf.clauses, Vec::new())] } else { Vec::new() };
+        Ok(Models { names, projected, stack })
+    }
+
+    /// the number of models of the expression.
+    pub fn count_models(&self) -> BigUint {
+        self.count_projected_models(&self.variables()).expect("the variables of the expression")
+    }
+
+    /// the number of assignments of `variables` that extend to a model. Small expressions are
+    /// counted with their truth table, larger ones with a DPLL search that splits the clauses
+    /// into independent components and caches their counts.
+    pub fn count_projected_models(&self, variables: &[String]) -> Result<BigUint, EvaluatorError> {
+        let names = self.projection(variables)?;
+        if self.variables().len() <= TABLE_LIMIT {
+            self.count_with_table(&names)
+        } else {
+            Ok(self.count_with_search(&names))
+        }
+    }
+
+    /// the projected variables in order of appearance.
+    fn projection(&self, variables: &[String]) -> Result<Vec<String>, EvaluatorError> {
+        let all = self.variables();
+        if let Some(unknown) = variables.iter().find(|v| !all.contains(v)) {
+            return Err(EvaluatorError::new(format!("'{}' is not a variable of the expression.", unknown)));
+        }
+        Ok(all.into_iter().filter(|v| variables.contains(v)).collect())
+    }
+
+    fn count_with_table(&self, names: &[String]) -> Result<BigUint, EvaluatorError> {
+        let all = self.variables();
+        let n = all.len();
+        let bits: Vec<usize> = names.iter().map(|name| n - all.iter().position(|v| v == name).unwrap() - 1).collect();
+        let vector = self.truth_vector()?;
+        let rows: HashSet<u64> = (0..vector.len())
+            .filter(|i| vector[*i])
+            .map(|i| bits.iter().fold(0u64, |row, bit| row << 1 | (i as u64 >> bit & 1)))
+            .collect();
+        Ok(BigUint::from(rows.len()))
+    }
+
+    fn count_with_search(&self, names: &[String]) -> BigUint {
+        let all = self.variables();
+        let cnf = Cnf::tseitin(&Expression::from(self), &all);
+        let projected: HashSet<i32> = names.iter().map(|n| all.iter().position(|v| v == n).unwrap() as i32 + 1).collect();
+        let mut counter = Counter { projected, cache: HashMap::new() };
+        //the projected variables that the encoding lost, like p in `(p or 1) and q`, are free.
+        let present = counter.projected_in(&cnf.clauses).len();
+        let free = counter.projected.len() - present;
+        counter.count(cnf.clauses) << free
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::Tokens;
+
+    fn evaluator(s: &str) -> Evaluator {
+        Evaluator::new(Tokens::from_text(s)).unwrap()
+    }
+
+    fn names(names: &str) -> Vec<String> {
+        names.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn models_in_table_order() {
+        let e = evaluator("(p -> q) and (q -> r)");
+        let models: Vec<IndexMap<String, bool>> = e.models().collect();
+        let rows: Vec<IndexMap<String, bool>> = e
+            .evaluate_all()
+            .unwrap()
+            .result
+            .into_iter()
+            .filter(|row| *row.last().unwrap().1)
+            .map(|row| row.into_iter().take(3).collect())
+            .collect();
+        assert_eq!(models, rows);
+        assert_eq!(evaluator("p and not p").models().count(), 0);
+        assert_eq!(evaluator("true").models().count(), 1);
+        let projected: Vec<_> = e.projected_models(&names("r")).unwrap().collect();
+        assert_eq!(projected, [[("r".to_string(), true)].into(), [("r".to_string(), false)].into()]);
+        assert!(e.projected_models(&names("x")).is_err());
+    }
+
+    #[test]
+    fn search_agrees_with_table() {
+        let expressions = [
+            "(p -> q) and (q -> r)",
+            "maj(a, b, c) xor d",
+            "exactly(2, a, b, c, d) and (e or not f)",
+            "(p or true) and q",
+            "p and not p",
+            "ite(x, y, z) <-> (x nand w)",
+        ];
+        for s in expressions {
+            let e = evaluator(s);
+            let all = e.variables();
+            assert_eq!(e.count_with_search(&all), e.count_with_table(&all).unwrap(), "{}", s);
+            assert_eq!(e.count_models(), BigUint::from(e.models().count()), "{}", s);
+            for k in 0..all.len() {
+                let names = &all[..k];
+                assert_eq!(e.count_with_search(names), e.count_with_table(names).unwrap(), "{} over {:?}", s, names);
+                assert_eq!(e.count_with_search(names), BigUint::from(e.projected_models(names).unwrap().count()));
+            }
+        }
+    }
+
+    #[test]
+    fn many_variables() {
+        //independent clauses over 100 variables: 3^50 models, far beyond a truth table.
+        let text: Vec<String> = (0..50).map(|i| format!("(x{} or y{})", i, i)).collect();
+        let e = evaluator(&text.join(" and "));
+        assert_eq!(e.count_models(), BigUint::from(3u8).pow(50));
+        let xs: Vec<String> = (0..50).map(|i| format!("x{}", i)).collect();
+        assert_eq!(e.count_projected_models(&xs).unwrap(), BigUint::from(1u8) << 50);
+        assert_eq!(e.models().next().unwrap().values().filter(|v| **v).count(), 100);
+    }
+
+    #[test]
+    fn xor_and_cardinality() {
+        //the encoding is linear in xor and counts, so these are searched, not tabled.
+        let xs: Vec<String> = (0..24).map(|i| format!("x{}", i)).collect();
+        let chain = evaluator(&xs.join(" xor "));
+        assert_eq!(chain.count_models(), BigUint::from(1u8) << 23);
+        let parity = evaluator(&format!("xor({}) <-> y", xs.join(", ")));
+        assert_eq!(parity.count_models(), BigUint::from(1u8) << 24);
+        let xs: Vec<String> = (0..20).map(|i| format!("x{}", i)).collect();
+        let exactly = evaluator(&format!("exactly(3, {})", xs.join(", ")));
+        assert_eq!(exactly.count_models(), BigUint::from(1140u16));
+        let at_most = evaluator(&format!("atmost(1, {}) and maj(x0, x1, x2)", xs.join(", ")));
+        assert_eq!(at_most.count_models(), BigUint::ZERO);
+    }
+}
@@ -0,0 +1,276 @@
+use indexmap::IndexMap;
+
+use crate::evaluator::{apply, Evaluator, EvaluatorError};
+use crate::expression::Expression;
+use crate::models::{is_satisfiable, Cnf};
+use crate::tokenizer::{Function, Token, Tokens};
+
+impl Expression {
+    /// the expression with the variables of `values` replaced by their value and the constants
+    /// folded away. The other operations keep their connective.
+    pub fn substitute(&self, values: &IndexMap<String, bool>) -> Expression {
+        match self {
+            Expression::Variable(name) => match values.get(name) {
+                Some(value) => Expression::Constant(*value),
+                None => self.clone(),
+            },
+            Expression::Operation(operator, operands) => {
+                let operands = operands.iter().map(|o| o.substitute(values)).collect();
+                fold_operation(operator.clone(), operands)
+            }
+            _ => self.clone(),
+        }
+    }
+}
+
+fn not(e: Expression) -> Expression {
+    match e {
+        Expression::Constant(v) => Expression::Constant(!v),
+        Expression::Operation(Token::Not(_), mut operands) => operands.remove(0),
+        e => Expression::Operation(Token::Not('¬'), vec![e]),
+    }
+}
+
+fn fold_operation(operator: Token, operands: Vec<Expression>) -> Expression {
+    if let Token::Function(function) = operator {
+        return fold_function(function, operands);
+    }
+    let constant = |e: &Expression| match e {
+        Expression::Constant(v) => Some(*v),
+        _ => None,
+    };
+    let [a, b] = match <[Expression; 2]>::try_from(operands) {
+        Ok(pair) => pair,
+        Err(mut operands) => match operator {
+            Token::Not(_) => return not(operands.remove(0)),
+            _ => return Expression::Operation(operator, operands),
+        },
+    };
+    //the modal and temporal operators keep their operands.
+    let connective = matches!(
+        operator,
+        Token::And(_) | Token::Nand(_) | Token::Or(_) | Token::Nor(_) | Token::XOr(_) | Token::NotEquals(_) |
+        Token::Biconditional(_) | Token::Equals(_) | Token::Implication(_) | Token::Converse(_) | Token::NonImplication(_)
+    );
+    if !connective {
+        return Expression::Operation(operator, vec![a, b]);
+    }
+    let (x, y) = (constant(&a), constant(&b));
+    if let (Some(x), Some(y)) = (x, y) {
+        return Expression::Constant(apply(&operator, &[Token::from(x), Token::from(y)]));
+    }
+    //one side is known: the value of the operation is a constant, the other side or its negation.
+    let (known, other, known_left) = match (x, y) {
+        (Some(x), None) => (x, b, true),
+        (None, Some(y)) => (y, a, false),
+        _ => return Expression::Operation(operator, vec![a, b]),
+    };
+    let op = |t: &Token| std::mem::discriminant(t) == std::mem::discriminant(&operator);
+    match (known, known_left) {
+        _ if op(&Token::And('∧')) => if known { other } else { Expression::Constant(false) },
+        _ if op(&Token::Nand('↑')) => if known { not(other) } else { Expression::Constant(true) },
+        _ if op(&Token::Or('∨')) => if known { Expression::Constant(true) } else { other },
+        _ if op(&Token::Nor('↓')) => if known { Expression::Constant(false) } else { not(other) },
+        _ if op(&Token::XOr('⊕')) || op(&Token::NotEquals('≠')) => if known { not(other) } else { other },
+        _ if op(&Token::Biconditional('↔')) || op(&Token::Equals('≡')) => if known { other } else { not(other) },
+        //p → q
+        (true, true) if op(&Token::Implication('→')) => other,
+        (false, true) if op(&Token::Implication('→')) => Expression::Constant(true),
+        (true, false) if op(&Token::Implication('→')) => Expression::Constant(true),
+        (false, false) if op(&Token::Implication('→')) => not(other),
+        //p ← q
+        (true, true) if op(&Token::Converse('←')) => Expression::Constant(true),
+        (false, true) if op(&Token::Converse('←')) => not(other),
+        (true, false) if op(&Token::Converse('←')) => other,
+        (false, false) if op(&Token::Converse('←')) => Expression::Constant(true),
+        //p ↛ q
+        (true, true) if op(&Token::NonImplication('↛')) => not(other),
+        (false, true) if op(&Token::NonImplication('↛')) => Expression::Constant(false),
+        (true, false) if op(&Token::NonImplication('↛')) => Expression::Constant(false),
+        (false, false) if op(&Token::NonImplication('↛')) => other,
+        _ => unreachable!("{} is a connective", operator),
+    }
+}
+
+fn fold_function(function: Function, mut operands: Vec<Expression>) -> Expression {
+    let count = match operands.first() {
+        Some(Expression::Count(k)) if function.takes_count() => *k,
+        _ => 0,
+    };
+    if function.takes_count() {
+        operands.remove(0);
+    }
+    let trues = operands.iter().filter(|o| **o == Expression::Constant(true)).count();
+    let known = operands.iter().filter(|o| matches!(o, Expression::Constant(_))).count();
+    let rest: Vec<Expression> = operands.iter().filter(|o| !matches!(o, Expression::Constant(_))).cloned().collect();
+    let call = |function: Function, count: Option<usize>, arguments: Vec<Expression>| {
+        let count = count.map(Expression::Count);
+        Expression::Operation(Token::Function(function), count.into_iter().chain(arguments).collect())
+    };
+    match function {
+        Function::Ite => {
+            let [c, a, b]: [Expression; 3] = operands.try_into().expect("ite takes 3 arguments");
+            match (c, a, b) {
+                (Expression::Constant(c), a, b) => if c { a } else { b },
+                (_, a, b) if a == b => a,
+                (c, Expression::Constant(true), Expression::Constant(false)) => c,
+                (c, Expression::Constant(false), Expression::Constant(true)) => not(c),
+                (c, a, b) => call(Function::Ite, None, vec![c, a, b]),
+            }
+        }
+        Function::Xor => {
+            let odd = trues % 2 == 1;
+            let folded = match rest.len() {
+                0 => Expression::Constant(false),
+                1 => rest[0].clone(),
+                _ => call(Function::Xor, None, rest),
+            };
+            if odd { not(folded) } else { folded }
+        }
+        _ if known == 0 => call(function, function.takes_count().then_some(count), operands),
+        Function::Maj => at_least(operands.len() / 2 + 1, trues, rest),
+        Function::AtLeast => at_least(count, trues, rest),
+        Function::AtMost => {
+            if trues > count {
+                return Expression::Constant(false);
+            }
+            match (count - trues, rest.len()) {
+                (k, n) if k >= n => Expression::Constant(true),
+                (0, 1) => not(rest[0].clone()),
+                (k, _) => call(Function::AtMost, Some(k), rest),
+            }
+        }
+        Function::Exactly => {
+            if trues > count || count - trues > rest.len() {
+                return Expression::Constant(false);
+            }
+            match (count - trues, rest.len()) {
+                (0, 0) => Expression::Constant(true),
+                (0, 1) => not(rest[0].clone()),
+                (1, 1) => rest[0].clone(),
+                (k, _) => call(Function::Exactly, Some(k), rest),
+            }
+        }
+    }
+}
+
+/// `atleast(k, ...)` once `trues` of the arguments are known to be true.
+fn at_least(k: usize, trues: usize, rest: Vec<Expression>) -> Expression {
+    match (k.saturating_sub(trues), rest.len()) {
+        (0, _) => Expression::Constant(true),
+        (k, n) if k > n => Expression::Constant(false),
+        (1, 1) => rest[0].clone(),
+        (k, _) => Expression::Operation(
+            Token::Function(Function::AtLeast),
+            std::iter::once(Expression::Count(k)).chain(rest).collect(),
+        ),
+    }
+}
+
+impl Evaluator {
+    /// the expression over the variables that have no value in `values`, the others replaced
+    /// by their value and folded away. It is a constant when the values decide the expression,
+    /// even if the rest does not fold, like `p and q or not q` with `p = 1`. Values of variables
+    /// that are not in the expression are ignored.
+    pub fn partial_evaluate(&self, values: &IndexMap<String, bool>) -> Result<Tokens, EvaluatorError> {
+        let residual = Expression::from(self).substitute(values);
+        Ok(decided(residual).to_tokens())
+    }
+}
+
+/// the constant the expression is equivalent to, or the expression itself.
+fn decided(e: Expression) -> Expression {
+    if matches!(e, Expression::Constant(_)) {
+        return e;
+    }
+    let variables = e.variables();
+    if !is_satisfiable(&Cnf::tseitin(&e, &variables).clauses) {
+        return Expression::Constant(false);
+    }
+    let negation = Expression::Operation(Token::Not('¬'), vec![e.clone()]);
+    if !is_satisfiable(&Cnf::tseitin(&negation, &variables).clauses) {
+        return Expression::Constant(true);
+    }
+    e
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::assignment;
+    use crate::tokenizer::Temporal;
+
+    fn residual(s: &str, values: &[(&str, bool)]) -> String {
+        let evaluator = Evaluator::new(Tokens::from_text(s)).unwrap();
+        let values: IndexMap<String, bool> = values.iter().map(|(k, v)| (k.to_string(), *v)).collect();
+        let residual = evaluator.partial_evaluate(&values).unwrap();
+        //the residual agrees with the expression on every row that matches the values.
+        let check = Evaluator::new(residual.clone()).unwrap();
+        let variables = evaluator.variables();
+        for index in 0..1u64 << variables.len() {
+            let row = assignment(&variables, index);
+            if values.iter().all(|(k, v)| row.get(k).is_none_or(|r| r == v)) {
+                let rest = row.iter().filter(|(k, _)| check.variables().contains(k)).map(|(k, v)| (k.clone(), *v)).collect();
+                assert_eq!(check.value(&rest).unwrap(), evaluator.value(&row).unwrap(), "{} with {:?}", s, row);
+            }
+        }
+        residual.to_string()
+    }
+
+    #[test]
+    fn connectives() {
+        assert_eq!(residual("p and q", &[("p", true)]), "q");
+        assert_eq!(residual("p and q", &[("p", false)]), "0");
+        assert_eq!(residual("p -> q", &[("q", false)]), "¬p");
+        assert_eq!(residual("(p <- q) or r", &[("p", false)]), "¬q ∨ r");
+        assert_eq!(residual("(a xor b) nand c", &[("a", true), ("c", true)]), "b");
+        assert_eq!(residual("(p ↛ q) = r", &[("q", false)]), "p ≡ r");
+        assert_eq!(residual("p and q", &[("x", true)]), "p ∧ q");
+        assert_eq!(residual("(p ↛ q) ↛ r", &[("p", true), ("r", false)]), "¬q");
+    }
+
+    #[test]
+    fn other_operators() {
+        //only the connectives fold, the modal and temporal operators keep their operands.
+        let (p, q) = (Expression::Variable("p".into()), Expression::Variable("q".into()));
+        let values = [("p".to_string(), true), ("q".to_string(), false)].into();
+        let until = Expression::Operation(Token::Temporal(Temporal::Until), vec![p.clone(), q]);
+        let substituted = Expression::Operation(
+            Token::Temporal(Temporal::Until),
+            vec![Expression::Constant(true), Expression::Constant(false)],
+        );
+        assert_eq!(until.substitute(&values), substituted);
+        let necessarily = Expression::Operation(Token::Necessarily('□'), vec![p]);
+        assert_eq!(necessarily.substitute(&values), Expression::Operation(Token::Necessarily('□'), vec![Expression::Constant(true)]));
+    }
+
+    #[test]
+    fn functions() {
+        assert_eq!(residual("ite(c, a, b)", &[("c", true)]), "a");
+        assert_eq!(residual("maj(a, b, c)", &[("a", true)]), "atleast(1, b, c)");
+        assert_eq!(residual("exactly(2, a, b, c)", &[("a", true), ("b", false)]), "c");
+        assert_eq!(residual("atmost(1, a, b, c)", &[("a", true)]), "atmost(0, b, c)");
+        assert_eq!(residual("xor(a, b, c)", &[("b", true)]), "¬xor(a, c)");
+        assert_eq!(residual("atleast(2, a, b, c)", &[("a", false), ("b", false)]), "0");
+    }
+
+    #[test]
+    fn decided_by_the_values() {
+        assert_eq!(residual("p and q or not q", &[("p", true)]), "1");
+        assert_eq!(residual("(p or r) and not r and not p", &[("x", true)]), "0");
+        assert_eq!(residual("q", &[("q", true)]), "1");
+    }
+
+    #[test]
+    fn many_variables() {
+        //the satisfiability checks stay linear in the size of xor chains and counts.
+        let xs: Vec<String> = (0..24).map(|i| format!("x{}", i)).collect();
+        let values: IndexMap<String, bool> = [("x0".to_string(), true)].into();
+        let chain = Evaluator::new(Tokens::from_text(&xs.join(" xor "))).unwrap();
+        let residual = chain.partial_evaluate(&values).unwrap().to_string();
+        assert!(residual.contains("(¬x1 ⊕ x2)") && residual.ends_with("⊕ x23"), "{}", residual);
+        let count = Evaluator::new(Tokens::from_text(&format!("exactly(2, {}) or atmost(0, {})", xs.join(", "), xs.join(", "))));
+        let residual = count.unwrap().partial_evaluate(&values).unwrap().to_string();
+        assert!(residual.starts_with("exactly(1, x1, x2,"), "{}", residual);
+    }
+}
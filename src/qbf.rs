@@ -0,0 +1,417 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use indexmap::IndexMap;
+
+use crate::evaluator::{assignment, Evaluator, EvaluatorError};
+use crate::expression::Expression;
+use crate::first_order::{lex, Lexeme, Quantifier};
+use crate::models::{assign, Clause, Cnf};
+use crate::synthesis::Specification;
+use crate::tokenizer::{Token, Tokens};
+
+/// formulas with at most this many variables are decided by expanding the quantifiers.
+const EXPANSION_LIMIT: usize = 16;
+
+/// the most variables of the losing side a strategy is computed for, its functions are tables.
+const STRATEGY_LIMIT: usize = 12;
+
+/// A quantified boolean formula in prenex form, like `∀p ∃q. p ↔ q`. Variables of the formula
+/// that are not quantified are existential and come first.
+#[derive(Clone)]
+pub struct Qbf {
+    prefix: Vec<(Quantifier, String)>,
+    matrix: Evaluator,
+}
+
+/// How the winning side plays: when the formula is true, a Skolem function for each existential
+/// variable over the universal variables before it. When it is false, a counter-strategy: a
+/// function for each universal variable over the existential variables before it.
+#[derive(Debug, Clone)]
+pub struct Strategy {
+    pub value: bool,
+    pub functions: IndexMap<String, Tokens>,
+}
+
+impl Qbf {
+    pub fn new(prefix: Vec<(Quantifier, String)>, matrix: Evaluator) -> Result<Self, EvaluatorError> {
+        for (i, (_, name)) in prefix.iter().enumerate() {
+            if prefix[..i].iter().any(|(_, other)| other == name) {
+                return Err(EvaluatorError::new(format!("The variable '{}' is quantified twice.", name)));
+            }
+        }
+        let free: Vec<String> =
+            matrix.variables().into_iter().filter(|v| !prefix.iter().any(|(_, name)| name == v)).collect();
+        let prefix = free.into_iter().map(|v| (Quantifier::Exists, v)).chain(prefix).collect();
+        Ok(Qbf { prefix, matrix })
+    }
+
+    /// reads `∀p ∃q. EXPR` or `forall p, q exists r. EXPR`, the quantifiers are only allowed in
+    /// front. Error positions are byte offsets in `text`.
+    pub fn from_text(text: &str) -> Result<Self, EvaluatorError> {
+        let lexemes = lex(text)?;
+        let mut prefix: Vec<(Quantifier, String)> = Vec::new();
+        let mut i = 0;
+        while let Some((Lexeme::Quantifier(quantifier), span)) = lexemes.get(i) {
+            i += 1;
+            loop {
+                let Some((Lexeme::Token(Token::Ident(name)), variable)) = lexemes.get(i) else {
+                    let position = lexemes.get(i).map_or(span.end, |l| l.1.start);
+                    return Err(EvaluatorError::at("Expected a variable after the quantifier.", Some(position)));
+                };
+                if prefix.iter().any(|(_, other)| other == name) {
+                    return Err(EvaluatorError::at(format!("'{}' is quantified twice.", name), Some(variable.start)));
+                }
+                prefix.push((*quantifier, name.clone()));
+                i += 1;
+                match lexemes.get(i).map(|l| &l.0) {
+                    Some(Lexeme::Token(Token::Comma)) => i += 1,
+                    _ => break,
+                }
+            }
+            if lexemes.get(i).map(|l| &l.0) == Some(&Lexeme::Dot) {
+                i += 1;
+            }
+        }
+        let mut tokens = Vec::new();
+        let mut spans = Vec::new();
+        for (lexeme, span) in &lexemes[i..] {
+            match lexeme {
+                Lexeme::Token(token) => {
+                    tokens.push(token.clone());
+                    spans.push(span.clone());
+                }
+                Lexeme::Quantifier(_) => {
+                    return Err(EvaluatorError::at("Quantifiers are only allowed in front of the formula.", Some(span.start)));
+                }
+                Lexeme::Dot => return Err(EvaluatorError::at("'.' only follows the variables of a quantifier.", Some(span.start))),
+            }
+        }
+        if tokens.is_empty() && !prefix.is_empty() {
+            return Err(EvaluatorError::at("Missing the formula after the quantifiers.", Some(text.trim_end().len())));
+        }
+        Self::new(prefix, Evaluator::new(Tokens::with_spans(tokens, spans))?)
+    }
+
+    /// the quantified variables from the outermost, the free variables first.
+    pub fn prefix(&self) -> &[(Quantifier, String)] {
+        &self.prefix
+    }
+
+    pub fn matrix(&self) -> &Evaluator {
+        &self.matrix
+    }
+
+    fn variables(&self) -> Vec<String> {
+        self.prefix.iter().map(|(_, name)| name.clone()).collect()
+    }
+
+    /// whether the formula is true. Small formulas are expanded, larger ones are decided by a
+    /// QDPLL search on the CNF of the matrix.
+    pub fn is_true(&self) -> Result<bool, EvaluatorError> {
+        if self.prefix.len() <= EXPANSION_LIMIT {
+            return Ok(self.expansion()?[0][0]);
+        }
+        let (search, clauses) = Search::new(self);
+        Ok(search.solve(clauses))
+    }
+
+    /// the value of every position of the game: `levels[k][p]` is the value of the formula once
+    /// the first `k` variables have the values of the bits of `p`, the first one the most
+    /// significant. `levels[0][0]` is the value of the formula.
+    fn expansion(&self) -> Result<Vec<Vec<bool>>, EvaluatorError> {
+        let variables = self.variables();
+        let n = variables.len();
+        let mut levels = vec![Vec::new(); n + 1];
+        levels[n] = (0..1u64 << n).map(|i| self.matrix.value(&assignment(&variables, i))).collect::<Result<_, _>>()?;
+        for k in (0..n).rev() {
+            let below = &levels[k + 1];
+            levels[k] = (0..below.len() / 2)
+                .map(|p| match self.prefix[k].0 {
+                    Quantifier::ForAll => below[2 * p] && below[2 * p + 1],
+                    Quantifier::Exists => below[2 * p] || below[2 * p + 1],
+                })
+                .collect();
+        }
+        Ok(levels)
+    }
+
+    /// the strategy of the winning side, `None` when the losing side has more than 12 variables.
+    pub fn strategy(&self) -> Result<Option<Strategy>, EvaluatorError> {
+        let value = self.is_true()?;
+        let player = if value { Quantifier::Exists } else { Quantifier::ForAll };
+        if self.prefix.iter().filter(|(q, _)| *q != player).count() > STRATEGY_LIMIT {
+            return Ok(None);
+        }
+        let levels = match self.prefix.len() <= EXPANSION_LIMIT {
+            true => Some(self.expansion()?),
+            false => None,
+        };
+        let (search, clauses) = Search::new(self);
+        let mut game = Game {
+            qbf: self,
+            player,
+            value: &mut |k: usize, p: u64| match &levels {
+                Some(levels) => levels[k][p as usize],
+                None => search.solve_from(&clauses, k, p),
+            },
+            tables: IndexMap::new(),
+        };
+        for (k, (q, name)) in self.prefix.iter().enumerate() {
+            if *q == player {
+                let arguments: Vec<String> =
+                    self.prefix[..k].iter().filter(|(q, _)| *q != player).map(|(_, n)| n.clone()).collect();
+                let rows = 1 << arguments.len();
+                game.tables.insert(name.clone(), (arguments, vec![false; rows]));
+            }
+        }
+        game.play(0, 0);
+        let mut functions = IndexMap::new();
+        for (name, (arguments, table)) in game.tables {
+            functions.insert(name, Specification::new(arguments, table)?.minimized());
+        }
+        Ok(Some(Strategy { value, functions }))
+    }
+}
+
+/// A walk through the positions the winning side reaches, recording its moves.
+struct Game<'a, F: FnMut(usize, u64) -> bool> {
+    qbf: &'a Qbf,
+    player: Quantifier,
+    /// the value of a position, see `Qbf::expansion`.
+    value: &'a mut F,
+    /// the arguments of each function of the strategy and its table over them.
+    tables: IndexMap<String, (Vec<String>, Vec<bool>)>,
+}
+
+impl<F: FnMut(usize, u64) -> bool> Game<'_, F> {
+    fn play(&mut self, k: usize, p: u64) {
+        let Some((quantifier, name)) = self.qbf.prefix.get(k) else {
+            return;
+        };
+        if *quantifier != self.player {
+            for b in [1, 0] {
+                self.play(k + 1, 2 * p + b);
+            }
+            return;
+        }
+        //the move that keeps the value the player wants, true if both do.
+        let wanted = self.player == Quantifier::Exists;
+        let b = u64::from((self.value)(k + 1, 2 * p + 1) == wanted);
+        let mut row = 0;
+        for (j, (q, _)) in self.qbf.prefix[..k].iter().enumerate() {
+            if *q != self.player {
+                row = row << 1 | (p >> (k - 1 - j) & 1) as usize;
+            }
+        }
+        self.tables.get_mut(name).expect("a variable of the player").1[row] = b == 1;
+        self.play(k + 1, 2 * p + b);
+    }
+}
+
+/// QDPLL on the Tseitin CNF of the matrix. The Tseitin variables are existential and innermost.
+struct Search {
+    /// the position in the prefix of each variable `v`, at `v - 1`.
+    levels: Vec<usize>,
+    universal: Vec<bool>,
+}
+
+impl Search {
+    fn new(qbf: &Qbf) -> (Self, Vec<Clause>) {
+        let variables = qbf.variables();
+        let cnf = Cnf::tseitin(&Expression::from(&qbf.matrix), &variables);
+        let n = variables.len();
+        let levels = (0..cnf.count).map(|i| i.min(n)).collect();
+        let universal = (0..cnf.count).map(|i| i < n && qbf.prefix[i].0 == Quantifier::ForAll).collect();
+        (Search { levels, universal }, cnf.clauses)
+    }
+
+    fn level(&self, literal: i32) -> usize {
+        self.levels[literal.unsigned_abs() as usize - 1]
+    }
+
+    fn is_universal(&self, literal: i32) -> bool {
+        self.universal[literal.unsigned_abs() as usize - 1]
+    }
+
+    /// drops the universal literals that are quantified inside every existential literal of
+    /// their clause: the universal side makes them false.
+    fn reduce(&self, clauses: Vec<Clause>) -> Vec<Clause> {
+        clauses
+            .into_iter()
+            .map(|clause| {
+                let deepest = clause.iter().filter(|l| !self.is_universal(**l)).map(|l| self.level(*l)).max();
+                clause.into_iter().filter(|l| !self.is_universal(*l) || deepest.is_some_and(|d| self.level(*l) < d)).collect()
+            })
+            .collect()
+    }
+
+    /// a literal that only appears with one sign, made true if existential and false if universal.
+    fn pure(&self, clauses: &[Clause]) -> Option<i32> {
+        let literals: HashSet<i32> = clauses.iter().flatten().copied().collect();
+        let pure = literals.iter().filter(|l| !literals.contains(&-**l)).min_by_key(|l| (self.level(**l), l.abs()))?;
+        Some(if self.is_universal(*pure) { -pure } else { *pure })
+    }
+
+    fn solve(&self, mut clauses: Vec<Clause>) -> bool {
+        loop {
+            clauses = self.reduce(clauses);
+            if clauses.iter().any(|c| c.is_empty()) {
+                return false;
+            }
+            if clauses.is_empty() {
+                return true;
+            }
+            //after the reduction a unit clause is a single existential literal.
+            let forced = clauses.iter().find(|c| c.len() == 1).map(|c| c[0]).or_else(|| self.pure(&clauses));
+            match forced {
+                Some(literal) => match assign(&clauses, literal) {
+                    Some(rest) => clauses = rest,
+                    None => return false,
+                },
+                None => break,
+            }
+        }
+        //the outermost variable left is the next to be chosen.
+        let v = clauses.iter().flatten().map(|l| l.abs()).min_by_key(|v| self.level(*v)).expect("a literal");
+        let branch = |literal: i32| assign(&clauses, literal).is_some_and(|rest| self.solve(rest));
+        match self.is_universal(v) {
+            true => branch(v) && branch(-v),
+            false => branch(v) || branch(-v),
+        }
+    }
+
+    /// the value once the first `k` variables have the values of the bits of `p`.
+    fn solve_from(&self, clauses: &[Clause], k: usize, p: u64) -> bool {
+        let mut clauses = clauses.to_vec();
+        for j in 0..k {
+            let literal = if p >> (k - 1 - j) & 1 == 1 { j as i32 + 1 } else { -(j as i32 + 1) };
+            match assign(&clauses, literal) {
+                Some(rest) => clauses = rest,
+                None => return false,
+            }
+        }
+        self.solve(clauses)
+    }
+}
+
+impl fmt::Display for Qbf {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut blocks: Vec<(Quantifier, Vec<&str>)> = Vec::new();
+        for (q, name) in &self.prefix {
+            match blocks.last_mut() {
+                Some((last, names)) if last == q => names.push(name),
+                _ => blocks.push((*q, vec![name])),
+            }
+        }
+        let blocks: Vec<String> = blocks.iter().map(|(q, names)| format!("{}{}", q, names.join(", "))).collect();
+        match blocks.is_empty() {
+            true => write!(f, "{}", Tokens::from(self.matrix.clone())),
+            false => write!(f, "{}. {}", blocks.join(" "), Tokens::from(self.matrix.clone())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn qbf(s: &str) -> Qbf {
+        Qbf::from_text(s).unwrap()
+    }
+
+    /// the matrix with the functions of the strategy put in.
+    fn played(qbf: &Qbf, strategy: &Strategy) -> Evaluator {
+        let matrix = Expression::from(qbf.matrix()).replace_variables(&mut |name| {
+            Ok::<_, EvaluatorError>(match strategy.functions.get(name) {
+                Some(tokens) => Expression::from(&Evaluator::new(tokens.clone())?),
+                None => Expression::Variable(name.to_string()),
+            })
+        });
+        Evaluator::new(matrix.unwrap().to_tokens()).unwrap()
+    }
+
+    fn check_strategy(qbf: &Qbf) {
+        let strategy = qbf.strategy().unwrap().unwrap();
+        let played = played(qbf, &strategy);
+        match strategy.value {
+            true => assert!(played.is_tautology().unwrap(), "{}", qbf),
+            false => assert!(!played.is_satisfiable().unwrap(), "{}", qbf),
+        }
+    }
+
+    #[test]
+    fn values() {
+        assert!(qbf("∀p ∃q. p ↔ q").is_true().unwrap());
+        assert!(!qbf("∃q ∀p. p ↔ q").is_true().unwrap());
+        assert!(qbf("forall p, q exists r. maj(p, q, r) = (p or q)").is_true().unwrap());
+        //r is free, so existential.
+        let free = qbf("∀p. p or r");
+        assert_eq!(free.to_string(), "∃r ∀p. p ∨ r");
+        assert!(free.is_true().unwrap());
+    }
+
+    #[test]
+    fn strategies() {
+        let strategy = qbf("∀p ∃q. p ↔ q").strategy().unwrap().unwrap();
+        assert_eq!(strategy.functions["q"].to_string(), "p");
+        let counter = qbf("∃q ∀p. p ↔ q").strategy().unwrap().unwrap();
+        assert!(!counter.value);
+        assert_eq!(counter.functions["p"].to_string(), "¬q");
+        for s in [
+            "∀a, b ∃c ∀d ∃e. (c ↔ a ⊕ b) ∧ (e ↔ c ∧ d)",
+            "∃x ∀y ∃z. (x ∨ y ∨ z) ∧ (¬x ∨ ¬y ∨ ¬z) ∧ (y ↔ ¬z)",
+            "∀x ∃y ∀z. (y → x) ∧ (z ∨ y)",
+            "∃a ∀b ∃c. exactly(1, a, b, c)",
+        ] {
+            check_strategy(&qbf(s));
+        }
+    }
+
+    #[test]
+    fn search_agrees_with_expansion() {
+        for s in [
+            "∀p ∃q. p ↔ q",
+            "∃q ∀p. p ↔ q",
+            "∀a, b ∃c ∀d ∃e. (c ↔ a ⊕ b) ∧ (e ↔ c ∧ d)",
+            "∃x ∀y ∃z. (x ∨ y ∨ z) ∧ (¬x ∨ ¬y ∨ ¬z) ∧ (y ↔ ¬z)",
+            "∀x ∃y ∀z. (y → x) ∧ (z ∨ y)",
+            "∃a ∀b ∃c. exactly(1, a, b, c)",
+            "∀a ∃b ∀c ∃d. ite(a, b ↑ c, d ↓ c) ∧ (a ↛ d)",
+            "∃p ∀q. p ∧ ¬p",
+        ] {
+            let qbf = qbf(s);
+            let (search, clauses) = Search::new(&qbf);
+            assert_eq!(search.solve(clauses), qbf.expansion().unwrap()[0][0], "{}", s);
+        }
+        //20 variables are searched.
+        let copies = (0..10).map(|i| format!("(x{} ↔ y{})", i, i)).collect::<Vec<_>>().join(" ∧ ");
+        let xs = (0..10).map(|i| format!("x{}", i)).collect::<Vec<_>>().join(", ");
+        let ys = (0..10).map(|i| format!("y{}", i)).collect::<Vec<_>>().join(", ");
+        let large = qbf(&format!("∀{} ∃{}. {}", xs, ys, copies));
+        assert!(large.is_true().unwrap());
+        assert!(!qbf(&format!("∃{} ∀{}. {}", ys, xs, copies)).is_true().unwrap());
+        //the strategy of a searched formula takes a search per move, few universal variables keep it short.
+        let copies = (0..16).map(|i| format!("(y{} ↔ x{} ⊕ y{})", i, i % 2, (i + 15) % 16)).collect::<Vec<_>>().join(" ∨ ");
+        let ys = (0..16).map(|i| format!("y{}", i)).collect::<Vec<_>>().join(", ");
+        check_strategy(&qbf(&format!("∀x0, x1 ∃{}. {}", ys, copies)));
+    }
+
+    #[test]
+    fn search_over_xor() {
+        //17 variables are searched, the xor chain is encoded with a gate per operator.
+        let xs: Vec<String> = (0..16).map(|i| format!("x{}", i)).collect();
+        let parity = qbf(&format!("∀{} ∃y. y ↔ ({})", xs.join(", "), xs.join(" ⊕ ")));
+        assert!(parity.is_true().unwrap());
+        let fixed = qbf(&format!("∃y ∀{}. y ↔ ({})", xs.join(", "), xs.join(" ⊕ ")));
+        assert!(!fixed.is_true().unwrap());
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(Qbf::from_text("∀p. p ∧ ∃q. q").err().unwrap().position(), Some(12));
+        assert!(Qbf::from_text("∀p ∀p. p").is_err());
+        assert!(Qbf::from_text("∀p.").is_err());
+        assert_eq!(Qbf::from_text("∀p. p ∧").err().unwrap().message(), Evaluator::new(Tokens::from_text("p ∧")).err().unwrap().message());
+    }
+}
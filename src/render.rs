@@ -0,0 +1,201 @@
+use std::str::FromStr;
+
+/// How `true` and `false` are written in the cells of a truth table.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum ValueStyle {
+    /// `true` / `false`
+    #[default]
+    Words,
+    /// `T` / `F`
+    Letters,
+    /// `1` / `0`
+    Digits,
+    /// `⊤` / `⊥`
+    Symbols,
+    /// user supplied text for true and false.
+    Custom { t: String, f: String },
+}
+
+impl ValueStyle {
+    pub fn render(&self, value: bool) -> &str {
+        match (self, value) {
+            (ValueStyle::Words, true) => "true",
+            (ValueStyle::Words, false) => "false",
+            (ValueStyle::Letters, true) => "T",
+            (ValueStyle::Letters, false) => "F",
+            (ValueStyle::Digits, true) => "1",
+            (ValueStyle::Digits, false) => "0",
+            (ValueStyle::Symbols, true) => "⊤",
+            (ValueStyle::Symbols, false) => "⊥",
+            (ValueStyle::Custom { t, .. }, true) => t,
+            (ValueStyle::Custom { f, .. }, false) => f,
+        }
+    }
+}
+
+impl FromStr for ValueStyle {
+    type Err = String;
+    /// `words`, `letters`, `digits`, `symbols` or a custom pair such as `yes/no`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "words" | "true/false" => Ok(ValueStyle::Words),
+            "letters" | "T/F" => Ok(ValueStyle::Letters),
+            "digits" | "1/0" => Ok(ValueStyle::Digits),
+            "symbols" | "⊤/⊥" => Ok(ValueStyle::Symbols),
+            _ => match s.split_once('/') {
+                Some((t, f)) => Ok(ValueStyle::Custom { t: t.into(), f: f.into() }),
+                None => Err(format!("unknown value style '{}'", s)),
+            },
+        }
+    }
+}
+
+/// The symbols used for the operators in the column headers.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SymbolSet {
+    /// `¬`, `∧`, `∨`, `⊕`, `→`, `↔`, `≡`, `≠`
+    #[default]
+    Unicode,
+    /// `!`, `&`, `|`, `xor`, `->`, `<->`, `==`, `!=`
+    Ascii,
+}
+
+impl SymbolSet {
+    pub fn render(&self, header: &str) -> String {
+        match self {
+            SymbolSet::Unicode => header.to_string(),
+            SymbolSet::Ascii => {
+                let mut out = String::with_capacity(header.len());
+                for c in header.chars() {
+                    match ascii_symbol(c) {
+                        Some(s) => out.push_str(s),
+                        None => out.push(c),
+                    }
+                }
+                out
+            }
+        }
+    }
+}
+
+impl FromStr for SymbolSet {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "unicode" => Ok(SymbolSet::Unicode),
+            "ascii" => Ok(SymbolSet::Ascii),
+            _ => Err(format!("unknown symbol set '{}'", s)),
+        }
+    }
+}
+
+/// ascii spelling of an operator symbol. the spellings are accepted by the tokenizer.
+pub fn ascii_symbol(symbol: char) -> Option<&'static str> {
+    match symbol {
+        '¬' => Some("!"),
+        '□' => Some("box "),
+        '◇' => Some("dia "),
+        '∧' => Some("&"),
+        '∨' => Some("|"),
+        '⊕' => Some("xor"),
+        '→' => Some("->"),
+        '←' => Some("<-"),
+        '↛' => Some("-/>"),
+        '↑' => Some("nand"),
+        '↓' => Some("nor"),
+        '↔' => Some("<->"),
+        '≡' => Some("=="),
+        '≠' => Some("!="),
+        _ => None,
+    }
+}
+
+/// Color scheme of the html report.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Theme {
+    #[default]
+    Light,
+    Dark,
+    /// follows the `prefers-color-scheme` of the browser.
+    Auto,
+}
+
+impl FromStr for Theme {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "light" => Ok(Theme::Light),
+            "dark" => Ok(Theme::Dark),
+            "auto" => Ok(Theme::Auto),
+            _ => Err(format!("unknown theme '{}'", s)),
+        }
+    }
+}
+
+/// Options shared by all the outputs (terminal, csv, html, pdf, ...).
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RenderOptions {
+    pub values: ValueStyle,
+    pub symbols: SymbolSet,
+    /// emphasize the last column, which holds the value of the whole expression.
+    pub highlight_result: bool,
+    /// only used by the html report.
+    pub theme: Theme,
+}
+
+impl RenderOptions {
+    pub fn with_values(mut self, values: ValueStyle) -> Self {
+        self.values = values;
+        self
+    }
+
+    pub fn with_symbols(mut self, symbols: SymbolSet) -> Self {
+        self.symbols = symbols;
+        self
+    }
+
+    pub fn with_highlight_result(mut self, highlight_result: bool) -> Self {
+        self.highlight_result = highlight_result;
+        self
+    }
+
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    pub fn value(&self, value: bool) -> &str {
+        self.values.render(value)
+    }
+
+    pub fn header(&self, header: &str) -> String {
+        self.symbols.render(header)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values() {
+        assert_eq!(ValueStyle::Words.render(true), "true");
+        assert_eq!(ValueStyle::Digits.render(false), "0");
+        assert_eq!(ValueStyle::Symbols.render(true), "⊤");
+        let custom = ValueStyle::Custom { t: "yes".into(), f: "no".into() };
+        assert_eq!(custom.render(false), "no");
+        assert_eq!("T/F".parse::<ValueStyle>().unwrap(), ValueStyle::Letters);
+        assert_eq!("yes/no".parse::<ValueStyle>().unwrap(), custom);
+    }
+
+    #[test]
+    fn ascii_headers() {
+        let options = RenderOptions::default().with_symbols(SymbolSet::Ascii);
+        assert_eq!(options.header("((P ∧ ¬Q) ↔ (P → Q))"), "((P & !Q) <-> (P -> Q))");
+        assert_eq!(options.header("(P ⊕ Q)"), "(P xor Q)");
+        let header = "((P ↑ Q) ↛ (P ↓ Q)) ← R";
+        let ascii = options.header(header);
+        assert_eq!(ascii, "((P nand Q) -/> (P nor Q)) <- R");
+        assert_eq!(crate::tokenizer::Tokens::from_text(&ascii).to_string(), header);
+    }
+}
@@ -0,0 +1,355 @@
+use indexmap::IndexMap;
+
+use crate::definitions::Definitions;
+use crate::evaluator::{Evaluator, EvaluatorError};
+use crate::evaluator_result::EvaluatorResult;
+use crate::render::RenderOptions;
+use crate::tokenizer::{Token, Tokens};
+
+const HELP: &str = "\
+EXPR                 print the truth table of EXPR
+let p = 1            fix the value of a variable (1, 0, true or false)
+name := EXPR         define a formula, `name` is replaced by (EXPR) in expressions
+def name = EXPR      the same as name := EXPR
+:unset name          remove a variable value or a definition
+:env                 list the variable values and the definitions
+:check EXPR          tautology, contradiction or contingency
+:sat EXPR            an assignment that makes EXPR true
+:equiv EXPR, EXPR    compare two expressions
+:simplify EXPR       minimal sum of products
+:cnf EXPR            minimal product of sums
+:residual EXPR       EXPR with the values of `let` put in and folded away
+:post EXPR           the classes of Post's lattice of EXPR (0-/1-preserving, self-dual, monotone, affine)
+:set values STYLE    words, letters, digits, symbols or a pair like yes/no
+:set symbols SET     unicode or ascii
+:set named on|off    show the value of each definition as a column of the tables
+:history             the lines entered so far
+:help                this help
+:quit                leave";
+
+/// What the session has to show for a line.
+pub enum Output {
+    Table(EvaluatorResult),
+    Text(String),
+    Nothing,
+    Quit,
+}
+
+/// The state of an interactive session: variable values, definitions and history.
+/// Error positions are byte offsets into the line given to `execute`.
+#[derive(Default)]
+pub struct Session {
+    pub options: RenderOptions,
+    bindings: IndexMap<String, bool>,
+    definitions: Definitions,
+    named_columns: bool,
+    history: Vec<String>,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// adds a line to the history without executing it, e.g. lines loaded from a history file.
+    pub fn push_history(&mut self, line: &str) {
+        self.history.push(line.to_string());
+    }
+
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    pub fn execute(&mut self, line: &str) -> Result<Output, EvaluatorError> {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            return Ok(Output::Nothing);
+        }
+        if !trimmed.starts_with(":history") {
+            self.push_history(trimmed);
+        }
+        let offset = line.len() - line.trim_start().len();
+        let (word, rest) = split_word(trimmed);
+        let rest_offset = offset + trimmed.len() - rest.len();
+        match word {
+            "let" => self.bind(rest, rest_offset),
+            "def" => self.define(rest, rest_offset),
+            ":unset" => {
+                let name = rest.trim();
+                let removed = self.definitions.remove(name).is_some() | self.bindings.shift_remove(name).is_some();
+                if removed {
+                    Ok(Output::Nothing)
+                } else {
+                    Err(EvaluatorError::at(format!("'{}' is not defined.", name), Some(rest_offset)))
+                }
+            }
+            ":env" => Ok(Output::Text(self.environment())),
+            ":check" => {
+                let evaluator = self.compile(rest, rest_offset, true)?;
+                Ok(Output::Text(evaluator.classify()?.to_string()))
+            }
+            ":sat" => {
+                let evaluator = self.compile(rest, rest_offset, true)?;
+                Ok(Output::Text(match evaluator.satisfying_assignment()? {
+                    Some(row) => self.describe(&row),
+                    None => "unsatisfiable".into(),
+                }))
+            }
+            ":equiv" => {
+                let Some(comma) = top_level_comma(rest) else {
+                    return Err(EvaluatorError::at("Expected two expressions separated by ','.", Some(rest_offset)));
+                };
+                let left = self.compile(&rest[..comma], rest_offset, true)?;
+                let right = self.compile(&rest[comma + 1..], rest_offset + comma + 1, true)?;
+                Ok(Output::Text(match left.difference(&right)? {
+                    None => "equivalent".into(),
+                    Some(row) => format!("not equivalent, they differ when {}", self.describe(&row)),
+                }))
+            }
+            ":simplify" => {
+                let evaluator = self.compile(rest, rest_offset, true)?;
+                Ok(Output::Text(self.options.header(&evaluator.simplify()?.to_string())))
+            }
+            ":cnf" => {
+                let evaluator = self.compile(rest, rest_offset, true)?;
+                Ok(Output::Text(self.options.header(&evaluator.cnf()?.to_string())))
+            }
+            ":residual" => {
+                let evaluator = self.compile(rest, rest_offset, false)?;
+                let residual = evaluator.partial_evaluate(&self.bindings)?;
+                Ok(Output::Text(self.options.header(&residual.to_string())))
+            }
+            ":post" => {
+                let evaluator = self.compile(rest, rest_offset, true)?;
+                let classes: Vec<String> = evaluator.post_classes()?.iter().map(|c| c.to_string()).collect();
+                Ok(Output::Text(if classes.is_empty() { "none".into() } else { classes.join(", ") }))
+            }
+            ":set" => self.set(rest, rest_offset),
+            ":history" => Ok(Output::Text(self.history.join("\n"))),
+            ":help" => Ok(Output::Text(HELP.into())),
+            ":quit" | ":q" | ":exit" => Ok(Output::Quit),
+            _ if word.starts_with(':') => {
+                Err(EvaluatorError::at(format!("Unknown command '{}', see :help.", word), Some(offset)))
+            }
+            _ => {
+                let tokens = lex(trimmed, offset)?;
+                if Definitions::is_definition(&tokens) {
+                    self.definitions.define(tokens)?;
+                    return Ok(Output::Nothing);
+                }
+                let evaluator = self.definitions.compile(&tokens)?;
+                let mut result = self.definitions.evaluate_all(&tokens, self.named_columns)?;
+                let bound: Vec<(String, bool)> = evaluator
+                    .variables()
+                    .iter()
+                    .filter_map(|v| self.bindings.get(v).map(|b| (v.clone(), *b)))
+                    .collect();
+                result.result.retain(|row| bound.iter().all(|(v, b)| row.get(v) == Some(b)));
+                Ok(Output::Table(result))
+            }
+        }
+    }
+
+    fn bind(&mut self, text: &str, offset: usize) -> Result<Output, EvaluatorError> {
+        let (name, value) = assignment(text, offset)?;
+        let name = single_ident(name.0, name.1)?;
+        let tokens = Tokens::from_text(value.0);
+        let value = match (tokens.first(), tokens.len(), tokens.unknown().is_empty()) {
+            (Some(Token::True), 1, true) => true,
+            (Some(Token::False), 1, true) => false,
+            _ => return Err(EvaluatorError::at("Expected a value: 1, 0, true or false.", Some(value.1))),
+        };
+        self.bindings.insert(name, value);
+        Ok(Output::Nothing)
+    }
+
+    fn define(&mut self, text: &str, offset: usize) -> Result<Output, EvaluatorError> {
+        let (name, body) = assignment(text, offset)?;
+        let eq = body.1 - 1;
+        let name = single_ident(name.0, name.1)?;
+        let body = lex(body.0, body.1)?;
+        let mut tokens = vec![Token::Ident(name), Token::Define];
+        tokens.extend(body.iter().cloned());
+        let mut spans = vec![eq..eq + 1; 2];
+        spans.extend(body.spans().iter().cloned());
+        self.definitions.define(Tokens::with_spans(tokens, spans))?;
+        Ok(Output::Nothing)
+    }
+
+    fn set(&mut self, text: &str, offset: usize) -> Result<Output, EvaluatorError> {
+        let (what, value) = split_word(text.trim());
+        let result = match what {
+            "values" => value.trim().parse().map(|v| self.options.values = v),
+            "symbols" => value.trim().parse().map(|v| self.options.symbols = v),
+            "named" => match value.trim() {
+                "on" | "off" => {
+                    self.named_columns = value.trim() == "on";
+                    Ok(())
+                }
+                other => Err(format!("Expected on or off, not '{}'.", other)),
+            },
+            _ => Err(format!("Unknown setting '{}'.", what)),
+        };
+        result
+            .map(|_| Output::Nothing)
+            .map_err(|e| EvaluatorError::at(e, Some(offset)))
+    }
+
+    /// the tokens of `text` with the definitions expanded. With `substitute`, variables with a
+    /// value are replaced by the value.
+    fn expand(&self, text: &str, offset: usize, substitute: bool) -> Result<Tokens, EvaluatorError> {
+        let expanded = self.definitions.expand(&lex(text, offset)?)?;
+        if !substitute {
+            return Ok(expanded);
+        }
+        let tokens = expanded
+            .iter()
+            .map(|t| match t {
+                Token::Ident(name) => self.bindings.get(name).map(|v| Token::from(*v)).unwrap_or(t.clone()),
+                _ => t.clone(),
+            })
+            .collect();
+        Ok(Tokens::with_spans(tokens, expanded.spans().to_vec()))
+    }
+
+    fn compile(&self, text: &str, offset: usize, substitute: bool) -> Result<Evaluator, EvaluatorError> {
+        let tokens = self.expand(text, offset, substitute)?;
+        if tokens.is_empty() {
+            return Err(EvaluatorError::at("Empty expression.", Some(offset)));
+        }
+        Evaluator::new(tokens)
+    }
+
+    fn describe(&self, row: &IndexMap<String, bool>) -> String {
+        if row.is_empty() {
+            return "always".into();
+        }
+        row.iter()
+            .map(|(k, v)| format!("{} = {}", k, self.options.value(*v)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn environment(&self) -> String {
+        let mut lines: Vec<String> = self
+            .bindings
+            .iter()
+            .map(|(k, v)| format!("let {} = {}", k, self.options.value(*v)))
+            .collect();
+        for (name, tokens) in self.definitions.iter() {
+            lines.push(format!("{} := {}", name, self.options.header(&tokens.to_string())));
+        }
+        lines.join("\n")
+    }
+}
+
+/// the first word of `text` and the rest after it.
+fn split_word(text: &str) -> (&str, &str) {
+    match text.find(char::is_whitespace) {
+        Some(i) => (&text[..i], &text[i..]),
+        None => (text, ""),
+    }
+}
+
+/// splits `name = value` into the two sides with their offsets.
+#[allow(clippy::type_complexity)]
+fn assignment(text: &str, offset: usize) -> Result<((&str, usize), (&str, usize)), EvaluatorError> {
+    let Some(eq) = text.find('=') else {
+        return Err(EvaluatorError::at("Expected 'name = value'.", Some(offset + text.len())));
+    };
+    Ok(((&text[..eq], offset), (&text[eq + 1..], offset + eq + 1)))
+}
+
+/// checks that `text` is a single name and returns it.
+fn single_ident(text: &str, offset: usize) -> Result<String, EvaluatorError> {
+    let tokens = Tokens::from_text(text);
+    match (tokens.first(), tokens.len(), tokens.unknown().is_empty()) {
+        (Some(Token::Ident(name)), 1, true) => Ok(name.clone()),
+        _ => Err(EvaluatorError::at("Expected a name made of letters, digits and '_'.", Some(offset))),
+    }
+}
+
+/// the tokens of `text`, which starts at `offset` in the line.
+fn lex(text: &str, offset: usize) -> Result<Tokens, EvaluatorError> {
+    let tokens = Tokens::from_text(text).shifted(offset);
+    match tokens.unknown().first() {
+        Some(span) => Err(EvaluatorError::at("Unknown symbol.", Some(span.start))),
+        None => Ok(tokens),
+    }
+}
+
+/// the position of the first ',' outside of any parentheses.
+fn top_level_comma(text: &str) -> Option<usize> {
+    let mut depth = 0i32;
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            ',' if depth == 0 => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(output: Output) -> String {
+        match output {
+            Output::Text(text) => text,
+            _ => panic!("expected text"),
+        }
+    }
+
+    #[test]
+    fn definitions() {
+        let mut session = Session::new();
+        session.execute("def imp = p -> q").unwrap();
+        assert_eq!(text(session.execute(":equiv imp, not p or q").unwrap()), "equivalent");
+        assert_eq!(text(session.execute(":check imp <-> (not q -> not p)").unwrap()), "tautology");
+        assert_eq!(text(session.execute(":post imp").unwrap()), "1-preserving");
+        let Output::Table(table) = session.execute("imp and r").unwrap() else {
+            panic!("expected a table");
+        };
+        assert_eq!(table.result.len(), 8);
+        assert!(table.result[0].contains_key("((p → q) ∧ r)"));
+        session.execute("door_ok := door_closed and not alarm").unwrap();
+        session.execute(":set named on").unwrap();
+        let Output::Table(table) = session.execute("door_ok or imp").unwrap() else {
+            panic!("expected a table");
+        };
+        assert!(table.result[0].contains_key("door_ok") && table.result[0].contains_key("imp"));
+        assert!(session.execute("imp := imp or r").is_err());
+    }
+
+    #[test]
+    fn bindings() {
+        let mut session = Session::new();
+        session.execute("let p = 1").unwrap();
+        let Output::Table(table) = session.execute("p and q").unwrap() else {
+            panic!("expected a table");
+        };
+        assert_eq!(table.result.len(), 2);
+        assert_eq!(text(session.execute(":simplify p and q").unwrap()), "q");
+        session.execute("def imp = p -> q").unwrap();
+        assert_eq!(text(session.execute(":equiv imp, q").unwrap()), "equivalent");
+        assert_eq!(text(session.execute(":residual imp and (r or not p)").unwrap()), "q ∧ r");
+        session.execute(":unset p").unwrap();
+        assert_eq!(text(session.execute(":cnf (p or q) and (p or r)").unwrap()), "(p ∨ q) ∧ (p ∨ r)");
+    }
+
+    #[test]
+    fn error_positions() {
+        let mut session = Session::new();
+        let error = session.execute("  p and (q or )").err().unwrap();
+        assert_eq!(error.position(), Some(14));
+        session.execute("def a = p and q").unwrap();
+        let error = session.execute(":equiv a, a or %").err().unwrap();
+        assert_eq!(error.position(), Some(15));
+        assert!(session.execute("let p = 2").is_err());
+        assert!(session.execute(":nope").is_err());
+        assert_eq!(session.history().len(), 5);
+    }
+}
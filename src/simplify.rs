@@ -0,0 +1,209 @@
+use std::collections::BTreeSet;
+
+use crate::evaluator::{Evaluator, EvaluatorError};
+use crate::synthesis::Specification;
+use crate::tokenizer::{Token, Tokens};
+
+/// A product of literals. Bits set in `mask` are variables that do not appear in the term, the
+/// other bits of `value` give the polarity of the variables that do (1 = positive).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct Implicant {
+    pub value: u64,
+    pub mask: u64,
+}
+
+impl Implicant {
+    fn covers(&self, index: u64) -> bool {
+        index & !self.mask == self.value & !self.mask
+    }
+
+    fn literals(&self) -> u32 {
+        (!self.mask).count_ones()
+    }
+
+    /// terms over the first variables come first, positive literals before negative ones.
+    fn order_key(&self) -> (u64, u64) {
+        (self.mask, !self.value & !self.mask)
+    }
+}
+
+/// the prime implicants of the function that is true on `ones` (Quine-McCluskey).
+pub(crate) fn prime_implicants(ones: &[u64]) -> Vec<Implicant> {
+    let mut current: BTreeSet<Implicant> = ones.iter().map(|m| Implicant { value: *m, mask: 0 }).collect();
+    let mut primes = BTreeSet::new();
+    while !current.is_empty() {
+        let mut next = BTreeSet::new();
+        let mut combined = BTreeSet::new();
+        let terms: Vec<Implicant> = current.iter().copied().collect();
+        for (i, a) in terms.iter().enumerate() {
+            for b in terms[i + 1..].iter() {
+                if a.mask != b.mask {
+                    continue;
+                }
+                let diff = (a.value ^ b.value) & !a.mask;
+                if diff.count_ones() == 1 {
+                    next.insert(Implicant { value: a.value & !diff, mask: a.mask | diff });
+                    combined.insert(*a);
+                    combined.insert(*b);
+                }
+            }
+        }
+        primes.extend(current.difference(&combined).copied());
+        current = next;
+    }
+    primes.into_iter().collect()
+}
+
+/// picks prime implicants covering all of `ones`: the essential ones first, then the smallest
+/// cover of what is left (searched exhaustively when there are few candidates, greedily otherwise).
+pub(crate) fn minimal_cover(ones: &[u64]) -> Vec<Implicant> {
+    let primes = prime_implicants(ones);
+    let mut chosen: Vec<Implicant> = Vec::new();
+    for m in ones {
+        let covering: Vec<&Implicant> = primes.iter().filter(|p| p.covers(*m)).collect();
+        if covering.len() == 1 && !chosen.contains(covering[0]) {
+            chosen.push(*covering[0]);
+        }
+    }
+    let left: Vec<u64> = ones.iter().copied().filter(|m| !chosen.iter().any(|p| p.covers(*m))).collect();
+    if !left.is_empty() {
+        chosen.extend(cover_rest(&primes, &chosen, left));
+    }
+    chosen.sort_by_key(|p| p.order_key());
+    chosen
+}
+
+fn cover_rest(primes: &[Implicant], chosen: &[Implicant], left: Vec<u64>) -> Vec<Implicant> {
+    let candidates: Vec<Implicant> = primes
+        .iter()
+        .copied()
+        .filter(|p| !chosen.contains(p) && left.iter().any(|m| p.covers(*m)))
+        .collect();
+    if candidates.len() <= 16 {
+        let mut best: Option<Vec<Implicant>> = None;
+        for set in 1u32..(1 << candidates.len()) {
+            let picked: Vec<Implicant> = (0..candidates.len())
+                .filter(|i| set & (1 << i) != 0)
+                .map(|i| candidates[i])
+                .collect();
+            if !left.iter().all(|m| picked.iter().any(|p| p.covers(*m))) {
+                continue;
+            }
+            let cost = |v: &Vec<Implicant>| (v.len(), v.iter().map(|p| p.literals()).sum::<u32>());
+            if best.as_ref().is_none_or(|b| cost(&picked) < cost(b)) {
+                best = Some(picked);
+            }
+        }
+        best.unwrap_or_default()
+    } else {
+        let mut left = left;
+        let mut picked = Vec::new();
+        while !left.is_empty() {
+            let best = candidates
+                .iter()
+                .max_by_key(|p| (left.iter().filter(|m| p.covers(**m)).count(), std::cmp::Reverse(p.literals())))
+                .copied()
+                .unwrap();
+            left.retain(|m| !best.covers(*m));
+            picked.push(best);
+        }
+        picked
+    }
+}
+
+/// writes the implicants as a sum of products (`or` of `and`s), or as a product of sums when
+/// `product` is set, in which case each implicant describes a row where the function is false.
+pub(crate) fn to_tokens(variables: &[String], implicants: &[Implicant], product: bool) -> Tokens {
+    let n = variables.len();
+    let (inner, outer) = if product {
+        (Token::Or('∨'), Token::And('∧'))
+    } else {
+        (Token::And('∧'), Token::Or('∨'))
+    };
+    if implicants.is_empty() {
+        return Tokens::from(vec![Token::from(product)]);
+    }
+    let mut tokens = Vec::new();
+    for (i, implicant) in implicants.iter().enumerate() {
+        if i > 0 {
+            tokens.push(outer.clone());
+        }
+        let literals: Vec<(&String, bool)> = variables
+            .iter()
+            .enumerate()
+            .filter(|(k, _)| implicant.mask >> (n - k - 1) & 1 == 0)
+            .map(|(k, v)| (v, (implicant.value >> (n - k - 1)) & 1 == 1))
+            .collect();
+        if literals.is_empty() {
+            return Tokens::from(vec![Token::from(!product)]);
+        }
+        let enclose = literals.len() > 1 && implicants.len() > 1;
+        if enclose {
+            tokens.push(Token::OpenParen);
+        }
+        for (j, (v, positive)) in literals.iter().enumerate() {
+            if j > 0 {
+                tokens.push(inner.clone());
+            }
+            //in a product of sums a false row is excluded by the opposite literals.
+            if *positive == product {
+                tokens.push(Token::Not('¬'));
+            }
+            tokens.push(Token::Ident(v.to_string()));
+        }
+        if enclose {
+            tokens.push(Token::CloseParen);
+        }
+    }
+    Tokens::from(tokens)
+}
+
+impl Evaluator {
+    /// an equivalent sum of products with as few terms and literals as possible.
+    pub fn simplify(&self) -> Result<Tokens, EvaluatorError> {
+        Ok(Specification::from_evaluator(self)?.minimized())
+    }
+
+    /// an equivalent product of sums (conjunctive normal form) with as few clauses and literals as possible.
+    pub fn cnf(&self) -> Result<Tokens, EvaluatorError> {
+        Ok(Specification::from_evaluator(self)?.minimized_cnf())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn simplify(s: &str) -> String {
+        let evaluator = Evaluator::new(Tokens::from_text(s)).unwrap();
+        let simplified = evaluator.simplify().unwrap();
+        let check = Evaluator::new(simplified.clone()).unwrap();
+        assert!(evaluator.is_equivalent(&check).unwrap());
+        simplified.to_string()
+    }
+
+    #[test]
+    fn simplify_expressions() {
+        assert_eq!(simplify("(p and q) or (p and not q)"), "p");
+        assert_eq!(simplify("p or not p"), "1");
+        assert_eq!(simplify("p and not p"), "0");
+        assert_eq!(simplify("p -> q"), "¬p ∨ q");
+        assert_eq!(simplify("(p and q) or (not p and r) or (q and r)"), "(p ∧ q) ∨ (¬p ∧ r)");
+    }
+
+    #[test]
+    fn cnf() {
+        let evaluator = Evaluator::new(Tokens::from_text("(p or q) and (p or r)")).unwrap();
+        let cnf = evaluator.cnf().unwrap();
+        assert_eq!(cnf.to_string(), "(p ∨ q) ∧ (p ∨ r)");
+        assert!(evaluator.is_equivalent(&Evaluator::new(cnf).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn product_of_sums() {
+        let variables = ["p".to_string(), "q".to_string()];
+        //p → q is false only on the row p = 1, q = 0.
+        let zeros = minimal_cover(&[0b10]);
+        assert_eq!(to_tokens(&variables, &zeros, true).to_string(), "¬p ∨ q");
+    }
+}
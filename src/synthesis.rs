@@ -0,0 +1,350 @@
+use crate::evaluator::{assignment, Evaluator, EvaluatorError};
+use crate::evaluator_result::EvaluatorResult;
+use crate::simplify::{minimal_cover, to_tokens, Implicant};
+use crate::tokenizer::{Token, Tokens};
+
+/// A truth table to find a formula for: the names of the inputs and the output column. `table[i]`
+/// is the output for the row `i`, where the first input is the most significant bit of `i` (see
+/// `evaluator::assignment`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Specification {
+    variables: Vec<String>,
+    table: Vec<bool>,
+}
+
+impl Specification {
+    /// a table over `variables`, `table` must have `2^n` rows for `n` variables.
+    pub fn new(variables: Vec<String>, table: Vec<bool>) -> Result<Self, EvaluatorError> {
+        for (i, name) in variables.iter().enumerate() {
+            if !is_identifier(name) {
+                return Err(EvaluatorError::new(format!("'{}' can not be the name of a variable.", name)));
+            }
+            if variables[..i].contains(name) {
+                return Err(EvaluatorError::new(format!("The variable '{}' is given twice.", name)));
+            }
+        }
+        let n = variables.len();
+        if n >= 64 || table.len() as u64 != 1 << n {
+            return Err(EvaluatorError::new(format!(
+                "{} variables need a table of 2^{} rows, not {}.",
+                n,
+                n,
+                table.len()
+            )));
+        }
+        Ok(Specification { variables, table })
+    }
+
+    /// a table written as a bit vector like `0110`, row `0` first. Spaces and `_` are ignored.
+    pub fn from_bits(variables: Vec<String>, bits: &str) -> Result<Self, EvaluatorError> {
+        let table = bits
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '_')
+            .map(|c| match c {
+                '0' => Ok(false),
+                '1' => Ok(true),
+                _ => Err(EvaluatorError::new(format!("'{}' is not a bit, use 0 and 1.", c))),
+            })
+            .collect::<Result<Vec<bool>, _>>()?;
+        Self::new(variables, table)
+    }
+
+    /// a table given by the rows where it is true, like `Σm(1, 3, 7)`, `m(1, 3, 7)` or `1, 3, 7`.
+    pub fn from_minterms(variables: Vec<String>, minterms: &str) -> Result<Self, EvaluatorError> {
+        Self::from_terms(variables, &parse_indices(minterms, &["Σm", "∑m", "m"])?, true)
+    }
+
+    /// a table given by the rows where it is false, like `ΠM(0, 2)` or `M(0, 2)`.
+    pub fn from_maxterms(variables: Vec<String>, maxterms: &str) -> Result<Self, EvaluatorError> {
+        Self::from_terms(variables, &parse_indices(maxterms, &["ΠM", "∏M", "M"])?, false)
+    }
+
+    /// a table written as a bit vector, minterms or maxterms, told apart by the notation.
+    pub fn from_notation(variables: Vec<String>, text: &str) -> Result<Self, EvaluatorError> {
+        let text = text.trim();
+        if text.chars().all(|c| matches!(c, '0' | '1' | '_') || c.is_whitespace()) {
+            Self::from_bits(variables, text)
+        } else if ["ΠM", "∏M", "M"].iter().any(|p| text.starts_with(p)) {
+            Self::from_maxterms(variables, text)
+        } else {
+            Self::from_minterms(variables, text)
+        }
+    }
+
+    /// the rows `indices` have the output `value`, the others the opposite.
+    fn from_terms(variables: Vec<String>, indices: &[u64], value: bool) -> Result<Self, EvaluatorError> {
+        let rows = 1u64.checked_shl(variables.len() as u32).unwrap_or(0);
+        let mut table = vec![!value; rows as usize];
+        for index in indices {
+            if *index >= rows {
+                return Err(EvaluatorError::new(format!(
+                    "{} {} is out of range, {} variables have {} rows.",
+                    if value { "Minterm" } else { "Maxterm" },
+                    index,
+                    variables.len(),
+                    rows
+                )));
+            }
+            table[*index as usize] = value;
+        }
+        Self::new(variables, table)
+    }
+
+    /// the table of `evaluate_all`. The inputs are the columns before the first one that is not a
+    /// variable name and the output is the last column, so named columns must be left out.
+    pub fn from_result(result: &EvaluatorResult) -> Result<Self, EvaluatorError> {
+        let header: Vec<String> = result.result.first().map(|row| row.keys().cloned().collect()).unwrap_or_default();
+        let rows = result.result.iter().map(|row| row.values().copied().collect()).collect();
+        Self::from_columns(header, rows)
+    }
+
+    /// a table saved by `save_to_csv`, with any of the value styles but custom ones. The columns
+    /// are read as in `from_result`.
+    #[cfg(feature = "csv")]
+    pub fn from_csv(text: &str) -> Result<Self, EvaluatorError> {
+        let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+        let header = lines.next().map(csv_fields).unwrap_or_default();
+        let mut rows = Vec::new();
+        for (i, line) in lines.enumerate() {
+            let row = csv_fields(line)
+                .iter()
+                .map(|cell| {
+                    parse_value(cell).ok_or_else(|| {
+                        EvaluatorError::new(format!("Unknown value '{}' in row {}, use true/false, T/F, 1/0 or ⊤/⊥.", cell, i + 1))
+                    })
+                })
+                .collect::<Result<Vec<bool>, _>>()?;
+            if row.len() != header.len() {
+                return Err(EvaluatorError::new(format!(
+                    "Row {} has {} values for {} columns.",
+                    i + 1,
+                    row.len(),
+                    header.len()
+                )));
+            }
+            rows.push(row);
+        }
+        Self::from_columns(header, rows)
+    }
+
+    #[cfg(feature = "csv")]
+    pub fn from_csv_file(file_name: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self::from_csv(&std::fs::read_to_string(file_name)?)?)
+    }
+
+    /// the table of an expression over its variables in order of appearance.
+    pub fn from_evaluator(evaluator: &Evaluator) -> Result<Self, EvaluatorError> {
+        Self::new(evaluator.variables(), evaluator.truth_vector()?)
+    }
+
+    fn from_columns(header: Vec<String>, rows: Vec<Vec<bool>>) -> Result<Self, EvaluatorError> {
+        if header.is_empty() {
+            return Err(EvaluatorError::new("The table has no columns."));
+        }
+        let n = input_count(&header);
+        if n >= 64 {
+            return Err(EvaluatorError::new("The table has too many variables."));
+        }
+        let mut table: Vec<Option<(usize, bool)>> = vec![None; 1 << n];
+        for (i, row) in rows.iter().enumerate() {
+            let index = row[..n].iter().fold(0usize, |index, v| index << 1 | usize::from(*v));
+            let value = row[row.len() - 1];
+            match table[index] {
+                Some((first, other)) if other != value => {
+                    return Err(EvaluatorError::new(format!(
+                        "Rows {} and {} have the same inputs but different outputs.",
+                        first + 1,
+                        i + 1
+                    )));
+                }
+                Some(_) => {}
+                None => table[index] = Some((i, value)),
+            }
+        }
+        let variables: Vec<String> = header[..n].to_vec();
+        let table = table
+            .iter()
+            .enumerate()
+            .map(|(index, row)| {
+                row.map(|r| r.1).ok_or_else(|| {
+                    let values: Vec<String> = assignment(&variables, index as u64)
+                        .iter()
+                        .map(|(k, v)| format!("{} = {}", k, v))
+                        .collect();
+                    EvaluatorError::new(format!("The table has no row for {}.", values.join(", ")))
+                })
+            })
+            .collect::<Result<Vec<bool>, _>>()?;
+        Self::new(variables, table)
+    }
+
+    pub fn variables(&self) -> &[String] {
+        &self.variables
+    }
+
+    pub fn table(&self) -> &[bool] {
+        &self.table
+    }
+
+    /// the indices of the rows where the output is true.
+    pub fn minterms(&self) -> Vec<u64> {
+        (0..self.table.len() as u64).filter(|i| self.table[*i as usize]).collect()
+    }
+
+    /// the indices of the rows where the output is false.
+    pub fn maxterms(&self) -> Vec<u64> {
+        (0..self.table.len() as u64).filter(|i| !self.table[*i as usize]).collect()
+    }
+
+    /// the `or` of a full product of literals for every minterm.
+    pub fn canonical_dnf(&self) -> Tokens {
+        let terms: Vec<Implicant> = self.minterms().into_iter().map(|value| Implicant { value, mask: 0 }).collect();
+        to_tokens(&self.variables, &terms, false)
+    }
+
+    /// the `and` of a full sum of literals for every maxterm.
+    pub fn canonical_cnf(&self) -> Tokens {
+        let terms: Vec<Implicant> = self.maxterms().into_iter().map(|value| Implicant { value, mask: 0 }).collect();
+        to_tokens(&self.variables, &terms, true)
+    }
+
+    /// a sum of products with as few terms and literals as possible.
+    pub fn minimized(&self) -> Tokens {
+        to_tokens(&self.variables, &minimal_cover(&self.minterms()), false)
+    }
+
+    /// a product of sums with as few clauses and literals as possible.
+    pub fn minimized_cnf(&self) -> Tokens {
+        to_tokens(&self.variables, &minimal_cover(&self.maxterms()), true)
+    }
+}
+
+/// the number of leading columns of a table that are inputs. A table of a single variable has no
+/// other column, otherwise the last column is the output.
+pub(crate) fn input_count(header: &[String]) -> usize {
+    let n = header.iter().take_while(|name| is_identifier(name)).count();
+    if n == header.len() && n > 1 {
+        n - 1
+    } else {
+        n
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    matches!(&Tokens::from_text(name)[..], [Token::Ident(n)] if n == name)
+}
+
+/// the numbers of a list like `Σm(1, 3, 7)`, the list may be written with one of `prefixes`.
+fn parse_indices(text: &str, prefixes: &[&str]) -> Result<Vec<u64>, EvaluatorError> {
+    let text = text.trim();
+    let list = match text.find('(') {
+        Some(open) if text.ends_with(')') => {
+            let prefix = text[..open].trim();
+            if !prefix.is_empty() && !prefixes.contains(&prefix) {
+                return Err(EvaluatorError::new(format!("Unknown notation '{}', use {}.", prefix, prefixes.join(", "))));
+            }
+            &text[open + 1..text.len() - 1]
+        }
+        Some(_) => return Err(EvaluatorError::new("Missing ')' at the end of the list.")),
+        None => text,
+    };
+    list.split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse().map_err(|_| EvaluatorError::new(format!("'{}' is not a row index.", s))))
+        .collect()
+}
+
+#[cfg(feature = "csv")]
+pub(crate) fn parse_value(cell: &str) -> Option<bool> {
+    match cell.trim() {
+        "true" | "T" | "1" | "⊤" => Some(true),
+        "false" | "F" | "0" | "⊥" => Some(false),
+        _ => None,
+    }
+}
+
+/// the fields of a csv line, fields with commas are quoted and their quotes doubled.
+#[cfg(feature = "csv")]
+pub(crate) fn csv_fields(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().unwrap().push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            _ => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &str) -> Vec<String> {
+        names.split_whitespace().map(String::from).collect()
+    }
+
+    fn assert_equivalent(tokens: Tokens, evaluator: &Evaluator) {
+        let synthesized = Evaluator::new(tokens.clone()).unwrap();
+        assert!(synthesized.is_equivalent(evaluator).unwrap(), "{} is not equivalent", tokens);
+    }
+
+    #[test]
+    fn bits_and_minterms() {
+        let xor = Specification::from_bits(names("p q"), "0110").unwrap();
+        assert_eq!(xor.canonical_dnf().to_string(), "(¬p ∧ q) ∨ (p ∧ ¬q)");
+        assert_eq!(xor.canonical_cnf().to_string(), "(p ∨ q) ∧ (¬p ∨ ¬q)");
+        let spec = Specification::from_minterms(names("a b c"), "Σm(1, 3, 7)").unwrap();
+        assert_eq!(spec.minterms(), [1, 3, 7]);
+        assert_eq!(spec.minimized().to_string(), "(¬a ∧ c) ∨ (b ∧ c)");
+        assert_eq!(Specification::from_minterms(names("a b c"), "1,3,7").unwrap(), spec);
+        assert_eq!(Specification::from_minterms(names("p"), "Σm()").unwrap().minimized().to_string(), "0");
+        assert_eq!(Specification::from_bits(vec![], "1").unwrap().canonical_dnf().to_string(), "1");
+        assert!(Specification::from_minterms(names("a b"), "Σm(4)").is_err());
+        assert!(Specification::from_minterms(names("a b"), "ΠM(1)").is_err());
+        assert_eq!(Specification::from_notation(names("p q"), "ΠM(0, 3)").unwrap(), xor);
+        assert_eq!(Specification::from_notation(names("p q"), "Σm(1, 2)").unwrap(), xor);
+        assert!(Specification::from_bits(names("a b"), "011").is_err());
+        assert!(Specification::from_bits(names("a a"), "0110").is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let expressions = ["p -> q", "maj(a, b, c) xor d", "(p nand q) <-> not r", "ite(x, y, z) and w", "p or not p", "q"];
+        for s in expressions {
+            let evaluator = Evaluator::new(Tokens::from_text(s)).unwrap();
+            let spec = Specification::from_result(&evaluator.evaluate_all().unwrap()).unwrap();
+            assert_eq!(spec.variables(), evaluator.variables());
+            assert_equivalent(spec.canonical_dnf(), &evaluator);
+            assert_equivalent(spec.canonical_cnf(), &evaluator);
+            assert_equivalent(spec.minimized(), &evaluator);
+            assert_equivalent(spec.minimized_cnf(), &evaluator);
+            assert_eq!(Specification::from_evaluator(&evaluator).unwrap(), spec);
+        }
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn csv() {
+        use crate::render::{RenderOptions, ValueStyle};
+        let evaluator = Evaluator::new(Tokens::from_text("exactly(1, p, q, r)")).unwrap();
+        let result = evaluator.evaluate_all().unwrap();
+        for values in [ValueStyle::Words, ValueStyle::Letters, ValueStyle::Digits] {
+            let csv = result.to_csv(&RenderOptions::default().with_values(values));
+            let spec = Specification::from_csv(&csv).unwrap();
+            assert_eq!(spec.variables(), ["p", "q", "r"]);
+            assert_equivalent(spec.minimized(), &evaluator);
+        }
+        let error = Specification::from_csv("p,q,(p ∧ q)\ntrue,true,true\n").unwrap_err();
+        assert_eq!(error.message(), "The table has no row for p = false, q = false.");
+        assert!(Specification::from_csv("p,¬p\ntrue,false\ntrue,true\nfalse,true\n").is_err());
+    }
+}
@@ -0,0 +1,310 @@
+use std::fmt;
+use std::str::FromStr;
+
+use indexmap::IndexMap;
+use prettytable::Table;
+
+use crate::evaluator::{apply, Evaluator, EvaluatorError};
+use crate::render::{RenderOptions, ValueStyle};
+use crate::tokenizer::{Function, Token};
+
+/// A truth value of a three-valued logic, ordered from false to true.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Ternary {
+    False,
+    Unknown,
+    True,
+}
+
+/// The meaning of the connectives when a value is unknown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum Logic {
+    /// a result is unknown only when the unknown values could change it.
+    #[default]
+    StrongKleene,
+    /// Bochvar's logic: a single unknown value makes the result unknown.
+    WeakKleene,
+    /// strong Kleene with `→` and `↔` on the values 0, ½ and 1, so that `u → u` is true.
+    Lukasiewicz,
+}
+
+impl From<bool> for Ternary {
+    fn from(value: bool) -> Self {
+        if value { Ternary::True } else { Ternary::False }
+    }
+}
+
+impl Ternary {
+    pub const ALL: [Ternary; 3] = [Ternary::True, Ternary::Unknown, Ternary::False];
+
+    /// the value counted in halves: 0, 1 or 2.
+    fn halves(self) -> u8 {
+        self as u8
+    }
+
+    fn from_halves(halves: u8) -> Self {
+        match halves {
+            0 => Ternary::False,
+            1 => Ternary::Unknown,
+            _ => Ternary::True,
+        }
+    }
+
+    /// `None` when unknown.
+    pub fn known(self) -> Option<bool> {
+        match self {
+            Ternary::False => Some(false),
+            Ternary::Unknown => None,
+            Ternary::True => Some(true),
+        }
+    }
+}
+
+impl std::ops::Not for Ternary {
+    type Output = Ternary;
+    fn not(self) -> Ternary {
+        Ternary::from_halves(2 - self.halves())
+    }
+}
+
+impl fmt::Display for Ternary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Ternary::False => write!(f, "false"),
+            Ternary::Unknown => write!(f, "unknown"),
+            Ternary::True => write!(f, "true"),
+        }
+    }
+}
+
+impl fmt::Display for Logic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Logic::StrongKleene => write!(f, "strong Kleene"),
+            Logic::WeakKleene => write!(f, "weak Kleene"),
+            Logic::Lukasiewicz => write!(f, "Łukasiewicz"),
+        }
+    }
+}
+
+impl FromStr for Logic {
+    type Err = String;
+    /// `kleene`, `bochvar` or `lukasiewicz`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "kleene" | "strong-kleene" => Ok(Logic::StrongKleene),
+            "bochvar" | "weak-kleene" => Ok(Logic::WeakKleene),
+            "lukasiewicz" | "łukasiewicz" => Ok(Logic::Lukasiewicz),
+            _ => Err(format!("unknown logic '{}'", s)),
+        }
+    }
+}
+
+impl Logic {
+    /// the value of an operator or a function, `count` is the count of `exactly`, `atmost` and
+    /// `atleast`.
+    pub fn apply(&self, operator: &Token, count: usize, values: &[Ternary]) -> Ternary {
+        match self {
+            Logic::StrongKleene => completions(operator, count, values),
+            Logic::WeakKleene => match values.contains(&Ternary::Unknown) {
+                true => Ternary::Unknown,
+                false => completions(operator, count, values),
+            },
+            Logic::Lukasiewicz => lukasiewicz(operator, count, values),
+        }
+    }
+}
+
+/// the value shared by every way of making the unknown values true or false, unknown when they
+/// disagree. On `¬`, `∧` and `∨` these are the tables of strong Kleene logic.
+fn completions(operator: &Token, count: usize, values: &[Ternary]) -> Ternary {
+    let unknown: Vec<usize> = (0..values.len()).filter(|i| values[*i] == Ternary::Unknown).collect();
+    let mut result = None;
+    for fill in 0..1u64 << unknown.len() {
+        let mut operands: Vec<Token> = values.iter().map(|v| Token::from(v.known().unwrap_or_default())).collect();
+        for (bit, i) in unknown.iter().enumerate() {
+            operands[*i] = Token::from(fill >> bit & 1 == 1);
+        }
+        if matches!(operator, Token::Function(f) if f.takes_count()) {
+            operands.insert(0, Token::Number(count));
+        }
+        let value = apply(operator, &operands);
+        match result {
+            None => result = Some(value),
+            Some(other) if other != value => return Ternary::Unknown,
+            Some(_) => {}
+        }
+    }
+    Ternary::from(result.unwrap_or_default())
+}
+
+fn lukasiewicz(operator: &Token, count: usize, values: &[Ternary]) -> Ternary {
+    let h: Vec<u8> = values.iter().map(|v| v.halves()).collect();
+    let implies = |a: u8, b: u8| (2 + b).saturating_sub(a).min(2);
+    let halves = match (operator, h.as_slice()) {
+        (Token::Implication(_), [a, b]) => implies(*a, *b),
+        (Token::Converse(_), [a, b]) => implies(*b, *a),
+        (Token::NonImplication(_), [a, b]) => 2 - implies(*a, *b),
+        (Token::Biconditional(_) | Token::Equals(_), [a, b]) => 2 - a.abs_diff(*b),
+        (Token::XOr(_) | Token::NotEquals(_), [a, b]) => a.abs_diff(*b),
+        (Token::Function(Function::Xor), _) => h.iter().copied().reduce(|a, b| a.abs_diff(b)).unwrap_or(0),
+        _ => return completions(operator, count, values),
+    };
+    Ternary::from_halves(halves)
+}
+
+/// The truth table of a three-valued evaluation, `3^n` rows for `n` variables.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ThreeValuedResult {
+    pub logic: Logic,
+    pub result: Vec<IndexMap<String, Ternary>>,
+}
+
+/// how an unknown value is written next to the style of true and false.
+fn unknown(style: &ValueStyle) -> &'static str {
+    match style {
+        ValueStyle::Words => "unknown",
+        ValueStyle::Letters => "U",
+        ValueStyle::Digits => "½",
+        ValueStyle::Symbols | ValueStyle::Custom { .. } => "?",
+    }
+}
+
+impl ThreeValuedResult {
+    /// the values of the last column, which holds the value of the whole expression.
+    pub fn final_column(&self) -> Vec<Ternary> {
+        self.result.iter().filter_map(|row| row.last().map(|x| *x.1)).collect()
+    }
+
+    pub fn print_with_options(&self, options: &RenderOptions) {
+        self.get_table(options).printstd();
+    }
+
+    pub fn to_plain_text(&self, options: &RenderOptions) -> String {
+        self.get_table(options).to_string()
+    }
+
+    #[cfg(feature = "csv")]
+    pub fn to_csv(&self, options: &RenderOptions) -> String {
+        let mut out = Vec::new();
+        self.get_table(options).to_csv(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn get_table(&self, options: &RenderOptions) -> Table {
+        let mut table = Table::new();
+        if let Some(header) = self.result.first() {
+            table.add_row(header.iter().map(|x| options.header(x.0)).collect());
+        }
+        for row in self.result.iter() {
+            let values = row
+                .iter()
+                .map(|x| match x.1.known() {
+                    Some(value) => options.value(value),
+                    None => unknown(&options.values),
+                })
+                .collect();
+            table.add_row(values);
+        }
+        table
+    }
+}
+
+impl Evaluator {
+    /// the value of every column for values that may be unknown.
+    pub fn evaluate_three_valued(
+        &self,
+        logic: Logic,
+        values: &IndexMap<String, Ternary>,
+    ) -> Result<IndexMap<String, Ternary>, EvaluatorError> {
+        self.evaluate_with(values, Ternary::from, |operator, count, values| logic.apply(operator, count, values))
+    }
+
+    /// the truth table with every variable true, unknown or false. As in `evaluate_all`, the first
+    /// variable changes slowest and the rows start with every variable true.
+    pub fn evaluate_all_three_valued(&self, logic: Logic) -> Result<ThreeValuedResult, EvaluatorError> {
+        let variables = self.variables();
+        let rows = 3u64.checked_pow(variables.len() as u32).filter(|r| *r <= 1 << 32).ok_or_else(|| {
+            EvaluatorError::new(format!("{} variables are too many for a table of 3^n rows.", variables.len()))
+        })?;
+        let mut result = Vec::new();
+        for i in 0..rows {
+            let mut row: IndexMap<String, Ternary> = IndexMap::new();
+            let mut rest = i;
+            for v in variables.iter().rev() {
+                row.insert(v.clone(), Ternary::ALL[(rest % 3) as usize]);
+                rest /= 3;
+            }
+            row.reverse();
+            let mut evaluated = self.evaluate_three_valued(logic, &row)?;
+            for (k, v) in row.iter().rev() {
+                evaluated.insert_before(0, k.clone(), *v);
+            }
+            result.push(evaluated);
+        }
+        Ok(ThreeValuedResult { logic, result })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::Tokens;
+    use Ternary::*;
+
+    fn value(s: &str, logic: Logic, values: &[(&str, Ternary)]) -> Ternary {
+        let evaluator = Evaluator::new(Tokens::from_text(s)).unwrap();
+        let values = values.iter().map(|(k, v)| (k.to_string(), *v)).collect();
+        *evaluator.evaluate_three_valued(logic, &values).unwrap().last().unwrap().1
+    }
+
+    #[test]
+    fn connectives() {
+        use Logic::*;
+        let u = [("p", Unknown), ("q", False)];
+        assert_eq!(value("p and q", StrongKleene, &u), False);
+        assert_eq!(value("p and q", WeakKleene, &u), Unknown);
+        assert_eq!(value("p or not q", StrongKleene, &u), True);
+        assert_eq!(value("p or not q", WeakKleene, &u), Unknown);
+        assert_eq!(value("p -> p", StrongKleene, &u), Unknown);
+        assert_eq!(value("p -> p", Lukasiewicz, &u), True);
+        assert_eq!(value("p <-> p", Lukasiewicz, &u), True);
+        assert_eq!(value("p xor p", Lukasiewicz, &u), False);
+        assert_eq!(value("q -> p", Lukasiewicz, &u), True);
+        assert_eq!(value("p ↛ q", Lukasiewicz, &u), Unknown);
+    }
+
+    #[test]
+    fn functions() {
+        use Logic::*;
+        let u = [("a", Unknown), ("b", True), ("c", True)];
+        assert_eq!(value("maj(a, b, c)", StrongKleene, &u), True);
+        assert_eq!(value("maj(a, b, c)", WeakKleene, &u), Unknown);
+        assert_eq!(value("exactly(2, a, b, c)", StrongKleene, &u), Unknown);
+        assert_eq!(value("atleast(1, a, b)", StrongKleene, &u), True);
+        assert_eq!(value("ite(a, b, c)", StrongKleene, &u), True);
+        assert_eq!(value("ite(a, b, c)", Lukasiewicz, &u), True);
+        assert_eq!(value("ite(b, a, c)", Lukasiewicz, &u), Unknown);
+    }
+
+    #[test]
+    fn tables() {
+        let evaluator = Evaluator::new(Tokens::from_text("p and q")).unwrap();
+        let table = evaluator.evaluate_all_three_valued(Logic::StrongKleene).unwrap();
+        assert_eq!(table.result.len(), 9);
+        assert_eq!(table.result[0]["p"], True);
+        assert_eq!(table.result[1]["q"], Unknown);
+        assert_eq!(table.final_column(), [True, Unknown, False, Unknown, Unknown, False, False, False, False]);
+        //known values agree with the two-valued table.
+        let classical = evaluator.evaluate_all().unwrap().final_column();
+        let known: Vec<bool> = table.final_column().iter().zip(&table.result).filter(|(_, row)| !row.values().any(|v| *v == Unknown)).map(|(v, _)| v.known().unwrap()).collect();
+        assert_eq!(known, classical);
+        let text = table.to_plain_text(&RenderOptions::default());
+        assert!(text.contains("unknown"));
+        assert_eq!("bochvar".parse::<Logic>().unwrap(), Logic::WeakKleene);
+    }
+}
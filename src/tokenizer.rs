@@ -1,14 +1,18 @@
 use std::{
     fmt::{self, Display, Write},
-    ops::Deref,
+    ops::{Deref, DerefMut, Range},
 };
 
 use logos::Logos;
 
 #[derive(Logos, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[logos(skip r"\s+")]
 pub enum Token {
-    #[regex("[a-zA-Z]+", |lex| lex.slice().chars().next().unwrap())]
-    Ident(char),
+    #[regex("[a-zA-Z_][a-zA-Z0-9_]*", |lex| lex.slice().to_string())]
+    Ident(String),
+    #[token(":=")]
+    Define,
     #[token("(")]
     OpenParen,
     #[token(")")]
@@ -27,12 +31,26 @@ pub enum Token {
     #[token("∼", |_| '¬')]
     #[token("~", |_| '¬')]
     Not(char),
+    /// the modal operators, evaluated at the worlds of a Kripke model (see `modal`).
+    #[token("box", |_| '□')]
+    #[token("□", |_| '□')]
+    Necessarily(char),
+    #[token("dia", |_| '◇')]
+    #[token("◇", |_| '◇')]
+    #[token("◊", |_| '◇')]
+    Possibly(char),
     #[token("->", |_| '→')]
     #[token("=>", |_| '→')]
     #[token("⇒", |_| '→')]
     #[token("→", |_| '→')]
     #[token("⊃", |_| '→')]
     Implication(char),
+    #[token("<-", |_| '←')]
+    #[token("←", |_| '←')]
+    Converse(char),
+    #[token("-/>", |_| '↛')]
+    #[token("↛", |_| '↛')]
+    NonImplication(char),
     #[token("<->", |_| '↔')]
     #[token("<=>", |_| '↔')]
     #[token("⇔", |_| '↔')]
@@ -45,11 +63,18 @@ pub enum Token {
     #[token("&&", |_| '∧')]
     #[token("∧", |_| '∧')]
     And(char),
+    #[token("nand", |_| '↑')]
+    #[token("↑", |_| '↑')]
+    Nand(char),
     #[token("or", |_| '∨')]
     #[token("|", |_| '∨')]
     #[token("||", |_| '∨')]
     #[token("∨", |_| '∨')]
     Or(char),
+    #[token("nor", |_| '↓')]
+    #[token("↓", |_| '↓')]
+    Nor(char),
+    //`xor(a, b, c)` is read as `Function::Xor` by `Evaluator::new`.
     #[token("xor", |_| '⊕')]
     #[token("⊕", |_| '⊕')]
     XOr(char),
@@ -69,14 +94,106 @@ pub enum Token {
     #[token("true")]
     #[token("True")]
     True,
+    #[token("ite", |_| Function::Ite)]
+    #[token("maj", |_| Function::Maj)]
+    #[token("exactly", |_| Function::Exactly)]
+    #[token("atmost", |_| Function::AtMost)]
+    #[token("atleast", |_| Function::AtLeast)]
+    Function(Function),
+    #[token(",")]
+    Comma,
+    /// the count of `exactly`, `atmost` and `atleast`. `0` and `1` are read as `False` and `True`.
+    #[regex("[0-9]+", |lex| lex.slice().parse::<usize>().ok(), priority = 1)]
+    Number(usize),
+    /// an operator of linear temporal logic. The letters are names in other expressions, only
+    /// `ltl` reads them as operators.
+    Temporal(Temporal),
 }
 
-impl Into<bool> for Token {
-    fn into(self) -> bool{
-        if let Token::True = self {
-            return  true;
+/// Functions written with their arguments in parentheses, like `maj(a, b, c)`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Function {
+    /// `ite(c, a, b)` is `a` when `c` is true, otherwise `b`.
+    Ite,
+    /// true when more than half of the arguments are true.
+    Maj,
+    /// `exactly(k, ...)` is true when exactly `k` of the other arguments are true.
+    Exactly,
+    /// `atmost(k, ...)` is true when at most `k` of the other arguments are true.
+    AtMost,
+    /// `atleast(k, ...)` is true when at least `k` of the other arguments are true.
+    AtLeast,
+    /// true when an odd number of the arguments are true.
+    Xor,
+}
+
+impl Function {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Function::Ite => "ite",
+            Function::Maj => "maj",
+            Function::Exactly => "exactly",
+            Function::AtMost => "atmost",
+            Function::AtLeast => "atleast",
+            Function::Xor => "xor",
+        }
+    }
+
+    /// whether the first argument is the count `k` rather than a formula.
+    pub fn takes_count(&self) -> bool {
+        matches!(self, Function::Exactly | Function::AtMost | Function::AtLeast)
+    }
+
+    /// the least and the most number of arguments, counting `k`.
+    pub fn arity(&self) -> (usize, Option<usize>) {
+        match self {
+            Function::Ite => (3, Some(3)),
+            Function::Maj | Function::Xor => (1, None),
+            Function::Exactly | Function::AtMost | Function::AtLeast => (2, None),
+        }
+    }
+}
+
+/// The operators of linear temporal logic over finite traces (see `ltl`).
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Temporal {
+    /// `X a` is true when there is a next step and `a` is true at it.
+    Next,
+    /// `F a` is true when `a` is true now or at a later step.
+    Eventually,
+    /// `G a` is true when `a` is true now and at every later step.
+    Always,
+    /// `a U b` is true when `b` is true at some step and `a` at every step before it.
+    Until,
+    /// `a R b` is true when `b` is true up to and including the first step where `a` is, or to
+    /// the end of the trace.
+    Release,
+}
+
+impl Temporal {
+    pub fn symbol(&self) -> char {
+        match self {
+            Temporal::Next => 'X',
+            Temporal::Eventually => 'F',
+            Temporal::Always => 'G',
+            Temporal::Until => 'U',
+            Temporal::Release => 'R',
         }
-        false
+    }
+
+    /// whether the operator applies to the operand after it, like `¬`.
+    pub fn is_unary(&self) -> bool {
+        matches!(self, Temporal::Next | Temporal::Eventually | Temporal::Always)
+    }
+}
+
+impl From<Token> for bool {
+    fn from(value: Token) -> Self {
+        value == Token::True
     }
 }
 
@@ -89,21 +206,84 @@ impl From<bool> for Token {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct Tokens {
     tokens: Vec<Token>,
+    /// byte ranges of the tokens in the source text, empty when the tokens were not lexed.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    spans: Vec<Range<usize>>,
+    /// byte ranges of the text that is not a token.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    unknown: Vec<Range<usize>>,
+}
+
+impl From<Vec<Token>> for Tokens {
+    fn from(tokens: Vec<Token>) -> Self {
+        Self { tokens, spans: Vec::new(), unknown: Vec::new() }
+    }
 }
 
 impl Tokens {
     pub fn from_text(text: &str) -> Self {
-        Self {
-            tokens: Token::lexer(text).filter_map(|result| result.ok()).collect(),
+        let mut tokens = Vec::new();
+        let mut spans = Vec::new();
+        let mut unknown = Vec::new();
+        for (result, span) in Token::lexer(text).spanned() {
+            match result {
+                Ok(token) => {
+                    tokens.push(token);
+                    spans.push(span);
+                }
+                Err(_) => unknown.push(span),
+            }
         }
+        Self { tokens, spans, unknown }
+    }
+
+    /// tokens with their byte ranges in a source text. `spans` must be as long as `tokens`.
+    pub fn with_spans(tokens: Vec<Token>, spans: Vec<Range<usize>>) -> Self {
+        debug_assert_eq!(tokens.len(), spans.len());
+        Self { tokens, spans, unknown: Vec::new() }
+    }
+
+    /// the byte range of the token at `index` in the source text, if known.
+    pub fn span(&self, index: usize) -> Option<Range<usize>> {
+        self.spans.get(index).cloned()
+    }
+
+    pub fn spans(&self) -> &[Range<usize>] {
+        &self.spans
+    }
+
+    /// the byte ranges of the text that could not be read as tokens. They are left out of the tokens.
+    pub fn unknown(&self) -> &[Range<usize>] {
+        &self.unknown
     }
     
+    /// the tokens in `range`, with their spans.
+    pub fn slice(&self, range: Range<usize>) -> Self {
+        let spans = if self.spans.is_empty() { Vec::new() } else { self.spans[range.clone()].to_vec() };
+        Self { tokens: self.tokens[range].to_vec(), spans, unknown: Vec::new() }
+    }
+
+    /// moves the spans by `offset` bytes, for text that was lexed out of a longer text.
+    pub fn shifted(mut self, offset: usize) -> Self {
+        for span in self.spans.iter_mut().chain(self.unknown.iter_mut()) {
+            *span = span.start + offset..span.end + offset;
+        }
+        self
+    }
+
     pub fn enclose(&mut self, left: Token,right: Token) {
         self.tokens.insert(0, left);
         self.tokens.push(right);
+        if !self.spans.is_empty() {
+            let end = self.spans.last().map(|s| s.end).unwrap_or_default();
+            self.spans.insert(0, 0..0);
+            self.spans.push(end..end);
+        }
     }
 }
 
@@ -114,27 +294,68 @@ impl Deref for Tokens {
     }
 }
 
+impl DerefMut for Tokens {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.tokens
+    }
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(name) => f.write_str(name),
+            Token::Define => f.write_str(":="),
+            Token::OpenParen => f.write_char('('),
+            Token::CloseParen => f.write_char(')'),
+            Token::OpenBracket => f.write_char('['),
+            Token::CloseBracket => f.write_char(']'),
+            Token::OpenCurlyBrace => f.write_char('{'),
+            Token::CloseCurlyBrace => f.write_char('}'),
+            Token::Not(symb) |
+            Token::Necessarily(symb) |
+            Token::Possibly(symb) |
+            Token::Implication(symb) |
+            Token::Converse(symb) |
+            Token::NonImplication(symb) |
+            Token::Biconditional(symb) |
+            Token::And(symb) |
+            Token::Nand(symb) |
+            Token::Or(symb) |
+            Token::Nor(symb) |
+            Token::XOr(symb) | 
+            Token::Equals(symb) | 
+            Token::NotEquals(symb) => f.write_char(*symb),
+            Token::False => f.write_char('0'),
+            Token::True => f.write_char('1'),
+            Token::Function(function) => f.write_str(function.name()),
+            Token::Comma => f.write_char(','),
+            Token::Number(n) => write!(f, "{}", n),
+            Token::Temporal(temporal) => f.write_char(temporal.symbol()),
+        }
+    }
+}
+
 impl Display for Tokens {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         for token in self.tokens.iter() {
             match token {
-                Token::Ident(c) => f.write_char(*c),
-                Token::OpenParen => f.write_char('('),
-                Token::CloseParen => f.write_char(')'),
-                Token::OpenBracket => f.write_char('['),
-                Token::CloseBracket => f.write_char(']'),
-                Token::OpenCurlyBrace => f.write_char('{'),
-                Token::CloseCurlyBrace => f.write_char('}'),
-                Token::Not(symb) |
-                Token::Implication(symb) |
-                Token::Biconditional(symb) |
-                Token::And(symb) |
-                Token::Or(symb) |
-                Token::XOr(symb) | 
-                Token::Equals(symb) | 
-                Token::NotEquals(symb) => f.write_str(&format!(" {} ",symb.to_string())),
-                Token::False => f.write_char('0'),
-                Token::True => f.write_char('1'),
+                Token::Implication(_) |
+                Token::Converse(_) |
+                Token::NonImplication(_) |
+                Token::Biconditional(_) |
+                Token::And(_) |
+                Token::Nand(_) |
+                Token::Or(_) |
+                Token::Nor(_) |
+                Token::XOr(_) | 
+                Token::Equals(_) | 
+                Token::NotEquals(_) |
+                Token::Temporal(Temporal::Until | Temporal::Release) |
+                Token::Define => write!(f, " {} ", token),
+                //`Gp` would read as a name.
+                Token::Temporal(_) => write!(f, "{} ", token),
+                Token::Comma => write!(f, "{} ", token),
+                _ => write!(f, "{}", token),
             }?
         }
         Ok(())
@@ -150,7 +371,7 @@ mod tests {
         let s = "a -> b";
         assert_eq!(
             Tokens::from_text(s).tokens,
-            vec![Token::Ident('a'), Token::Implication('→'), Token::Ident('b')]
+            vec![Token::Ident("a".into()), Token::Implication('→'), Token::Ident("b".into())]
         );
     }
 
@@ -159,7 +380,7 @@ mod tests {
         let s = "a == b";
         assert_eq!(
             Tokens::from_text(s).tokens,
-            vec![Token::Ident('a'), Token::Equals('≡'), Token::Ident('b')]
+            vec![Token::Ident("a".into()), Token::Equals('≡'), Token::Ident("b".into())]
         );
     }
 
@@ -168,7 +389,7 @@ mod tests {
         let s = "a != b";
         assert_eq!(
             Tokens::from_text(s).tokens,
-            vec![Token::Ident('a'), Token::NotEquals('≠'), Token::Ident('b')]
+            vec![Token::Ident("a".into()), Token::NotEquals('≠'), Token::Ident("b".into())]
         );
     }
 
@@ -178,7 +399,7 @@ mod tests {
         let tokens = Tokens::from_text(s).tokens;
         assert_eq!(
             tokens,
-            vec![Token::OpenParen,Token::Not('¬'), Token::Ident('a'),Token::CloseParen]
+            vec![Token::OpenParen,Token::Not('¬'), Token::Ident("a".into()),Token::CloseParen]
         );
     }
 
@@ -190,11 +411,72 @@ mod tests {
             let tokens = Tokens::from_text(&expr).tokens;
             assert_eq!(
                 tokens,
-                vec![Token::Ident('a'),Token::And('∧'), Token::Ident('b')]
+                vec![Token::Ident("a".into()),Token::And('∧'), Token::Ident("b".into())]
             );
         }
     }
 
+    #[test]
+    fn connectives() {
+        let tokens = Tokens::from_text("a nand b ↑ c nor d ↓ e <- f ← g -/> h ↛ i <-> j");
+        let operators: Vec<String> = tokens.iter().skip(1).step_by(2).map(|t| t.to_string()).collect();
+        assert_eq!(operators, ["↑", "↑", "↓", "↓", "←", "←", "↛", "↛", "↔"]);
+        assert_eq!(tokens.len(), 19);
+    }
+
+    #[test]
+    fn functions() {
+        let tokens = Tokens::from_text("exactly(2, a, b) and ite(c,a,b) or items");
+        assert_eq!(
+            tokens[..6],
+            [
+                Token::Function(Function::Exactly),
+                Token::OpenParen,
+                Token::Number(2),
+                Token::Comma,
+                Token::Ident("a".into()),
+                Token::Comma
+            ]
+        );
+        assert_eq!(tokens.last(), Some(&Token::Ident("items".into())));
+        assert_eq!(tokens.to_string(), "exactly(2, a, b) ∧ ite(c, a, b) ∨ items");
+    }
+
+    #[test]
+    fn modalities() {
+        let tokens = Tokens::from_text("box p -> □◊p and dia q");
+        assert_eq!(tokens[0], Token::Necessarily('□'));
+        assert_eq!(tokens[4], Token::Possibly('◇'));
+        assert_eq!(tokens.to_string(), "□p → □◇p ∧ ◇q");
+    }
+
+    #[test]
+    fn names() {
+        let tokens = Tokens::from_text("safe := door_closed and not alarm2 or android");
+        assert_eq!(
+            tokens.tokens,
+            vec![
+                Token::Ident("safe".into()),
+                Token::Define,
+                Token::Ident("door_closed".into()),
+                Token::And('∧'),
+                Token::Not('¬'),
+                Token::Ident("alarm2".into()),
+                Token::Or('∨'),
+                Token::Ident("android".into())
+            ]
+        );
+        assert_eq!(tokens.to_string(), "safe := door_closed ∧ ¬alarm2 ∨ android");
+    }
+
+    #[test]
+    fn spans() {
+        let tokens = Tokens::from_text("ab ∧ $c");
+        assert_eq!(tokens.tokens, vec![Token::Ident("ab".into()), Token::And('∧'), Token::Ident("c".into())]);
+        assert_eq!(tokens.spans(), &[0..2, 3..6, 8..9]);
+        assert_eq!(tokens.unknown().to_vec(), vec![7..8]);
+    }
+
     #[test]
     fn or() {
         let symbols = ["or", "|", "||", "∨"];
@@ -203,7 +485,7 @@ mod tests {
             let tokens = Tokens::from_text(&expr).tokens;
             assert_eq!(
                 tokens,
-                vec![Token::Ident('a'),Token::Or('∨'), Token::Ident('b')]
+                vec![Token::Ident("a".into()),Token::Or('∨'), Token::Ident("b".into())]
             );
         }
     }
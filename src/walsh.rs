@@ -0,0 +1,156 @@
+use crate::anf::Anf;
+use crate::completeness::BooleanFunction;
+
+/// the fast Walsh–Hadamard transform, in place, of a vector of `2^n` values.
+fn transform(values: &mut [i64]) {
+    let mut step = 1;
+    while step < values.len() {
+        for i in 0..values.len() {
+            if i & step == 0 {
+                let (a, b) = (values[i], values[i | step]);
+                values[i] = a + b;
+                values[i | step] = a - b;
+            }
+        }
+        step <<= 1;
+    }
+}
+
+impl BooleanFunction {
+    /// `W(a) = Σ (-1)^(f(x) ⊕ a·x)` over the rows `x`, where `a·x` is the parity of `a & x`.
+    pub fn walsh_spectrum(&self) -> Vec<i64> {
+        let mut values: Vec<i64> = self.table().iter().map(|v| if *v { -1 } else { 1 }).collect();
+        transform(&mut values);
+        values
+    }
+
+    /// the distance to the nearest affine function, `2^(n-1) - max |W(a)| / 2`.
+    pub fn nonlinearity(&self) -> u64 {
+        let max = self.walsh_spectrum().iter().map(|w| w.unsigned_abs()).max().unwrap_or(0);
+        (self.table().len() as u64 - max) / 2
+    }
+
+    /// true on exactly half of the rows.
+    pub fn is_balanced(&self) -> bool {
+        self.table().iter().filter(|v| **v).count() * 2 == self.table().len()
+    }
+
+    /// the largest `m` such that the output is independent of any `m` of the arguments, that is
+    /// `W(a) = 0` whenever `a` has between 1 and `m` bits set.
+    pub fn correlation_immunity(&self) -> usize {
+        let spectrum = self.walsh_spectrum();
+        (1..=self.arity())
+            .find(|m| (1..spectrum.len()).any(|a| a.count_ones() as usize == *m && spectrum[a] != 0))
+            .map(|m| m - 1)
+            .unwrap_or(self.arity())
+    }
+
+    /// the correlation immunity of a balanced function, `None` when it is not balanced.
+    pub fn resiliency(&self) -> Option<usize> {
+        self.is_balanced().then(|| self.correlation_immunity())
+    }
+
+    /// the smallest degree of a nonzero function `g` with `f ∧ g = 0` or `¬f ∧ g = 0`.
+    pub fn algebraic_immunity(&self) -> usize {
+        let complement: Vec<bool> = self.table().iter().map(|v| !v).collect();
+        (0..=self.arity())
+            .find(|d| has_annihilator(self.table(), *d) || has_annihilator(&complement, *d))
+            .unwrap_or(self.arity())
+    }
+
+    /// the degree of the algebraic normal form.
+    pub fn algebraic_degree(&self) -> usize {
+        Anf::from_function(self).degree()
+    }
+
+    /// `r(a) = Σ (-1)^(f(x) ⊕ f(x ⊕ a))` over the rows `x`.
+    pub fn autocorrelation(&self) -> Vec<i64> {
+        let mut values: Vec<i64> = self.walsh_spectrum().iter().map(|w| w * w).collect();
+        transform(&mut values);
+        let rows = self.table().len() as i64;
+        values.iter().map(|v| v / rows).collect()
+    }
+}
+
+/// whether a nonzero function of degree at most `degree` vanishes on every row where `table` is
+/// true. Its coefficients in algebraic normal form solve one linear equation per such row.
+fn has_annihilator(table: &[bool], degree: usize) -> bool {
+    let monomials: Vec<usize> = (0..table.len()).filter(|m| m.count_ones() as usize <= degree).collect();
+    let words = monomials.len().div_ceil(64);
+    let mut rows: Vec<Vec<u64>> = Vec::new();
+    for x in (0..table.len()).filter(|x| table[*x]) {
+        //g(x) is the xor of the coefficients of the monomials contained in x.
+        let mut row = vec![0u64; words];
+        for (k, m) in monomials.iter().enumerate() {
+            if m & x == *m {
+                row[k / 64] |= 1 << (k % 64);
+            }
+        }
+        rows.push(row);
+    }
+    rank(rows, monomials.len()) < monomials.len()
+}
+
+/// the rank over GF(2) of bit rows of `columns` bits.
+fn rank(mut rows: Vec<Vec<u64>>, columns: usize) -> usize {
+    let mut rank = 0;
+    for column in 0..columns {
+        let (word, bit) = (column / 64, 1u64 << (column % 64));
+        let Some(pivot) = (rank..rows.len()).find(|r| rows[*r][word] & bit != 0) else {
+            continue;
+        };
+        rows.swap(rank, pivot);
+        for r in 0..rows.len() {
+            if r != rank && rows[r][word] & bit != 0 {
+                let pivot_row = rows[rank].clone();
+                rows[r].iter_mut().zip(pivot_row).for_each(|(a, b)| *a ^= b);
+            }
+        }
+        rank += 1;
+    }
+    rank
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::Evaluator;
+    use crate::tokenizer::Tokens;
+
+    fn function(s: &str) -> BooleanFunction {
+        BooleanFunction::from_evaluator(&Evaluator::new(Tokens::from_text(s)).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn spectrum() {
+        let and = function("a and b");
+        assert_eq!(and.walsh_spectrum(), [2, 2, 2, -2]);
+        assert_eq!(and.nonlinearity(), 1);
+        assert!(!and.is_balanced());
+        assert_eq!(and.resiliency(), None);
+        //a bent function is as far as possible from the affine functions.
+        let bent = function("(a and b) xor (c and d)");
+        assert!(bent.walsh_spectrum().iter().all(|w| w.abs() == 4));
+        assert_eq!(bent.nonlinearity(), 6);
+        assert_eq!(bent.autocorrelation(), [16, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn immunity() {
+        let xor = function("xor(a, b, c)");
+        assert!(xor.is_balanced());
+        assert_eq!(xor.nonlinearity(), 0);
+        assert_eq!(xor.correlation_immunity(), 2);
+        assert_eq!(xor.resiliency(), Some(2));
+        assert_eq!(xor.algebraic_immunity(), 1);
+        let maj = function("maj(a, b, c)");
+        assert_eq!(maj.correlation_immunity(), 0);
+        assert_eq!(maj.nonlinearity(), 2);
+        assert_eq!(function("a and b").algebraic_immunity(), 1);
+        assert_eq!(function("(a and b) xor (c and d)").algebraic_immunity(), 2);
+        assert_eq!(function("a and not a").algebraic_immunity(), 0);
+        let raw = BooleanFunction::from_table(vec![false, true, true, false]).unwrap();
+        assert_eq!(raw.autocorrelation(), [4, -4, -4, 4]);
+        assert_eq!(raw.algebraic_degree(), 1);
+    }
+}